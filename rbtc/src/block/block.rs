@@ -1,10 +1,21 @@
-use crate::encode::error::Error;
+use crate::encode::error::{Error, Frame};
 use crate::encode::encode::{Encodable, Decodable};
-use crate::block::transaction::Transactions;
+use crate::block::header::BlockHeader;
+use crate::block::merkle::MerkleRoot;
+use crate::block::transaction::{Transactions, WITNESS_SCALE_FACTOR};
+use crate::utils::sha256::Sha256;
+use crate::utils::uint256::Uint256;
 
 use std::io::{Read, Write, Cursor};
 use byteorder::{LittleEndian, ReadBytesExt};
 
+/// Maximum weight of a block, BIP141
+pub const MAX_BLOCK_WEIGHT : usize = 4_000_000;
+/// Maximum signature operations cost of a block, BIP141
+pub const MAX_BLOCK_SIGOPS_COST : usize = 80_000;
+/// Maximum size of a serialized block, its witnesses included, BIP141
+pub const MAX_BLOCK_SERIALIZED_SIZE : u64 = 4_000_000;
+
 /// https://en.bitcoin.it/wiki/Block
 /// 
 /// # Block
@@ -21,7 +32,7 @@ use byteorder::{LittleEndian, ReadBytesExt};
 /// +--------------+------------------------------------------+-------+
 /// | Field        | Description                              | Bytes |
 /// +--------------+------------------------------------------+-------+
-/// | Magic no     | value always 0xD9B4BEF9                  | 4     |
+/// | Magic no     | network magic, 0xD9B4BEF9 on main        | 4     |
 /// +--------------+------------------------------------------+-------+
 /// | Blocksize    | number of bytes following up to end      | 4     |
 /// |              |  of block                                |       |
@@ -33,6 +44,9 @@ use byteorder::{LittleEndian, ReadBytesExt};
 /// | transactions | the (non empty) list of transactions     | var   |
 /// +--------------+------------------------------------------+-------+
 /// ```
+///
+/// The magic and the size frame the blocks of the blk*.dat files, `Block::parse` reads
+/// from the header on, see `BlockStore`.
 /// 
 /// ## Description
 /// 
@@ -72,13 +86,7 @@ use byteorder::{LittleEndian, ReadBytesExt};
 /// 
 #[derive(Debug)]
 pub struct Block {
-    // header
-    pub version: u32,
-    pub previous: [u8; 32],
-    pub merkleroot: [u8; 32],
-    pub time: u32,
-    pub bits: u32,
-    pub nonce: u32,
+    pub header: BlockHeader,
     pub transactions: Transactions
 }
 impl Block {
@@ -91,37 +99,161 @@ impl Block {
             return Err(Error::InvalidLength);
         }
         let mut r = Cursor::new(hex);
-        let result = Block::decode(&mut r)?;
+        let result = Block::decode(&mut r).map_err(|e| e.at(r.position()))?;
 
         if r.position() as usize != hex.len() {
             return Err(Error::RemainingContent);
         }
         Ok(result)
     }
+
+    /// Block hash, the double sha256 of the 80 bytes header.
+    pub fn hash(&self) -> Sha256 {
+        self.header.hash()
+    }
+
+    /// Checks the proof of work of the header, see `BlockHeader::check_pow`.
+    pub fn check_pow(&self, pow_limit: &Uint256) -> Result<(), Error> {
+        self.header.check_pow(pow_limit)
+    }
+
+    /// Checks the header merkle root against the transactions, rejecting the
+    /// CVE-2012-2459 duplicated transactions mutation, then checks the BIP141
+    /// witness commitment when the coinbase carries one.
+    pub fn validate_merkle(&self) -> Result<(), Error> {
+
+        trace!("validate_merkle");
+
+        let merkle = MerkleRoot::block(self);
+        if merkle.root.hash != self.header.merkleroot {
+            return Err(Error::BlockMerkleRootMismatch);
+        }
+        if merkle.mutated {
+            return Err(Error::BlockMerkleRootMutated);
+        }
+
+        let commitment = match MerkleRoot::find_witness_commitment(self) {
+            Some(commitment) => commitment,
+            None => {
+                if self.transactions.iter().any(|tx| tx.has_witness()) {
+                    return Err(Error::BlockWitnessUnexpected);
+                }
+                return Ok(());
+            }
+        };
+
+        // the coinbase witness holds the 32 bytes witness reserved value
+        let coinbase = self.transactions.get(0).ok_or(Error::BlockWitnessReservedValue)?;
+        let input = coinbase.inputs.get(0).ok_or(Error::BlockWitnessReservedValue)?;
+        if input.witness.len() != 1 || input.witness[0].data.len() != 32 {
+            return Err(Error::BlockWitnessReservedValue);
+        }
+
+        let witness = MerkleRoot::witness(self);
+        let expected = MerkleRoot::witness_commitment(&witness.root, &input.witness[0].data);
+        if expected != commitment {
+            return Err(Error::BlockWitnessCommitment);
+        }
+
+        Ok(())
+    }
+
+    /// Size of the block serialized without witness, as seen by a pre-segwit node.
+    pub fn stripped_size(&self) -> usize {
+        let mut data : Vec<u8> = Vec::new();
+        self.encode_without_witness(&mut data).unwrap();
+        data.len()
+    }
+
+    /// Size of the full serialization, with the witnesses.
+    pub fn total_size(&self) -> usize {
+        let mut data : Vec<u8> = Vec::new();
+        self.encode(&mut data).unwrap();
+        data.len()
+    }
+
+    /// Weight of the block, BIP141, stripped size * 3 + total size, see `Transaction::weight`.
+    pub fn weight(&self) -> usize {
+        self.stripped_size() * (WITNESS_SCALE_FACTOR - 1) + self.total_size()
+    }
+
+    /// Signature operations cost of the scriptSigs and scriptPubKeys of the block,
+    /// counted the legacy way, see `Transaction::legacy_sigop_count`.
+    pub fn legacy_sigop_cost(&self) -> usize {
+        self.transactions.iter().map(|tx| tx.legacy_sigop_count() * WITNESS_SCALE_FACTOR).sum()
+    }
+
+    /// Checks the limits a block can be checked against without the outputs it spends:
+    ///
+    /// ```
+    /// +--------------------+------------+-------------------------------------+
+    /// | Limit              | Maximum    | Error                               |
+    /// +--------------------+------------+-------------------------------------+
+    /// | weight             | 4 000 000  | BlockWeight                         |
+    /// +--------------------+------------+-------------------------------------+
+    /// | legacy sigops cost | 80 000     | BlockSigOps                         |
+    /// +--------------------+------------+-------------------------------------+
+    /// ```
+    ///
+    /// The P2SH and witness sigops need the spent outputs, see `Transaction::sigop_cost`,
+    /// and are counted against the same 80 000 when the block is connected, see `UtxoSet::connect_block`.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp CheckBlock
+    pub fn check_limits(&self) -> Result<(), Error> {
+
+        trace!("check_limits");
+
+        if self.weight() > MAX_BLOCK_WEIGHT {
+            return Err(Error::BlockWeight);
+        }
+        if self.legacy_sigop_cost() > MAX_BLOCK_SIGOPS_COST {
+            return Err(Error::BlockSigOps);
+        }
+        Ok(())
+    }
+
+    /// Serializes the block with every transaction in its legacy format,
+    /// as a pre-segwit node would receive it.
+    pub fn encode_without_witness<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode_without_witness");
+
+        self.header.encode(w)?;
+        self.transactions.encode_without_witness(w)?;
+
+        Ok(())
+    }
+}
+
+impl Encodable for Block {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.header.encode(w)?;
+        self.transactions.encode(w)?;
+
+        Ok(())
+    }
 }
 
 impl Decodable for Block {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Block, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Block, Error> {
 
         trace!("decode");
 
-        let version = u32::decode(r).map_err(|_| Error::BlockVersion)?;
-        let previous = <[u8; 32]>::decode(r).map_err(|_| Error::BlockPrevious)?;
-        let merkleroot = <[u8; 32]>::decode(r).map_err(|_| Error::BlockMerkleRoot)?;
-        let time = u32::decode(r).map_err(|_| Error::BlockTime)?;
-        let bits = u32::decode(r).map_err(|_| Error::BlockBits)?;
-        let nonce = u32::decode(r).map_err(|_| Error::BlockNonce)?;
-
-        let transactions = Transactions::decode(r)?;
+        // a block is not read past its maximum size
+        let mut r = r.take(MAX_BLOCK_SERIALIZED_SIZE);
+        let header = BlockHeader::decode(&mut r).map_err(|e| e.within(Frame::new("block")))?;
+        let transactions = Transactions::decode(&mut r).map_err(|e| {
+            let e = if r.limit() == 0 { Error::BlockTooLarge.caused_by(e) } else { e };
+            e.within(Frame::new("block"))
+        })?;
 
         let result = Block {
-            version: version,
-            previous: previous,
-            merkleroot: merkleroot,
-            time: time,
-            bits: bits,
-            nonce: nonce,
+            header: header,
             transactions: transactions
         };
 
@@ -133,10 +265,17 @@ impl Decodable for Block {
 mod test {
 
     use crate::utils::hexdump;
-    use crate::encode::error::Error;
+    use crate::encode::error::{Error, Frame};
     use crate::encode::encode::{Encodable, Decodable};
     use crate::block::block::Block;
-
+    use crate::block::transaction::Transactions;
+    use crate::encode::encode::test::{fuzz, noise};
+    use crate::block::check::COIN;
+    use crate::chain::chainparams::ChainParams;
+    use crate::chain::utxo::test::{block, coinbase};
+    use crate::utils::sha256::Sha256;
+
+    use std::error::Error as StdError;
     use std::io::Cursor;
 
     #[test]
@@ -147,7 +286,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 0);
@@ -167,7 +306,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 0);
@@ -188,7 +327,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 4);
@@ -210,7 +349,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 36);
@@ -234,7 +373,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 68);
@@ -258,7 +397,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 72);
@@ -282,7 +421,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 76);
@@ -306,7 +445,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_err());
         assert_eq!(c.position(), 80);
@@ -331,19 +470,19 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_ok());
         assert_eq!(c.position(), 81);
 
         let b : Block = block.unwrap();
 
-        assert_eq!(b.version, 0x00000001, "b.version");
-        assert_eq!(b.previous, [0; 32], "b.previous");
-        assert_eq!(b.merkleroot, [0; 32], "b.merkleroot");
-        assert_eq!(b.time, 0x00000000, "b.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
-        assert_eq!(b.bits, 0x00000000, "b.bits");
-        assert_eq!(b.nonce, 0x00000000, "b.nonce");
+        assert_eq!(b.header.version, 0x00000001, "b.header.version");
+        assert_eq!(b.header.previous, [0; 32], "b.header.previous");
+        assert_eq!(b.header.merkleroot, [0; 32], "b.header.merkleroot");
+        assert_eq!(b.header.time, 0x00000000, "b.header.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
+        assert_eq!(b.header.bits, 0x00000000, "b.header.bits");
+        assert_eq!(b.header.nonce, 0x00000000, "b.header.nonce");
         assert_eq!(b.transactions.len(), 0, "b.transactions.len");
 
 
@@ -362,26 +501,112 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let block = Block::decode(&mut c);
         assert!(block.is_ok());
         assert_eq!(c.position(), 81);
 
         let b : Block = block.unwrap();
 
-        assert_eq!(b.version, 0x03020100, "b.version");
-        assert_eq!(b.previous, [
+        assert_eq!(b.header.version, 0x03020100, "b.header.version");
+        assert_eq!(b.header.previous, [
             0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x00, 0x01, 0x02, 0x03,
             0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x00, 0x01, 0x02, 0x03
-        ], "b.previous");
-        assert_eq!(b.merkleroot, [
+        ], "b.header.previous");
+        assert_eq!(b.header.merkleroot, [
             0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x00, 0x01, 0x02, 0x03,
             0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x00, 0x01, 0x02, 0x03
-        ],"b.merkleroot");
-        assert_eq!(b.time, 0x07060504, "b.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
-        assert_eq!(b.bits, 0x0B0A0908, "b.bits");
-        assert_eq!(b.nonce, 0x0F0E0D0C, "b.nonce");
+        ],"b.header.merkleroot");
+        assert_eq!(b.header.time, 0x07060504, "b.header.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
+        assert_eq!(b.header.bits, 0x0B0A0908, "b.header.bits");
+        assert_eq!(b.header.nonce, 0x0F0E0D0C, "b.header.nonce");
         assert_eq!(b.transactions.len(), 0, "b.transactions.len");
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn when_decode_from_slice_reader_then_same_block() {
+
+        let genesis = ChainParams::mainnet().genesis_block();
+        let mut data = vec![];
+        genesis.encode(&mut data).unwrap();
+
+        // any reader, the slice moving forward as it is read
+        let mut r : &[u8] = &data;
+        let block = Block::decode(&mut r).unwrap();
+        assert!(r.is_empty());
+        assert_eq!(block.hash(), genesis.hash());
+
+        let mut encoded = vec![];
+        block.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, data);
+    }
+
+    /// A block of two coinbases, and the offset of the second one.
+    fn two_transactions() -> (Vec<u8>, usize) {
+        let block = block(Sha256::default(), vec![coinbase(300, 50 * COIN), coinbase(301, 50 * COIN)]);
+        let mut data = vec![];
+        block.encode(&mut data).unwrap();
+        let mut tx = vec![];
+        block.transactions.get(1).unwrap().encode(&mut tx).unwrap();
+        let offset = data.len() - tx.len();
+        (data, offset)
+    }
+
+    #[test]
+    fn when_transaction_flag_invalid_then_error_at_offset_in_tx() {
+
+        let (mut data, tx) = two_transactions();
+        // marker 0x00 followed by a flag of 0x02
+        data.splice(tx + 4..tx + 4, vec![0x00, 0x02]);
+
+        let error = Block::parse(&data).err().unwrap();
+        assert_eq!(error, Error::TransactionFlag);
+        assert_eq!(error.offset(), Some(tx as u64 + 6));
+        assert_eq!(error.path(), &[Frame::new("block"), Frame::item("tx", 1)]);
+        assert_eq!(error.to_string(), format!("TransactionFlag at byte {} in block -> tx #1", tx + 6));
+    }
+
+    #[test]
+    fn when_script_truncated_then_error_path_to_script() {
+
+        let (data, tx) = two_transactions();
+        // version, input count, outpoint, script length and 2 of the 4 bytes of the script
+        let data = data[..tx + 4 + 1 + 36 + 1 + 2].to_vec();
+
+        let error = Block::parse(&data).err().unwrap();
+        assert_eq!(error, Error::Signature);
+        assert_eq!(error.path(), &[Frame::new("block"), Frame::item("tx", 1), Frame::item("input", 0), Frame::new("script")]);
+        assert!(error.to_string().ends_with("in block -> tx #1 -> input #0 -> script"));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn when_decode_over_1000000_transactions_then_error() {
+        // 1 000 001 then 2^64 - 1 transactions
+        fuzz::<Transactions>(&[0xfe, 0x41, 0x42, 0x0f, 0x00], Error::TransactionsTooMany);
+        fuzz::<Transactions>(&[0xff; 9], Error::TransactionsTooMany);
+
+        let mut prefix = vec![0; 80];
+        prefix.extend_from_slice(&[0xfe, 0x41, 0x42, 0x0f, 0x00]);
+        fuzz::<Block>(&prefix, Error::TransactionsTooMany);
+    }
+
+    #[test]
+    fn when_decode_over_4mb_then_error() {
+
+        // a header, a transaction of a single input with a scriptSig of 4 100 000 bytes
+        let mut prefix = vec![0; 80];
+        prefix.extend_from_slice(&[0x01, 0x01, 0x00, 0x00, 0x00, 0x01]);
+        prefix.extend_from_slice(&[0; 36]);
+        prefix.extend_from_slice(&[0xfe, 0xa0, 0x8f, 0x3e, 0x00]);
+        for seed in 0..4 {
+            let mut data = prefix.clone();
+            data.extend(noise(seed, 4_100_000));
+            let mut r = &data[..];
+            assert_eq!(Block::decode(&mut r).err(), Some(Error::BlockTooLarge), "seed {}", seed);
+            // not read past the maximum size
+            assert_eq!(data.len() - r.len(), 4_000_000);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use crate::encode::error::Error;
+use crate::block::block::{Block, MAX_BLOCK_WEIGHT, MAX_BLOCK_SIGOPS_COST};
+use crate::block::script::Script;
+use crate::block::transaction::{Transaction, WITNESS_SCALE_FACTOR};
+use crate::chain::params::ConsensusParams;
+
+use std::collections::HashSet;
+
+/// Satoshis in a bitcoin
+pub const COIN : u64 = 100_000_000;
+/// Highest amount of an output, and of the outputs of a transaction, 21 million bitcoins
+pub const MAX_MONEY : u64 = 21_000_000 * COIN;
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/consensus/tx_check.cpp
+///
+/// # Check transaction
+///
+/// The rules a transaction must follow whatever the chain it is in:
+///
+/// ```
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | Rule                                | Core reject reason           | Error                         |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | at least one input                  | bad-txns-vin-empty           | TransactionInputsEmpty        |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | at least one output                 | bad-txns-vout-empty          | TransactionOutputsEmpty       |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | stripped size * 4 <= 4 000 000      | bad-txns-oversize            | TransactionOversize           |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | each output <= 21 million bitcoins  | bad-txns-vout-toolarge       | TransactionOutputValue        |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | outputs total <= 21 million         | bad-txns-txouttotal-toolarge | TransactionOutputTotal        |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | no outpoint spent twice             | bad-txns-inputs-duplicate    | TransactionInputsDuplicate    |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | coinbase scriptSig of 2 to 100      | bad-cb-length                | TransactionCoinbaseLength     |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | no null outpoint outside coinbase   | bad-txns-prevout-null        | TransactionPrevoutNull        |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/consensus/tx_check.cpp CheckTransaction
+pub fn check_transaction(transaction: &Transaction) -> Result<(), Error> {
+
+    trace!("check_transaction");
+
+    if transaction.inputs.is_empty() {
+        return Err(Error::TransactionInputsEmpty);
+    }
+    if transaction.outputs.is_empty() {
+        return Err(Error::TransactionOutputsEmpty);
+    }
+    if transaction.stripped_size() * WITNESS_SCALE_FACTOR > MAX_BLOCK_WEIGHT {
+        return Err(Error::TransactionOversize);
+    }
+
+    let mut total : u64 = 0;
+    for output in &transaction.outputs {
+        if output.amount > MAX_MONEY {
+            return Err(Error::TransactionOutputValue);
+        }
+        total += output.amount;
+        if total > MAX_MONEY {
+            return Err(Error::TransactionOutputTotal);
+        }
+    }
+
+    let mut outpoints = HashSet::with_capacity(transaction.inputs.len());
+    for input in &transaction.inputs {
+        if !outpoints.insert((input.previous.transaction_hash, input.previous.index)) {
+            return Err(Error::TransactionInputsDuplicate);
+        }
+    }
+
+    if transaction.is_coinbase() {
+        let len = transaction.inputs[0].signature.content.len();
+        if !(2..=100).contains(&len) {
+            return Err(Error::TransactionCoinbaseLength);
+        }
+    } else if transaction.inputs.iter().any(|input| input.previous.is_null()) {
+        return Err(Error::TransactionPrevoutNull);
+    }
+    Ok(())
+}
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp
+///
+/// # Check block
+///
+/// The rules a block must follow whatever its height, checked in this order:
+///
+/// ```
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | Rule                                | Core reject reason           | Error                         |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | hash below the target of the bits   | high-hash                    | PowHashAboveTarget, PowTarget |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | merkle root of the transactions     | bad-txnmrklroot              | BlockMerkleRootMismatch       |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | no duplicated transactions          | bad-txns-duplicate           | BlockMerkleRootMutated        |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | witness commitment when witnesses   | bad-witness-merkle-match     | BlockWitness...               |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | at least 1 transaction, stripped    | bad-blk-length               | BlockLength                   |
+/// | size * 4 <= 4 000 000               |                              |                               |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | first transaction is a coinbase     | bad-cb-missing               | BlockCoinbaseMissing          |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | no other coinbase                   | bad-cb-multiple              | BlockCoinbaseMultiple         |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | each transaction                    | see `check_transaction`      |                               |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | legacy sigops cost <= 80 000        | bad-blk-sigops               | BlockSigOps                   |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// ```
+///
+/// The weight of the block and the BIP34 height of its coinbase depend on the soft
+/// forks active at its height, see `check_block_height`.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp CheckBlock
+pub fn check_block(block: &Block, params: &ConsensusParams) -> Result<(), Error> {
+
+    trace!("check_block");
+
+    block.check_pow(&params.pow_limit)?;
+    block.validate_merkle()?;
+
+    let count = block.transactions.len();
+    if count == 0 || count * WITNESS_SCALE_FACTOR > MAX_BLOCK_WEIGHT
+        || block.stripped_size() * WITNESS_SCALE_FACTOR > MAX_BLOCK_WEIGHT {
+        return Err(Error::BlockLength);
+    }
+
+    let mut transactions = block.transactions.iter();
+    match transactions.next() {
+        Some(coinbase) if coinbase.is_coinbase() => {},
+        _ => return Err(Error::BlockCoinbaseMissing),
+    }
+    if transactions.any(|tx| tx.is_coinbase()) {
+        return Err(Error::BlockCoinbaseMultiple);
+    }
+
+    for tx in block.transactions.iter() {
+        check_transaction(tx)?;
+    }
+
+    if block.legacy_sigop_cost() > MAX_BLOCK_SIGOPS_COST {
+        return Err(Error::BlockSigOps);
+    }
+    Ok(())
+}
+
+/// The rules of a block depending on its height:
+///
+/// ```
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | Rule                                | Core reject reason           | Error                         |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | coinbase scriptSig starting with    | bad-cb-height                | BlockCoinbaseHeight           |
+/// | the height, from BIP34 activation   |                              |                               |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// | weight <= 4 000 000                 | bad-blk-weight               | BlockWeight                   |
+/// +-------------------------------------+------------------------------+-------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp ContextualCheckBlock
+pub fn check_block_height(block: &Block, height: u32, params: &ConsensusParams) -> Result<(), Error> {
+
+    trace!("check_block_height");
+
+    if height >= params.bip34_height {
+        let mut expected = Script::default();
+        expected.push_int(height as i64);
+        let script_sig = block.transactions.get(0)
+            .and_then(|coinbase| coinbase.inputs.first())
+            .map(|input| &input.signature.content[..])
+            .unwrap_or_default();
+        if !script_sig.starts_with(&expected.content) {
+            return Err(Error::BlockCoinbaseHeight);
+        }
+    }
+
+    if block.weight() > MAX_BLOCK_WEIGHT {
+        return Err(Error::BlockWeight);
+    }
+    Ok(())
+}
+
+/// The reason Core gives when rejecting a transaction or a block for a rule of
+/// `check_transaction`, `check_block`, `check_block_height` or `UtxoSet::connect_block`.
+pub fn reject_reason(error: &Error) -> Option<&'static str> {
+    let result = match error.kind() {
+        Error::TransactionInputsEmpty => "bad-txns-vin-empty",
+        Error::TransactionOutputsEmpty => "bad-txns-vout-empty",
+        Error::TransactionOversize => "bad-txns-oversize",
+        Error::TransactionOutputValue => "bad-txns-vout-toolarge",
+        Error::TransactionOutputTotal => "bad-txns-txouttotal-toolarge",
+        Error::TransactionInputsDuplicate => "bad-txns-inputs-duplicate",
+        Error::TransactionCoinbaseLength => "bad-cb-length",
+        Error::TransactionPrevoutNull => "bad-txns-prevout-null",
+        Error::PowHashAboveTarget => "high-hash",
+        Error::BlockMerkleRootMismatch => "bad-txnmrklroot",
+        Error::BlockMerkleRootMutated => "bad-txns-duplicate",
+        Error::BlockWitnessReservedValue => "bad-witness-nonce-size",
+        Error::BlockWitnessCommitment => "bad-witness-merkle-match",
+        Error::BlockWitnessUnexpected => "unexpected-witness",
+        Error::BlockLength => "bad-blk-length",
+        Error::BlockCoinbaseMissing => "bad-cb-missing",
+        Error::BlockCoinbaseMultiple => "bad-cb-multiple",
+        Error::BlockSigOps => "bad-blk-sigops",
+        Error::BlockCoinbaseHeight => "bad-cb-height",
+        Error::BlockWeight => "bad-blk-weight",
+        Error::UtxoMissing => "bad-txns-inputs-missingorspent",
+        Error::UtxoPrematureSpend => "bad-txns-premature-spend-of-coinbase",
+        Error::UtxoInputValues => "bad-txns-inputvalues-outofrange",
+        Error::UtxoInBelowOut => "bad-txns-in-belowout",
+        Error::UtxoCoinbaseAmount => "bad-cb-amount",
+        Error::UtxoOverwrite => "bad-txns-BIP30",
+        _ => return None,
+    };
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::block::block::Block;
+    use crate::block::check::{check_transaction, check_block, check_block_height, reject_reason, COIN, MAX_MONEY};
+    use crate::block::outpoint::OutPoint;
+    use crate::block::script::Script;
+    use crate::block::transaction::Transaction;
+    use crate::block::txin::TxIn;
+    use crate::block::txout::TxOut;
+    use crate::block::witness::Witness;
+    use crate::chain::params::ConsensusParams;
+    use crate::chain::utxo;
+    use crate::utils::sha256::Sha256;
+
+    fn input(transaction_hash: [u8; 32], index: u32, signature: Vec<u8>) -> TxIn {
+        TxIn {
+            previous: OutPoint { transaction_hash: transaction_hash, index: index },
+            signature: Script::new(signature),
+            sequence: 0xffff_ffff,
+            witness: vec![],
+        }
+    }
+
+    fn transaction(inputs: Vec<TxIn>, amounts: &[u64]) -> Transaction {
+        Transaction {
+            version: 1,
+            flag: None,
+            inputs: inputs,
+            outputs: amounts.iter().map(|amount| TxOut { amount: *amount, script_pubkey: Script::new(vec![0x51]) }).collect(),
+            locktime: 0,
+        }
+    }
+
+    /// A coinbase with `script_sig`, paying 50 bitcoins.
+    fn coinbase(script_sig: Vec<u8>) -> Transaction {
+        let mut result = utxo::test::coinbase(0, 50 * COIN);
+        result.inputs[0].signature = Script::new(script_sig);
+        result
+    }
+
+    /// A transaction spending the output 0 of the transaction `tag` repeated.
+    fn spend(tag: u8) -> Transaction {
+        transaction(vec![input([tag; 32], 0, vec![])], &[1000])
+    }
+
+    /// A regtest block of `transactions` on top of nothing, mined.
+    fn block(transactions: Vec<Transaction>) -> Block {
+        utxo::test::block(Sha256::default(), transactions)
+    }
+
+    #[test]
+    fn when_valid_transactions_then_ok() {
+        assert_eq!(check_transaction(&coinbase(vec![0x51, 0x51])), Ok(()));
+        assert_eq!(check_transaction(&spend(1)), Ok(()));
+    }
+
+    #[test]
+    fn when_no_input_then_vin_empty() {
+        let tx = transaction(vec![], &[1000]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionInputsEmpty));
+        assert_eq!(reject_reason(&Error::TransactionInputsEmpty), Some("bad-txns-vin-empty"));
+    }
+
+    #[test]
+    fn when_no_output_then_vout_empty() {
+        let tx = transaction(vec![input([1; 32], 0, vec![])], &[]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionOutputsEmpty));
+    }
+
+    #[test]
+    fn when_stripped_size_over_block_then_oversize() {
+        let tx = transaction(vec![input([1; 32], 0, vec![0x61; 1_000_000])], &[1000]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionOversize));
+    }
+
+    #[test]
+    fn when_output_over_max_money_then_vout_toolarge() {
+        assert_eq!(check_transaction(&transaction(vec![input([1; 32], 0, vec![])], &[MAX_MONEY])), Ok(()));
+        let tx = transaction(vec![input([1; 32], 0, vec![])], &[MAX_MONEY + 1]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionOutputValue));
+    }
+
+    #[test]
+    fn when_outputs_total_over_max_money_then_txouttotal_toolarge() {
+        let tx = transaction(vec![input([1; 32], 0, vec![])], &[MAX_MONEY, 1]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionOutputTotal));
+    }
+
+    // CVE-2018-17144
+    #[test]
+    fn when_outpoint_spent_twice_then_inputs_duplicate() {
+        let tx = transaction(vec![input([1; 32], 0, vec![]), input([1; 32], 1, vec![]), input([1; 32], 0, vec![])], &[1000]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionInputsDuplicate));
+    }
+
+    #[test]
+    fn when_coinbase_script_sig_not_2_to_100_then_cb_length() {
+        assert_eq!(check_transaction(&coinbase(vec![0x51])), Err(Error::TransactionCoinbaseLength));
+        assert_eq!(check_transaction(&coinbase(vec![0x51; 100])), Ok(()));
+        assert_eq!(check_transaction(&coinbase(vec![0x51; 101])), Err(Error::TransactionCoinbaseLength));
+    }
+
+    #[test]
+    fn when_null_outpoint_outside_coinbase_then_prevout_null() {
+        let tx = transaction(vec![input([1; 32], 0, vec![]), input([0; 32], 0xffff_ffff, vec![])], &[1000]);
+        assert_eq!(check_transaction(&tx), Err(Error::TransactionPrevoutNull));
+    }
+
+    #[test]
+    fn when_valid_block_then_ok() {
+        let params = ConsensusParams::regtest();
+        let block = block(vec![coinbase(vec![0x51, 0x51]), spend(1), spend(2)]);
+        assert_eq!(check_block(&block, &params), Ok(()));
+        assert_eq!(check_block_height(&block, 0, &params), Ok(()));
+    }
+
+    #[test]
+    fn when_hash_above_target_then_high_hash() {
+        let mut block = block(vec![coinbase(vec![0x51, 0x51])]);
+        block.header.bits = 0x1d00ffff;
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::PowHashAboveTarget));
+    }
+
+    #[test]
+    fn when_merkle_root_differs_then_bad_txnmrklroot() {
+        let mut block = block(vec![coinbase(vec![0x51, 0x51]), spend(1)]);
+        block.header.merkleroot = [1; 32];
+        block.header.nonce = 0;
+        while block.check_pow(&ConsensusParams::regtest().pow_limit).is_err() {
+            block.header.nonce += 1;
+        }
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockMerkleRootMismatch));
+    }
+
+    #[test]
+    fn when_transactions_duplicated_then_bad_txns_duplicate() {
+        let block = block(vec![coinbase(vec![0x51, 0x51]), spend(1), spend(2), spend(2)]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockMerkleRootMutated));
+    }
+
+    #[test]
+    fn when_witness_without_commitment_then_unexpected_witness() {
+        let mut witness = spend(1);
+        witness.inputs[0].witness = vec![Witness { data: vec![0x01] }];
+        let block = block(vec![coinbase(vec![0x51, 0x51]), witness]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockWitnessUnexpected));
+    }
+
+    #[test]
+    fn when_no_transaction_then_bad_blk_length() {
+        let block = block(vec![]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockLength));
+    }
+
+    #[test]
+    fn when_first_not_coinbase_then_cb_missing() {
+        let block = block(vec![spend(1), coinbase(vec![0x51, 0x51])]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockCoinbaseMissing));
+    }
+
+    #[test]
+    fn when_second_coinbase_then_cb_multiple() {
+        let block = block(vec![coinbase(vec![0x51, 0x51]), coinbase(vec![0x52, 0x52])]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockCoinbaseMultiple));
+    }
+
+    #[test]
+    fn when_transaction_invalid_then_block_invalid() {
+        let block = block(vec![coinbase(vec![0x51, 0x51]), transaction(vec![input([1; 32], 0, vec![])], &[])]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::TransactionOutputsEmpty));
+    }
+
+    #[test]
+    fn when_legacy_sigops_over_limit_then_bad_blk_sigops() {
+        let mut checksigs = spend(1);
+        checksigs.outputs[0].script_pubkey = Script::new(vec![0xac; 20_001]);
+        let block = block(vec![coinbase(vec![0x51, 0x51]), checksigs]);
+        assert_eq!(check_block(&block, &ConsensusParams::regtest()), Err(Error::BlockSigOps));
+    }
+
+    #[test]
+    fn when_coinbase_without_height_then_bad_cb_height() {
+
+        let params = ConsensusParams::regtest();
+        // height 1 is pushed as OP_1, 1000 as 0x02 0xe8 0x03
+        let block_1 = block(vec![coinbase(vec![0x51, 0x00])]);
+        assert_eq!(check_block_height(&block_1, 1, &params), Ok(()));
+        assert_eq!(check_block_height(&block_1, 2, &params), Err(Error::BlockCoinbaseHeight));
+        let block_1000 = block(vec![coinbase(vec![0x02, 0xe8, 0x03, 0x00])]);
+        assert_eq!(check_block_height(&block_1000, 1000, &params), Ok(()));
+        assert_eq!(check_block_height(&block_1000, 1001, &params), Err(Error::BlockCoinbaseHeight));
+
+        // not checked before BIP34
+        assert_eq!(check_block_height(&block_1000, 1001, &ConsensusParams::mainnet()), Ok(()));
+    }
+
+    #[test]
+    fn when_weight_over_limit_then_bad_blk_weight() {
+        let mut witness = spend(1);
+        witness.inputs[0].witness = vec![Witness { data: vec![0x00; 4_000_000] }];
+        let block = block(vec![coinbase(vec![0x51, 0x51]), witness]);
+        assert_eq!(check_block_height(&block, 0, &ConsensusParams::regtest()), Err(Error::BlockWeight));
+        assert_eq!(reject_reason(&Error::BlockWeight), Some("bad-blk-weight"));
+        assert_eq!(reject_reason(&Error::ScriptEvalFalse), None);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::block::pow;
+use crate::utils::sha256::Sha256;
+use crate::utils::uint256::Uint256;
+
+use std::io::{Read, Write};
+
+/// https://en.bitcoin.it/wiki/Block_hashing_algorithm
+///
+/// # Block header
+///
+/// The 80 bytes at the start of every block. The header commits to the transactions
+/// through the merkle root, and its double sha256 is the block hash.
+///
+/// ```
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// | Field Size | Description | Data type | Comments                                        |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |    4       | version     | int32_t   | Block version information                       |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |   32       | prev_block  | char[32]  | The hash value of the previous block this       |
+/// |            |             |           | particular block references                     |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |   32       | merkle_root | char[32]  | The reference to a Merkle tree collection which |
+/// |            |             |           | is a hash of all transactions                   |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |    4       | timestamp   | uint32_t  | When this block was created                     |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |    4       | bits        | uint32_t  | The calculated difficulty target                |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// |    4       | nonce       | uint32_t  | The nonce used to generate this block           |
+/// +------------+-------------+-----------+-------------------------------------------------+
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockHeader {
+    pub version: u32,
+    pub previous: [u8; 32],
+    pub merkleroot: [u8; 32],
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+
+    /// Block hash, the double sha256 of the 80 bytes header.
+    pub fn hash(&self) -> Sha256 {
+        let mut data : Vec<u8> = Vec::new();
+        // writing into a Vec<u8> cannot fail
+        self.encode(&mut data).unwrap();
+        Sha256::double(&data)
+    }
+
+    /// Target decoded from `bits`, see `pow::target`.
+    pub fn target(&self) -> Result<Uint256, Error> {
+        pow::target(self.bits)
+    }
+
+    /// Work of this header, added to the chain work of its parent.
+    pub fn work(&self) -> Uint256 {
+        pow::work(self.bits)
+    }
+
+    pub fn difficulty(&self) -> f64 {
+        pow::difficulty(self.bits)
+    }
+
+    /// Checks the header hash is at or below its target, itself at or below `pow_limit`.
+    pub fn check_pow(&self, pow_limit: &Uint256) -> Result<(), Error> {
+        pow::check_proof_of_work(&self.hash(), self.bits, pow_limit)
+    }
+}
+
+impl Encodable for BlockHeader {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.version.encode(w).map_err(|e| Error::BlockVersion.caused_by(e))?;
+        self.previous.encode(w).map_err(|e| Error::BlockPrevious.caused_by(e))?;
+        self.merkleroot.encode(w).map_err(|e| Error::BlockMerkleRoot.caused_by(e))?;
+        self.time.encode(w).map_err(|e| Error::BlockTime.caused_by(e))?;
+        self.bits.encode(w).map_err(|e| Error::BlockBits.caused_by(e))?;
+        self.nonce.encode(w).map_err(|e| Error::BlockNonce.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
+impl Decodable for BlockHeader {
+
+    fn decode<R: Read>(r: &mut R) -> Result<BlockHeader, Error> {
+
+        trace!("decode");
+
+        let version = u32::decode(r).map_err(|e| Error::BlockVersion.caused_by(e))?;
+        let previous = <[u8; 32]>::decode(r).map_err(|e| Error::BlockPrevious.caused_by(e))?;
+        let merkleroot = <[u8; 32]>::decode(r).map_err(|e| Error::BlockMerkleRoot.caused_by(e))?;
+        let time = u32::decode(r).map_err(|e| Error::BlockTime.caused_by(e))?;
+        let bits = u32::decode(r).map_err(|e| Error::BlockBits.caused_by(e))?;
+        let nonce = u32::decode(r).map_err(|e| Error::BlockNonce.caused_by(e))?;
+
+        let result = BlockHeader {
+            version: version,
+            previous: previous,
+            merkleroot: merkleroot,
+            time: time,
+            bits: bits,
+            nonce: nonce,
+        };
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::utils::hexdump;
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::block::header::BlockHeader;
+    use crate::block::pow;
+    use crate::utils::uint256::Uint256;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_decode_with_79_vec_then_fail_parse_error_blocknonce() {
+
+        let dump = "
+00000000   01 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   ver.previous.pre
+00000010   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   vious.previous.p
+00000020   00 00 00 00 3B A3 ED FD  7A 7B 12 B2 7A C7 2C 3E   rev.merkleroot.m
+00000030   67 76 8F 61 7F C8 1B C3  88 8A 51 32 3A 9F B8 AA   erkleroot.merkle
+00000040   4B 1E 5E 4A 29 AB 5F 49  FF FF 00 1D 1D AC 2B      roo.time.bits.n.
+";
+
+        let data : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&data);
+        let result = BlockHeader::decode(&mut c);
+
+        assert_eq!(result, Err(Error::BlockNonce));
+    }
+
+    #[test]
+    fn when_decode_encode_genesis_header_then_same() {
+
+        let dump = "
+00000000   01 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   ver.previous.pre
+00000010   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   vious.previous.p
+00000020   00 00 00 00 3B A3 ED FD  7A 7B 12 B2 7A C7 2C 3E   rev.merkleroot.m
+00000030   67 76 8F 61 7F C8 1B C3  88 8A 51 32 3A 9F B8 AA   erkleroot.merkle
+00000040   4B 1E 5E 4A 29 AB 5F 49  FF FF 00 1D 1D AC 2B 7C   roo.time.bits.no
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let result = BlockHeader::decode(&mut c);
+        assert!(result.is_ok());
+        assert_eq!(c.position(), 80);
+
+        let header = result.unwrap();
+        assert_eq!(header.version, 1, "header.version");
+        assert_eq!(header.time, 1231006505, "header.time");
+        assert_eq!(header.bits, 0x1D00FFFF, "header.bits");
+        assert_eq!(header.nonce, 0x7C2BAC1D, "header.nonce");
+        assert_eq!(header.hash().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f", "header.hash");
+
+        let mut encoded : Vec<u8> = Vec::new();
+        assert!(header.encode(&mut encoded).is_ok());
+        assert_eq!(original, encoded);
+
+        let limit = pow::target(0x1d00ffff).unwrap();
+        assert_eq!(header.difficulty(), 1.0, "header.difficulty");
+        assert_eq!(header.work(), Uint256::from_u64(0x100010001), "header.work");
+        assert_eq!(header.check_pow(&limit), Ok(()), "header.check_pow");
+
+        let mut tampered = header.clone();
+        tampered.nonce += 1;
+        assert_eq!(tampered.check_pow(&limit), Err(Error::PowHashAboveTarget), "tampered.check_pow");
+    }
+}
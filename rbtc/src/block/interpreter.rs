@@ -0,0 +1,1696 @@
+use crate::encode::error::Error;
+use crate::block::opcode::Opcode;
+use crate::block::script::{Script, Instructions, encode_num, decode_num};
+use crate::block::transaction::Transaction;
+use crate::block::witness::Witness;
+use crate::block::txout::TxOut;
+use crate::block::sighash::{legacy_sighash, SigHashCache, SIGHASH_DEFAULT};
+use crate::block::signature::{is_valid_der, is_low_s, is_defined_hashtype, verify_ecdsa, verify_schnorr};
+use crate::block::taproot::{tapleaf_hash, ControlBlock, TAPROOT_LEAF_TAPSCRIPT};
+use crate::encode::encode::Encodable;
+use crate::encode::varint::VarInt;
+use crate::utils::sha256::Sha256;
+
+use sha2::Digest;
+
+/// Maximum size of a pushed element
+const MAX_SCRIPT_ELEMENT_SIZE : usize = 520;
+/// Maximum number of non push opcodes in a script
+const MAX_OPS_PER_SCRIPT : usize = 201;
+/// Maximum number of public keys of a CHECKMULTISIG
+const MAX_PUBKEYS_PER_MULTISIG : i64 = 20;
+/// Maximum size of a script
+pub const MAX_SCRIPT_SIZE : usize = 10_000;
+/// Maximum number of elements on the stack and altstack
+const MAX_STACK_SIZE : usize = 1000;
+
+/// Sequence of an input opting out of its relative lock time, BIP68
+const SEQUENCE_LOCKTIME_DISABLE_FLAG : i64 = 1 << 31;
+/// Sequence relative lock time in units of 512 seconds instead of blocks, BIP68
+const SEQUENCE_LOCKTIME_TYPE_FLAG : i64 = 1 << 22;
+/// Relative lock time bits of the sequence, BIP68
+const SEQUENCE_LOCKTIME_MASK : i64 = 0x0000_ffff;
+/// Sequence of an input disabling the lock time of its transaction
+const SEQUENCE_FINAL : u32 = 0xffff_ffff;
+/// Lock times below are block heights, above are timestamps
+const LOCKTIME_THRESHOLD : i64 = 500_000_000;
+/// First byte of the annex, the last element of a taproot witness, BIP341
+const ANNEX_TAG : u8 = 0x50;
+/// Validation weight consumed by a signature check of a tapscript, BIP342
+const VALIDATION_WEIGHT_PER_SIGOP_PASSED : i64 = 50;
+/// Validation weight given to a tapscript besides the size of its witness, BIP342
+const VALIDATION_WEIGHT_OFFSET : i64 = 50;
+
+bitflags! {
+    /// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.h
+    ///
+    /// # Verification flags
+    ///
+    /// The consensus and standardness rules enabled when verifying a script. Each soft fork
+    /// adds a flag, so that the blocks before its activation are still verified without it.
+    ///
+    /// ```
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | Flag                                  | BIP   | Rule                                        |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | P2SH                                  | 16    | evaluate the redeem script of a P2SH        |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | STRICTENC                             |       | defined hash types and public key formats   |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DERSIG                                | 66    | strict DER signatures                       |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | LOW_S                                 | 62    | S of the signatures at most half the order  |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | NULLDUMMY                             | 147   | empty CHECKMULTISIG dummy element           |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | SIGPUSHONLY                           | 62    | scriptSig only pushing data                 |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | MINIMALDATA                           | 62    | smallest pushes and numbers                 |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DISCOURAGE_UPGRADABLE_NOPS            |       | fail on OP_NOP1 and OP_NOP4 to OP_NOP10     |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | CLEANSTACK                            | 62    | a single element left on the stack          |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | CHECKLOCKTIMEVERIFY                   | 65    | OP_CHECKLOCKTIMEVERIFY instead of OP_NOP2   |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | CHECKSEQUENCEVERIFY                   | 112   | OP_CHECKSEQUENCEVERIFY instead of OP_NOP3   |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | WITNESS                               | 141   | evaluate the witness programs               |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM |       | fail on unknown witness versions            |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | MINIMALIF                             |       | OP_IF argument empty or 0x01 in segwit      |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | NULLFAIL                              | 146   | failed signatures must be empty             |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | WITNESS_PUBKEYTYPE                    |       | compressed public keys in segwit            |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | CONST_SCRIPTCODE                      |       | no OP_CODESEPARATOR and signature in script |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | TAPROOT                               | 341   | evaluate the v1 witness programs            |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DISCOURAGE_UPGRADABLE_TAPROOT_VERSION |       | fail on unknown tapscript leaf versions     |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DISCOURAGE_OP_SUCCESS                 |       | fail on OP_SUCCESS in tapscripts            |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// | DISCOURAGE_UPGRADABLE_PUBKEYTYPE      |       | fail on unknown tapscript public key sizes  |
+    /// +---------------------------------------+-------+---------------------------------------------+
+    /// ```
+    ///
+    pub struct VerifyFlags : u32 {
+        const P2SH = 1 << 0;
+        const STRICTENC = 1 << 1;
+        const DERSIG = 1 << 2;
+        const LOW_S = 1 << 3;
+        const NULLDUMMY = 1 << 4;
+        const SIGPUSHONLY = 1 << 5;
+        const MINIMALDATA = 1 << 6;
+        const DISCOURAGE_UPGRADABLE_NOPS = 1 << 7;
+        const CLEANSTACK = 1 << 8;
+        const CHECKLOCKTIMEVERIFY = 1 << 9;
+        const CHECKSEQUENCEVERIFY = 1 << 10;
+        const WITNESS = 1 << 11;
+        const DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM = 1 << 12;
+        const MINIMALIF = 1 << 13;
+        const NULLFAIL = 1 << 14;
+        const WITNESS_PUBKEYTYPE = 1 << 15;
+        const CONST_SCRIPTCODE = 1 << 16;
+        const TAPROOT = 1 << 17;
+        const DISCOURAGE_UPGRADABLE_TAPROOT_VERSION = 1 << 18;
+        const DISCOURAGE_OP_SUCCESS = 1 << 19;
+        const DISCOURAGE_UPGRADABLE_PUBKEYTYPE = 1 << 20;
+    }
+}
+
+impl VerifyFlags {
+
+    /// The flags of the soft forks, enforced on the blocks of today.
+    pub fn consensus() -> VerifyFlags {
+        VerifyFlags::P2SH | VerifyFlags::DERSIG | VerifyFlags::NULLDUMMY |
+        VerifyFlags::CHECKLOCKTIMEVERIFY | VerifyFlags::CHECKSEQUENCEVERIFY |
+        VerifyFlags::WITNESS | VerifyFlags::TAPROOT
+    }
+}
+
+/// Version of the rules a script is evaluated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigVersion {
+    /// scriptSig, scriptPubKey and P2SH redeem scripts
+    Base,
+    /// P2WPKH and P2WSH witness scripts, BIP143
+    WitnessV0,
+    /// Taproot key path spends, BIP341
+    Taproot,
+    /// Taproot script path spends of leaf version 0xc0, BIP342
+    Tapscript,
+}
+
+/// What the signature of a taproot spend commits to besides the transaction, and the
+/// signature budget of a tapscript.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionData {
+    /// Last element of the witness starting with 0x50, if any, BIP341
+    pub annex: Option<Vec<u8>>,
+    /// Hash of the leaf of the tapscript being executed, BIP342
+    pub tapleaf_hash: Option<Sha256>,
+    /// Opcode position of the last executed OP_CODESEPARATOR, 0xffffffff if none, BIP342
+    pub codeseparator_position: u32,
+    /// Validation weight left to the signature checks of a tapscript, BIP342
+    pub validation_weight_left: i64,
+}
+
+impl Default for ExecutionData {
+    fn default() -> ExecutionData {
+        ExecutionData {
+            annex: None,
+            tapleaf_hash: None,
+            codeseparator_position: 0xffff_ffff,
+            validation_weight_left: 0,
+        }
+    }
+}
+
+/// Checks what the interpreter can not check alone: the signatures, needing the
+/// transaction they sign, and the lock times of the transaction.
+///
+/// Every check fails by default.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.h BaseSignatureChecker
+pub trait SignatureChecker {
+
+    /// ECDSA signature, hash type byte included, of the transaction by the public key.
+    fn check_ecdsa_signature(&self, _signature: &[u8], _pubkey: &[u8], _script_code: &Script, _sigversion: SigVersion) -> bool {
+        false
+    }
+
+    /// Schnorr signature, optional hash type byte included, of the transaction by the
+    /// x-only public key, failing with the reason.
+    fn check_schnorr_signature(&self, _signature: &[u8], _pubkey: &[u8], _sigversion: SigVersion, _execdata: &ExecutionData) -> Result<(), Error> {
+        Err(Error::ScriptSchnorrSig)
+    }
+
+    /// OP_CHECKLOCKTIMEVERIFY, the transaction is final after `locktime`.
+    fn check_locktime(&self, _locktime: i64) -> bool {
+        false
+    }
+
+    /// OP_CHECKSEQUENCEVERIFY, the input is at least `sequence` old.
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        false
+    }
+}
+
+/// Checks the signatures and the lock times against the input of a transaction.
+pub struct TransactionChecker<'a> {
+    pub transaction: &'a Transaction,
+    /// Index of the input being verified
+    pub index: usize,
+    /// Outputs spent by the inputs of the transaction, in the order of the inputs
+    pub spent_outputs: &'a [TxOut],
+    /// Hashes shared by the signatures of all the inputs
+    pub cache: &'a SigHashCache,
+}
+
+impl<'a> TransactionChecker<'a> {
+
+    pub fn new(transaction: &'a Transaction, index: usize, spent_outputs: &'a [TxOut], cache: &'a SigHashCache) -> TransactionChecker<'a> {
+        TransactionChecker {
+            transaction: transaction,
+            index: index,
+            spent_outputs: spent_outputs,
+            cache: cache,
+        }
+    }
+}
+
+impl<'a> SignatureChecker for TransactionChecker<'a> {
+
+    /// The hash type is the last byte of the signature, the rest being DER.
+    fn check_ecdsa_signature(&self, signature: &[u8], pubkey: &[u8], script_code: &Script, sigversion: SigVersion) -> bool {
+
+        let (hashtype, der) = match signature.split_last() {
+            Some((hashtype, der)) => (*hashtype as u32, der),
+            None => return false,
+        };
+        let sighash = match sigversion {
+            SigVersion::Base => legacy_sighash(self.transaction, self.index, script_code, hashtype),
+            SigVersion::WitnessV0 => {
+                let amount = match self.spent_outputs.get(self.index) {
+                    Some(output) => output.amount,
+                    None => return false,
+                };
+                match self.cache.segwit_v0_sighash(self.transaction, self.index, script_code, amount, hashtype) {
+                    Ok(sighash) => sighash,
+                    Err(_) => return false,
+                }
+            },
+            SigVersion::Taproot | SigVersion::Tapscript => return false,
+        };
+        verify_ecdsa(der, pubkey, &sighash)
+    }
+
+    /// A 64 bytes signature uses SIGHASH_DEFAULT, a 65 bytes one ends with its hash type,
+    /// which can not be SIGHASH_DEFAULT.
+    fn check_schnorr_signature(&self, signature: &[u8], pubkey: &[u8], sigversion: SigVersion, execdata: &ExecutionData) -> Result<(), Error> {
+
+        let (signature, hashtype) = match signature.len() {
+            64 => (signature, SIGHASH_DEFAULT),
+            65 if signature[64] as u32 != SIGHASH_DEFAULT => (&signature[..64], signature[64] as u32),
+            65 => return Err(Error::ScriptSchnorrSigHashType),
+            _ => return Err(Error::ScriptSchnorrSigSize),
+        };
+        let sighash = self.cache.taproot_sighash(self.transaction, self.index, self.spent_outputs, hashtype, sigversion, execdata)?;
+        if !verify_schnorr(signature, pubkey, &sighash) {
+            return Err(Error::ScriptSchnorrSig);
+        }
+        Ok(())
+    }
+
+    /// BIP65
+    fn check_locktime(&self, locktime: i64) -> bool {
+
+        let tx_locktime = self.transaction.locktime as i64;
+        // heights and timestamps can not be compared
+        if (tx_locktime < LOCKTIME_THRESHOLD) != (locktime < LOCKTIME_THRESHOLD) {
+            return false;
+        }
+        if locktime > tx_locktime {
+            return false;
+        }
+        // a final input would make the transaction final whatever its lock time
+        self.transaction.inputs[self.index].sequence != SEQUENCE_FINAL
+    }
+
+    /// BIP112
+    fn check_sequence(&self, sequence: i64) -> bool {
+
+        let tx_sequence = self.transaction.inputs[self.index].sequence as i64;
+        if (self.transaction.version as u32) < 2 {
+            return false;
+        }
+        if tx_sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+            return false;
+        }
+
+        let mask = SEQUENCE_LOCKTIME_TYPE_FLAG | SEQUENCE_LOCKTIME_MASK;
+        let tx_sequence = tx_sequence & mask;
+        let sequence = sequence & mask;
+        // blocks and time can not be compared
+        if (tx_sequence < SEQUENCE_LOCKTIME_TYPE_FLAG) != (sequence < SEQUENCE_LOCKTIME_TYPE_FLAG) {
+            return false;
+        }
+        sequence <= tx_sequence
+    }
+}
+
+/// https://en.bitcoin.it/wiki/Script
+///
+/// # Verify
+///
+/// Verifies an input spending an output: the scriptSig is evaluated, then the scriptPubKey
+/// on the stack it left. The spend is valid when the top of the stack is true.
+///
+/// With P2SH, a scriptPubKey `OP_HASH160 <hash> OP_EQUAL` also evaluates the last element
+/// pushed by the scriptSig as a script, the redeem script, on the rest of the stack.
+///
+/// With Witness, a scriptPubKey (or a redeem script) being a witness program evaluates the
+/// witness instead of the scriptSig, which has to be empty (or only push the redeem script).
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp VerifyScript
+pub fn verify_script(script_sig: &Script, script_pubkey: &Script, witness: &[Witness], flags: VerifyFlags, checker: &dyn SignatureChecker) -> Result<(), Error> {
+
+    trace!("verify_script");
+
+    if flags.contains(VerifyFlags::SIGPUSHONLY) && !script_sig.is_push_only() {
+        return Err(Error::ScriptSigPushOnly);
+    }
+
+    let mut execdata = ExecutionData::default();
+    let mut stack : Vec<Vec<u8>> = Vec::new();
+    eval_script(&mut stack, script_sig, flags, checker, SigVersion::Base, &mut execdata)?;
+    let p2sh_stack = stack.clone();
+    eval_script(&mut stack, script_pubkey, flags, checker, SigVersion::Base, &mut execdata)?;
+    if !stack.last().map(|top| cast_to_bool(top)).unwrap_or(false) {
+        return Err(Error::ScriptEvalFalse);
+    }
+
+    let mut had_witness = false;
+    if flags.contains(VerifyFlags::WITNESS) {
+        if let Some((version, program)) = script_pubkey.witness_program() {
+            had_witness = true;
+            if !script_sig.content.is_empty() {
+                return Err(Error::ScriptWitnessMalleated);
+            }
+            verify_witness_program(witness, version, program, flags, checker, false)?;
+            // the stack is not clean, only the witness stack has to be
+            stack.truncate(1);
+        }
+    }
+
+    if flags.contains(VerifyFlags::P2SH) && script_pubkey.is_p2sh() {
+
+        if !script_sig.is_push_only() {
+            return Err(Error::ScriptSigPushOnly);
+        }
+
+        // the scriptSig only pushed, the redeem script being its last push
+        stack = p2sh_stack;
+        let redeem_script = Script::new(stack.pop().ok_or(Error::ScriptEvalFalse)?);
+        eval_script(&mut stack, &redeem_script, flags, checker, SigVersion::Base, &mut execdata)?;
+        if !stack.last().map(|top| cast_to_bool(top)).unwrap_or(false) {
+            return Err(Error::ScriptEvalFalse);
+        }
+
+        if flags.contains(VerifyFlags::WITNESS) {
+            if let Some((version, program)) = redeem_script.witness_program() {
+                had_witness = true;
+                let mut expected = Script::default();
+                expected.push_data(&redeem_script.content);
+                if *script_sig != expected {
+                    return Err(Error::ScriptWitnessMalleatedP2sh);
+                }
+                verify_witness_program(witness, version, program, flags, checker, true)?;
+                stack.truncate(1);
+            }
+        }
+    }
+
+    // only soft forks on top of P2SH and Witness, the stack of their spends is not clean
+    if flags.contains(VerifyFlags::CLEANSTACK) && stack.len() != 1 {
+        return Err(Error::ScriptCleanStack);
+    }
+
+    if flags.contains(VerifyFlags::WITNESS) && !had_witness && !witness.is_empty() {
+        return Err(Error::ScriptWitnessUnexpected);
+    }
+    Ok(())
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0141.mediawiki#witness-program
+///
+/// A 20 bytes v0 program is the hash160 of a public key, spent like a P2PKH with a
+/// witness `<signature> <pubkey>`. A 32 bytes v0 program is the sha256 of the witness
+/// script, the last element of the witness, evaluated on the other elements.
+///
+/// A 32 bytes v1 program, not nested in P2SH, is a taproot output key, see `verify_taproot`.
+///
+/// Unknown versions are valid whatever the witness, left to future soft forks.
+fn verify_witness_program(witness: &[Witness], version: u8, program: &[u8], flags: VerifyFlags, checker: &dyn SignatureChecker, is_p2sh: bool) -> Result<(), Error> {
+
+    let mut stack : Vec<Vec<u8>> = witness.iter().map(|item| item.data.clone()).collect();
+    let mut execdata = ExecutionData::default();
+
+    if version == 0 {
+        let script = match program.len() {
+            32 => {
+                let script = Script::new(stack.pop().ok_or(Error::ScriptWitnessProgramWitnessEmpty)?);
+                if Sha256::single(&script.content).hash[..] != program[..] {
+                    return Err(Error::ScriptWitnessProgramMismatch);
+                }
+                script
+            },
+            20 => {
+                if stack.len() != 2 {
+                    return Err(Error::ScriptWitnessProgramMismatch);
+                }
+                let mut script = Script::default();
+                script.push_opcode(Opcode::Dup);
+                script.push_opcode(Opcode::Hash160);
+                script.push_data(program);
+                script.push_opcode(Opcode::EqualVerify);
+                script.push_opcode(Opcode::CheckSig);
+                script
+            },
+            _ => return Err(Error::ScriptWitnessProgramWrongLength),
+        };
+        return execute_witness_script(stack, &script, flags, checker, SigVersion::WitnessV0, &mut execdata);
+    }
+
+    if version == 1 && program.len() == 32 && !is_p2sh {
+        if !flags.contains(VerifyFlags::TAPROOT) {
+            return Ok(());
+        }
+        return verify_taproot(witness, stack, program, flags, checker);
+    }
+
+    if flags.contains(VerifyFlags::DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM) {
+        return Err(Error::ScriptDiscourageUpgradableWitnessProgram);
+    }
+    Ok(())
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#script-validation-rules
+///
+/// # Taproot
+///
+/// The last element of the witness starting with 0x50, when there are at least two, is
+/// the annex, signed but otherwise ignored. The witness left is then either
+///
+/// ```
+/// key path    : <signature>
+/// script path : <inputs>... <script> <control block>
+/// ```
+///
+/// The key path is a Schnorr signature by the output key. The script path reveals a leaf
+/// of the script tree committed to by the output key, the control block proving it. The
+/// script of a leaf version 0xc0 is a tapscript evaluated on the inputs, BIP342, the
+/// other versions are valid whatever the inputs, left to future soft forks.
+///
+/// The signature checks of a tapscript are limited by the size of the witness: each one
+/// consumes 50 of a budget of the witness size plus 50.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp VerifyWitnessProgram
+fn verify_taproot(witness: &[Witness], mut stack: Vec<Vec<u8>>, program: &[u8], flags: VerifyFlags, checker: &dyn SignatureChecker) -> Result<(), Error> {
+
+    if stack.is_empty() {
+        return Err(Error::ScriptWitnessProgramWitnessEmpty);
+    }
+
+    let mut execdata = ExecutionData::default();
+    if stack.len() >= 2 && stack.last().map(|last| last.first() == Some(&ANNEX_TAG)).unwrap_or(false) {
+        execdata.annex = stack.pop();
+    }
+
+    if stack.len() == 1 {
+        return checker.check_schnorr_signature(&stack[0], program, SigVersion::Taproot, &execdata);
+    }
+
+    let control = stack.pop().unwrap_or_default();
+    let script = Script::new(stack.pop().unwrap_or_default());
+    let control = ControlBlock::parse(&control)?;
+    let tapleaf_hash = tapleaf_hash(control.leaf_version, &script);
+    if !control.verify(program, &tapleaf_hash) {
+        return Err(Error::ScriptWitnessProgramMismatch);
+    }
+    execdata.tapleaf_hash = Some(tapleaf_hash);
+
+    if control.leaf_version == TAPROOT_LEAF_TAPSCRIPT {
+        execdata.validation_weight_left = witness_size(witness) as i64 + VALIDATION_WEIGHT_OFFSET;
+        return execute_witness_script(stack, &script, flags, checker, SigVersion::Tapscript, &mut execdata);
+    }
+    if flags.contains(VerifyFlags::DISCOURAGE_UPGRADABLE_TAPROOT_VERSION) {
+        return Err(Error::ScriptDiscourageUpgradableTaprootVersion);
+    }
+    Ok(())
+}
+
+/// Evaluates the script of a P2WPKH, P2WSH or tapscript on the rest of the witness,
+/// which must leave a single true element.
+///
+/// A tapscript containing an OP_SUCCESS opcode is valid without being evaluated, even
+/// when it fails to parse after it, so that any opcode can be given a meaning by a
+/// future soft fork.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp ExecuteWitnessScript
+fn execute_witness_script(mut stack: Vec<Vec<u8>>, script: &Script, flags: VerifyFlags, checker: &dyn SignatureChecker, sigversion: SigVersion, execdata: &mut ExecutionData) -> Result<(), Error> {
+
+    if sigversion == SigVersion::Tapscript {
+        for instruction in script.instructions() {
+            let instruction = instruction.map_err(|e| Error::ScriptBadOpcode.caused_by(e))?;
+            if is_op_success(instruction.opcode) {
+                if flags.contains(VerifyFlags::DISCOURAGE_OP_SUCCESS) {
+                    return Err(Error::ScriptDiscourageOpSuccess);
+                }
+                return Ok(());
+            }
+        }
+        if stack.len() > MAX_STACK_SIZE {
+            return Err(Error::ScriptStackSize);
+        }
+    }
+
+    if stack.iter().any(|item| item.len() > MAX_SCRIPT_ELEMENT_SIZE) {
+        return Err(Error::ScriptPushSize);
+    }
+    eval_script(&mut stack, script, flags, checker, sigversion, execdata)?;
+
+    // witness scripts always require a clean stack
+    if stack.len() != 1 {
+        return Err(Error::ScriptCleanStack);
+    }
+    if !cast_to_bool(&stack[0]) {
+        return Err(Error::ScriptEvalFalse);
+    }
+    Ok(())
+}
+
+/// Size of the serialized witness, its element count and elements.
+fn witness_size(witness: &[Witness]) -> usize {
+    let mut data : Vec<u8> = Vec::new();
+    VarInt::new(witness.len() as u64).encode(&mut data).unwrap();
+    for item in witness {
+        VarInt::new(item.data.len() as u64).encode(&mut data).unwrap();
+    }
+    data.len() + witness.iter().map(|item| item.data.len()).sum::<usize>()
+}
+
+/// https://en.bitcoin.it/wiki/Script
+///
+/// # Eval
+///
+/// Evaluates a script on a stack, one instruction at a time. The instructions of the
+/// branches not taken by OP_IF / OP_NOTIF / OP_ELSE are only parsed, except the
+/// disabled opcodes failing the script even there.
+///
+/// Numbers popped from the stack are little endian with a sign bit, of at most 4 bytes,
+/// but the results of the arithmetic may be 5 bytes long.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp EvalScript
+pub fn eval_script(stack: &mut Vec<Vec<u8>>, script: &Script, flags: VerifyFlags, checker: &dyn SignatureChecker, sigversion: SigVersion, execdata: &mut ExecutionData) -> Result<(), Error> {
+
+    trace!("eval_script");
+
+    // tapscripts are only limited by the size of the block and the validation weight
+    let legacy = sigversion == SigVersion::Base || sigversion == SigVersion::WitnessV0;
+    if legacy && script.content.len() > MAX_SCRIPT_SIZE {
+        return Err(Error::ScriptSize);
+    }
+
+    let require_minimal = flags.contains(VerifyFlags::MINIMALDATA);
+    let mut altstack : Vec<Vec<u8>> = Vec::new();
+    let mut conditions : Vec<bool> = Vec::new();
+    let mut op_count = 0;
+    // the signatures sign the script from the last OP_CODESEPARATOR
+    let mut code_separator = 0;
+
+    let mut instructions = script.instructions();
+    let mut opcode_position : u32 = 0;
+    while let Some(instruction) = instructions.next() {
+
+        let instruction = instruction.map_err(|e| Error::ScriptBadOpcode.caused_by(e))?;
+        let opcode = instruction.opcode;
+        let executed = conditions.iter().all(|condition| *condition);
+
+        if instruction.data.len() > MAX_SCRIPT_ELEMENT_SIZE {
+            return Err(Error::ScriptPushSize);
+        }
+        if legacy && opcode.to_u8() > Opcode::Op16.to_u8() {
+            op_count += 1;
+            if op_count > MAX_OPS_PER_SCRIPT {
+                return Err(Error::ScriptOpCount);
+            }
+        }
+        if is_disabled(opcode) {
+            return Err(Error::ScriptDisabledOpcode);
+        }
+        if opcode == Opcode::CodeSeparator && sigversion == SigVersion::Base && flags.contains(VerifyFlags::CONST_SCRIPTCODE) {
+            return Err(Error::ScriptOpCodeSeparator);
+        }
+
+        if opcode.is_push() {
+            if executed {
+                if require_minimal && !is_minimal_push(opcode, instruction.data) {
+                    return Err(Error::ScriptMinimalData);
+                }
+                stack.push(instruction.data.to_vec());
+            }
+        } else if executed || is_conditional(opcode) {
+            match opcode {
+
+                Opcode::Op1Negate | Opcode::Op1 | Opcode::Op2 | Opcode::Op3 | Opcode::Op4 |
+                Opcode::Op5 | Opcode::Op6 | Opcode::Op7 | Opcode::Op8 | Opcode::Op9 |
+                Opcode::Op10 | Opcode::Op11 | Opcode::Op12 | Opcode::Op13 | Opcode::Op14 |
+                Opcode::Op15 | Opcode::Op16 => {
+                    stack.push(encode_num(opcode.small_int().unwrap_or(0)));
+                },
+
+                Opcode::Nop => {},
+
+                // OP_NOP2 before BIP65
+                Opcode::CheckLockTimeVerify => {
+                    if flags.contains(VerifyFlags::CHECKLOCKTIMEVERIFY) {
+                        // lock times go up to 2^32 - 1, needing 5 bytes
+                        let locktime = read_num(top(stack, 1)?, require_minimal, 5)?;
+                        if locktime < 0 {
+                            return Err(Error::ScriptNegativeLockTime);
+                        }
+                        if !checker.check_locktime(locktime) {
+                            return Err(Error::ScriptUnsatisfiedLockTime);
+                        }
+                    }
+                },
+
+                // OP_NOP3 before BIP112
+                Opcode::CheckSequenceVerify => {
+                    if flags.contains(VerifyFlags::CHECKSEQUENCEVERIFY) {
+                        let sequence = read_num(top(stack, 1)?, require_minimal, 5)?;
+                        if sequence < 0 {
+                            return Err(Error::ScriptNegativeLockTime);
+                        }
+                        // the disable flag keeps the opcode a NOP for future soft forks
+                        if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG == 0 && !checker.check_sequence(sequence) {
+                            return Err(Error::ScriptUnsatisfiedLockTime);
+                        }
+                    }
+                },
+
+                Opcode::Nop1 | Opcode::Nop4 | Opcode::Nop5 | Opcode::Nop6 | Opcode::Nop7 |
+                Opcode::Nop8 | Opcode::Nop9 | Opcode::Nop10 => {
+                    if flags.contains(VerifyFlags::DISCOURAGE_UPGRADABLE_NOPS) {
+                        return Err(Error::ScriptDiscourageUpgradableNops);
+                    }
+                },
+
+                Opcode::If | Opcode::NotIf => {
+                    let mut value = false;
+                    if executed {
+                        let condition = stack.pop().ok_or(Error::ScriptInvalidStackOperation)?;
+                        let minimal = condition.len() <= 1 && condition.first().map(|b| *b == 1).unwrap_or(true);
+                        if sigversion == SigVersion::Tapscript && !minimal {
+                            return Err(Error::ScriptTapscriptMinimalIf);
+                        }
+                        if sigversion == SigVersion::WitnessV0 && flags.contains(VerifyFlags::MINIMALIF) && !minimal {
+                            return Err(Error::ScriptMinimalIf);
+                        }
+                        value = cast_to_bool(&condition) == (opcode == Opcode::If);
+                    }
+                    conditions.push(value);
+                },
+
+                Opcode::Else => {
+                    let last = conditions.last_mut().ok_or(Error::ScriptUnbalancedConditional)?;
+                    *last = !*last;
+                },
+
+                Opcode::EndIf => {
+                    conditions.pop().ok_or(Error::ScriptUnbalancedConditional)?;
+                },
+
+                Opcode::Verify => {
+                    if !cast_to_bool(top(stack, 1)?) {
+                        return Err(Error::ScriptVerify);
+                    }
+                    stack.pop();
+                },
+
+                Opcode::Return => return Err(Error::ScriptOpReturn),
+
+                Opcode::ToAltStack => {
+                    let value = stack.pop().ok_or(Error::ScriptInvalidStackOperation)?;
+                    altstack.push(value);
+                },
+
+                Opcode::FromAltStack => {
+                    let value = altstack.pop().ok_or(Error::ScriptInvalidAltStackOperation)?;
+                    stack.push(value);
+                },
+
+                Opcode::Drop2 => {
+                    check_len(stack, 2)?;
+                    stack.truncate(stack.len() - 2);
+                },
+
+                Opcode::Dup2 | Opcode::Dup3 => {
+                    let count = if opcode == Opcode::Dup2 { 2 } else { 3 };
+                    check_len(stack, count)?;
+                    let start = stack.len() - count;
+                    stack.extend_from_within(start..);
+                },
+
+                Opcode::Over2 => {
+                    check_len(stack, 4)?;
+                    let start = stack.len() - 4;
+                    stack.extend_from_within(start..start + 2);
+                },
+
+                Opcode::Rot2 => {
+                    check_len(stack, 6)?;
+                    let start = stack.len() - 6;
+                    let moved : Vec<Vec<u8>> = stack.drain(start..start + 2).collect();
+                    stack.extend(moved);
+                },
+
+                Opcode::Swap2 => {
+                    check_len(stack, 4)?;
+                    let len = stack.len();
+                    stack.swap(len - 4, len - 2);
+                    stack.swap(len - 3, len - 1);
+                },
+
+                Opcode::IfDup => {
+                    let value = top(stack, 1)?;
+                    if cast_to_bool(value) {
+                        let value = value.clone();
+                        stack.push(value);
+                    }
+                },
+
+                Opcode::Depth => {
+                    let depth = stack.len() as i64;
+                    stack.push(encode_num(depth));
+                },
+
+                Opcode::Drop => {
+                    stack.pop().ok_or(Error::ScriptInvalidStackOperation)?;
+                },
+
+                Opcode::Dup => {
+                    let value = top(stack, 1)?.clone();
+                    stack.push(value);
+                },
+
+                Opcode::Nip => {
+                    check_len(stack, 2)?;
+                    let len = stack.len();
+                    stack.remove(len - 2);
+                },
+
+                Opcode::Over => {
+                    let value = top(stack, 2)?.clone();
+                    stack.push(value);
+                },
+
+                Opcode::Pick | Opcode::Roll => {
+                    check_len(stack, 2)?;
+                    let n = read_num(&stack.pop().unwrap_or_default(), require_minimal, 4)?;
+                    if n < 0 || n as usize >= stack.len() {
+                        return Err(Error::ScriptInvalidStackOperation);
+                    }
+                    let index = stack.len() - 1 - n as usize;
+                    let value = if opcode == Opcode::Roll { stack.remove(index) } else { stack[index].clone() };
+                    stack.push(value);
+                },
+
+                Opcode::Rot => {
+                    check_len(stack, 3)?;
+                    let len = stack.len();
+                    stack.swap(len - 3, len - 2);
+                    stack.swap(len - 2, len - 1);
+                },
+
+                Opcode::Swap => {
+                    check_len(stack, 2)?;
+                    let len = stack.len();
+                    stack.swap(len - 2, len - 1);
+                },
+
+                Opcode::Tuck => {
+                    check_len(stack, 2)?;
+                    let value = top(stack, 1)?.clone();
+                    let len = stack.len();
+                    stack.insert(len - 2, value);
+                },
+
+                Opcode::Size => {
+                    let size = top(stack, 1)?.len() as i64;
+                    stack.push(encode_num(size));
+                },
+
+                Opcode::Equal | Opcode::EqualVerify => {
+                    check_len(stack, 2)?;
+                    let b = stack.pop().unwrap_or_default();
+                    let a = stack.pop().unwrap_or_default();
+                    let equal = a == b;
+                    if opcode == Opcode::EqualVerify {
+                        if !equal {
+                            return Err(Error::ScriptEqualVerify);
+                        }
+                    } else {
+                        stack.push(encode_bool(equal));
+                    }
+                },
+
+                Opcode::Add1 | Opcode::Sub1 | Opcode::Negate | Opcode::Abs | Opcode::Not | Opcode::NotEqual0 => {
+                    let a = read_num(top(stack, 1)?, require_minimal, 4)?;
+                    let result = match opcode {
+                        Opcode::Add1 => a + 1,
+                        Opcode::Sub1 => a - 1,
+                        Opcode::Negate => -a,
+                        Opcode::Abs => a.abs(),
+                        Opcode::Not => (a == 0) as i64,
+                        _ => (a != 0) as i64,
+                    };
+                    stack.pop();
+                    stack.push(encode_num(result));
+                },
+
+                Opcode::Add | Opcode::Sub | Opcode::BoolAnd | Opcode::BoolOr |
+                Opcode::NumEqual | Opcode::NumEqualVerify | Opcode::NumNotEqual |
+                Opcode::LessThan | Opcode::GreaterThan | Opcode::LessThanOrEqual | Opcode::GreaterThanOrEqual |
+                Opcode::Min | Opcode::Max => {
+                    check_len(stack, 2)?;
+                    let a = read_num(top(stack, 2)?, require_minimal, 4)?;
+                    let b = read_num(top(stack, 1)?, require_minimal, 4)?;
+                    let result = match opcode {
+                        Opcode::Add => a + b,
+                        Opcode::Sub => a - b,
+                        Opcode::BoolAnd => (a != 0 && b != 0) as i64,
+                        Opcode::BoolOr => (a != 0 || b != 0) as i64,
+                        Opcode::NumEqual | Opcode::NumEqualVerify => (a == b) as i64,
+                        Opcode::NumNotEqual => (a != b) as i64,
+                        Opcode::LessThan => (a < b) as i64,
+                        Opcode::GreaterThan => (a > b) as i64,
+                        Opcode::LessThanOrEqual => (a <= b) as i64,
+                        Opcode::GreaterThanOrEqual => (a >= b) as i64,
+                        Opcode::Min => a.min(b),
+                        _ => a.max(b),
+                    };
+                    stack.truncate(stack.len() - 2);
+                    if opcode == Opcode::NumEqualVerify {
+                        if result == 0 {
+                            return Err(Error::ScriptNumEqualVerify);
+                        }
+                    } else {
+                        stack.push(encode_num(result));
+                    }
+                },
+
+                Opcode::Within => {
+                    check_len(stack, 3)?;
+                    let x = read_num(top(stack, 3)?, require_minimal, 4)?;
+                    let min = read_num(top(stack, 2)?, require_minimal, 4)?;
+                    let max = read_num(top(stack, 1)?, require_minimal, 4)?;
+                    stack.truncate(stack.len() - 3);
+                    stack.push(encode_bool(min <= x && x < max));
+                },
+
+                Opcode::Ripemd160 | Opcode::Sha1 | Opcode::Sha256 | Opcode::Hash160 | Opcode::Hash256 => {
+                    let value = stack.pop().ok_or(Error::ScriptInvalidStackOperation)?;
+                    let hash = match opcode {
+                        Opcode::Ripemd160 => ripemd160::Ripemd160::digest(&value).to_vec(),
+                        Opcode::Sha1 => sha1::Sha1::digest(&value).to_vec(),
+                        Opcode::Sha256 => Sha256::single(&value).hash.to_vec(),
+                        Opcode::Hash160 => ripemd160::Ripemd160::digest(&Sha256::single(&value).hash).to_vec(),
+                        _ => Sha256::double(&value).hash.to_vec(),
+                    };
+                    stack.push(hash);
+                },
+
+                Opcode::CodeSeparator => {
+                    code_separator = instructions.position();
+                    execdata.codeseparator_position = opcode_position;
+                },
+
+                Opcode::CheckSig | Opcode::CheckSigVerify if sigversion == SigVersion::Tapscript => {
+                    check_len(stack, 2)?;
+                    let pubkey = stack.pop().unwrap_or_default();
+                    let signature = stack.pop().unwrap_or_default();
+                    let success = check_tapscript_signature(&signature, &pubkey, flags, checker, execdata)?;
+                    if opcode == Opcode::CheckSigVerify {
+                        if !success {
+                            return Err(Error::ScriptCheckSigVerify);
+                        }
+                    } else {
+                        stack.push(encode_bool(success));
+                    }
+                },
+
+                // <signature> <n> <pubkey> OP_CHECKSIGADD, n + 1 if the signature is valid, BIP342
+                Opcode::CheckSigAdd if sigversion == SigVersion::Tapscript => {
+                    check_len(stack, 3)?;
+                    let pubkey = stack.pop().unwrap_or_default();
+                    let n = read_num(&stack.pop().unwrap_or_default(), require_minimal, 4)?;
+                    let signature = stack.pop().unwrap_or_default();
+                    let success = check_tapscript_signature(&signature, &pubkey, flags, checker, execdata)?;
+                    stack.push(encode_num(n + success as i64));
+                },
+
+                Opcode::CheckSig | Opcode::CheckSigVerify => {
+                    check_len(stack, 2)?;
+                    let pubkey = stack.pop().unwrap_or_default();
+                    let signature = stack.pop().unwrap_or_default();
+
+                    let mut script_code = Script::new(script.content[code_separator..].to_vec());
+                    if sigversion == SigVersion::Base {
+                        // a signature can not sign itself
+                        let found = find_and_delete(&mut script_code, &signature);
+                        if found && flags.contains(VerifyFlags::CONST_SCRIPTCODE) {
+                            return Err(Error::ScriptSigFindAndDelete);
+                        }
+                    }
+
+                    check_signature_encoding(&signature, flags)?;
+                    check_pubkey_encoding(&pubkey, flags, sigversion)?;
+                    let success = checker.check_ecdsa_signature(&signature, &pubkey, &script_code, sigversion);
+                    if !success && flags.contains(VerifyFlags::NULLFAIL) && !signature.is_empty() {
+                        return Err(Error::ScriptNullFail);
+                    }
+
+                    if opcode == Opcode::CheckSigVerify {
+                        if !success {
+                            return Err(Error::ScriptCheckSigVerify);
+                        }
+                    } else {
+                        stack.push(encode_bool(success));
+                    }
+                },
+
+                // replaced by OP_CHECKSIGADD, batch verifiable
+                Opcode::CheckMultiSig | Opcode::CheckMultiSigVerify if sigversion == SigVersion::Tapscript => {
+                    return Err(Error::ScriptTapscriptCheckMultiSig);
+                },
+
+                Opcode::CheckMultiSig | Opcode::CheckMultiSigVerify => {
+                    op_count = check_multisig(stack, &script.content[code_separator..], flags, checker, sigversion, op_count)?;
+                    let success = cast_to_bool(top(stack, 1)?);
+                    if opcode == Opcode::CheckMultiSigVerify {
+                        if !success {
+                            return Err(Error::ScriptCheckMultiSigVerify);
+                        }
+                        stack.pop();
+                    }
+                },
+
+                _ => return Err(Error::ScriptBadOpcode),
+            }
+        }
+
+        if stack.len() + altstack.len() > MAX_STACK_SIZE {
+            return Err(Error::ScriptStackSize);
+        }
+        opcode_position += 1;
+    }
+
+    if !conditions.is_empty() {
+        return Err(Error::ScriptUnbalancedConditional);
+    }
+    Ok(())
+}
+
+/// `<dummy> <sig 1> ... <sig m> <m> <pubkey 1> ... <pubkey n> <n>`
+///
+/// The signatures are matched with the public keys in order, a key failing to match a
+/// signature being skipped, and the opcode fails once the signatures left outnumber the
+/// keys left. An extra element is popped, the famous off-by-one of the first client.
+///
+/// Pushes the result and returns the opcode count, increased by the number of keys.
+fn check_multisig(stack: &mut Vec<Vec<u8>>, code: &[u8], flags: VerifyFlags, checker: &dyn SignatureChecker, sigversion: SigVersion, op_count: usize) -> Result<usize, Error> {
+
+    let require_minimal = flags.contains(VerifyFlags::MINIMALDATA);
+
+    let mut i = 1;
+    let key_count = read_num(top(stack, i)?, require_minimal, 4)?;
+    if !(0..=MAX_PUBKEYS_PER_MULTISIG).contains(&key_count) {
+        return Err(Error::ScriptPubKeyCount);
+    }
+    let mut key_count = key_count as usize;
+    let op_count = op_count + key_count;
+    if op_count > MAX_OPS_PER_SCRIPT {
+        return Err(Error::ScriptOpCount);
+    }
+    i += 1;
+    let mut key_index = i;
+    // keys left to check for NULLFAIL, plus the key count itself
+    let mut nullfail_keys = key_count + 2;
+    i += key_count;
+
+    let sig_count = read_num(top(stack, i)?, require_minimal, 4)?;
+    if sig_count < 0 || sig_count as usize > key_count {
+        return Err(Error::ScriptSigCount);
+    }
+    let mut sig_count = sig_count as usize;
+    i += 1;
+    let mut sig_index = i;
+    i += sig_count;
+    check_len(stack, i)?;
+
+    let mut script_code = Script::new(code.to_vec());
+    if sigversion == SigVersion::Base {
+        for k in 0..sig_count {
+            let found = find_and_delete(&mut script_code, &stack[stack.len() - sig_index - k]);
+            if found && flags.contains(VerifyFlags::CONST_SCRIPTCODE) {
+                return Err(Error::ScriptSigFindAndDelete);
+            }
+        }
+    }
+
+    let mut success = true;
+    while success && sig_count > 0 {
+        let signature = &stack[stack.len() - sig_index];
+        let pubkey = &stack[stack.len() - key_index];
+
+        // the encodings are checked as the keys are tried, not upfront
+        check_signature_encoding(signature, flags)?;
+        check_pubkey_encoding(pubkey, flags, sigversion)?;
+        if checker.check_ecdsa_signature(signature, pubkey, &script_code, sigversion) {
+            sig_index += 1;
+            sig_count -= 1;
+        }
+        key_index += 1;
+        key_count -= 1;
+
+        if sig_count > key_count {
+            success = false;
+        }
+    }
+
+    // pops the signatures, the keys and the counts
+    while i > 1 {
+        i -= 1;
+        let value = stack.pop().unwrap_or_default();
+        if !success && flags.contains(VerifyFlags::NULLFAIL) && nullfail_keys == 0 && !value.is_empty() {
+            return Err(Error::ScriptNullFail);
+        }
+        nullfail_keys = nullfail_keys.saturating_sub(1);
+    }
+
+    // the extra element, BIP147
+    let dummy = stack.pop().ok_or(Error::ScriptInvalidStackOperation)?;
+    if flags.contains(VerifyFlags::NULLDUMMY) && !dummy.is_empty() {
+        return Err(Error::ScriptSigNullDummy);
+    }
+
+    stack.push(encode_bool(success));
+    Ok(op_count)
+}
+
+/// A signature check of a tapscript: an empty signature fails without failing the
+/// script, any other must be valid. Each non empty signature consumes validation weight.
+///
+/// Public keys other than 32 bytes are unknown key types, valid whatever the signature,
+/// left to future soft forks.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp EvalChecksigTapscript
+fn check_tapscript_signature(signature: &[u8], pubkey: &[u8], flags: VerifyFlags, checker: &dyn SignatureChecker, execdata: &mut ExecutionData) -> Result<bool, Error> {
+
+    let success = !signature.is_empty();
+    if success {
+        execdata.validation_weight_left -= VALIDATION_WEIGHT_PER_SIGOP_PASSED;
+        if execdata.validation_weight_left < 0 {
+            return Err(Error::ScriptTapscriptValidationWeight);
+        }
+    }
+
+    match pubkey.len() {
+        0 => return Err(Error::ScriptTapscriptEmptyPubKey),
+        32 => {
+            if success {
+                checker.check_schnorr_signature(signature, pubkey, SigVersion::Tapscript, execdata)?;
+            }
+        },
+        _ => {
+            if flags.contains(VerifyFlags::DISCOURAGE_UPGRADABLE_PUBKEYTYPE) {
+                return Err(Error::ScriptDiscourageUpgradablePubKeyType);
+            }
+        },
+    }
+    Ok(success)
+}
+
+/// OP_RESERVED, OP_VER, the disabled opcodes, OP_RESERVED1, OP_RESERVED2, and the ones not
+/// assigned, making a tapscript valid whatever its inputs, BIP342.
+fn is_op_success(opcode: Opcode) -> bool {
+    matches!(opcode.to_u8(), 80 | 98 | 126..=129 | 131..=134 | 137..=138 | 141..=142 | 149..=153 | 187..=254)
+}
+
+/// OP_CAT, OP_SUBSTR, OP_LEFT, OP_RIGHT, OP_INVERT, OP_AND, OP_OR, OP_XOR,
+/// OP_2MUL, OP_2DIV, OP_MUL, OP_DIV, OP_MOD, OP_LSHIFT and OP_RSHIFT, disabled in 2010.
+fn is_disabled(opcode: Opcode) -> bool {
+    matches!(opcode,
+        Opcode::Cat | Opcode::Substr | Opcode::Left | Opcode::Right |
+        Opcode::Invert | Opcode::And | Opcode::Or | Opcode::Xor |
+        Opcode::Mul2 | Opcode::Div2 | Opcode::Mul | Opcode::Div | Opcode::Mod |
+        Opcode::LShift | Opcode::RShift)
+}
+
+/// OP_IF to OP_ENDIF, evaluated in the branches not taken too.
+fn is_conditional(opcode: Opcode) -> bool {
+    (Opcode::If.to_u8()..=Opcode::EndIf.to_u8()).contains(&opcode.to_u8())
+}
+
+fn check_len(stack: &[Vec<u8>], len: usize) -> Result<(), Error> {
+    if stack.len() < len {
+        return Err(Error::ScriptInvalidStackOperation);
+    }
+    Ok(())
+}
+
+/// The `depth`th element from the top of the stack, 1 being the top.
+fn top(stack: &[Vec<u8>], depth: usize) -> Result<&Vec<u8>, Error> {
+    check_len(stack, depth)?;
+    Ok(&stack[stack.len() - depth])
+}
+
+/// False is an empty array, or zeroes with an optional sign bit (negative zero).
+pub fn cast_to_bool(value: &[u8]) -> bool {
+    for (i, byte) in value.iter().enumerate() {
+        if *byte != 0 {
+            return !(i == value.len() - 1 && *byte == 0x80);
+        }
+    }
+    false
+}
+
+fn encode_bool(value: bool) -> Vec<u8> {
+    if value { vec![1] } else { vec![] }
+}
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/script/script.h CScriptNum
+///
+/// Reads a number of at most `max_len` bytes. With MINIMALDATA it must not have
+/// extra zero bytes, the sign bit aside.
+pub fn read_num(data: &[u8], require_minimal: bool, max_len: usize) -> Result<i64, Error> {
+
+    if data.len() > max_len {
+        return Err(Error::ScriptNum);
+    }
+    if require_minimal {
+        if let Some(last) = data.last() {
+            // a last byte of only the sign bit is needed when the one before uses its highest bit
+            if last & 0x7f == 0 && (data.len() <= 1 || data[data.len() - 2] & 0x80 == 0) {
+                return Err(Error::ScriptNum);
+            }
+        }
+    }
+    Ok(decode_num(data))
+}
+
+/// The push is the one `Script::push_data` and `Script::push_int` would use.
+fn is_minimal_push(opcode: Opcode, data: &[u8]) -> bool {
+    match data.len() {
+        0 => opcode == Opcode::Op0,
+        // pushed with OP_1 to OP_16 and OP_1NEGATE, never a push opcode
+        1 if (1..=16).contains(&data[0]) || data[0] == 0x81 => false,
+        len if len <= 75 => opcode == Opcode::PushBytes(len as u8),
+        len if len <= 0xff => opcode == Opcode::PushData1,
+        len if len <= 0xffff => opcode == Opcode::PushData2,
+        _ => true,
+    }
+}
+
+/// Removes the pushes of `signature` found at instruction boundaries of the script.
+fn find_and_delete(script: &mut Script, signature: &[u8]) -> bool {
+
+    let mut pattern = Script::default();
+    pattern.push_data(signature);
+    let pattern = &pattern.content;
+
+    let content = &script.content;
+    let mut result = Vec::with_capacity(content.len());
+    let mut found = false;
+    let mut start = 0;
+    let mut position = 0;
+    loop {
+        result.extend_from_slice(&content[start..position]);
+        while content[position..].starts_with(pattern) {
+            position += pattern.len();
+            found = true;
+        }
+        start = position;
+
+        // moves to the next instruction boundary
+        let mut instructions = Instructions::new(&content[position..]);
+        match instructions.next() {
+            Some(Ok(_)) => position += instructions.position(),
+            _ => break,
+        }
+    }
+
+    if found {
+        result.extend_from_slice(&content[start..]);
+        script.content = result;
+    }
+    found
+}
+
+/// An empty signature is always well encoded, it fails the check without failing the script.
+fn check_signature_encoding(signature: &[u8], flags: VerifyFlags) -> Result<(), Error> {
+
+    if signature.is_empty() {
+        return Ok(());
+    }
+    if flags.intersects(VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC) && !is_valid_der(signature) {
+        return Err(Error::ScriptSigDer);
+    }
+    if flags.contains(VerifyFlags::LOW_S) && !is_low_s(signature) {
+        return Err(Error::ScriptSigHighS);
+    }
+    if flags.contains(VerifyFlags::STRICTENC) && !is_defined_hashtype(signature) {
+        return Err(Error::ScriptSigHashType);
+    }
+    Ok(())
+}
+
+/// 33 bytes compressed keys starting with 0x02 or 0x03, 65 bytes uncompressed ones with 0x04.
+fn check_pubkey_encoding(pubkey: &[u8], flags: VerifyFlags, sigversion: SigVersion) -> Result<(), Error> {
+
+    let compressed = pubkey.len() == 33 && (pubkey[0] == 0x02 || pubkey[0] == 0x03);
+    let uncompressed = pubkey.len() == 65 && pubkey[0] == 0x04;
+    if flags.contains(VerifyFlags::STRICTENC) && !compressed && !uncompressed {
+        return Err(Error::ScriptPubKeyType);
+    }
+    if flags.contains(VerifyFlags::WITNESS_PUBKEYTYPE) && sigversion == SigVersion::WitnessV0 && !compressed {
+        return Err(Error::ScriptWitnessPubKeyType);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use crate::encode::error::Error;
+    use crate::block::interpreter::{verify_script, eval_script, read_num, cast_to_bool, find_and_delete};
+    use crate::block::interpreter::{VerifyFlags, SigVersion, ExecutionData, SignatureChecker, TransactionChecker, ANNEX_TAG};
+    use crate::block::opcode::Opcode;
+    use crate::block::outpoint::OutPoint;
+    use crate::block::script::Script;
+    use crate::block::sighash::SigHashCache;
+    use crate::block::taproot::{tapleaf_hash, tweak_key, TAPROOT_LEAF_TAPSCRIPT};
+    use crate::block::transaction::Transaction;
+    use crate::block::txin::TxIn;
+    use crate::block::txout::TxOut;
+    use crate::block::witness::Witness;
+
+    use serde_json::Value;
+
+    /// https://github.com/bitcoin/bitcoin/blob/master/src/core_io.cpp ParseScript
+    ///
+    /// Numbers are pushed as numbers, 0x prefixed hex is copied as is, quoted
+    /// strings are pushed as data and the others are opcode names.
+    pub fn parse_script(text: &str) -> Script {
+
+        let mut result = Script::default();
+        for word in text.split_whitespace() {
+            let digits = word.strip_prefix('-').unwrap_or(word);
+            if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                result.push_int(word.parse::<i64>().unwrap());
+            } else if let Some(hex) = word.strip_prefix("0x") {
+                result.content.extend(hex::decode(hex).unwrap());
+            } else if word.len() >= 2 && word.starts_with('\'') && word.ends_with('\'') {
+                result.push_data(&word.as_bytes()[1..word.len() - 1]);
+            } else {
+                result.push_opcode(Opcode::from_name(word).unwrap_or_else(|| panic!("unknown opcode {}", word)));
+            }
+        }
+        result
+    }
+
+    pub fn parse_flags(text: &str) -> VerifyFlags {
+
+        let mut result = VerifyFlags::empty();
+        for name in text.split(',').filter(|name| !name.is_empty()) {
+            result |= match name {
+                "P2SH" => VerifyFlags::P2SH,
+                "STRICTENC" => VerifyFlags::STRICTENC,
+                "DERSIG" => VerifyFlags::DERSIG,
+                "LOW_S" => VerifyFlags::LOW_S,
+                "NULLDUMMY" => VerifyFlags::NULLDUMMY,
+                "SIGPUSHONLY" => VerifyFlags::SIGPUSHONLY,
+                "MINIMALDATA" => VerifyFlags::MINIMALDATA,
+                "DISCOURAGE_UPGRADABLE_NOPS" => VerifyFlags::DISCOURAGE_UPGRADABLE_NOPS,
+                "CLEANSTACK" => VerifyFlags::CLEANSTACK,
+                "CHECKLOCKTIMEVERIFY" => VerifyFlags::CHECKLOCKTIMEVERIFY,
+                "CHECKSEQUENCEVERIFY" => VerifyFlags::CHECKSEQUENCEVERIFY,
+                "WITNESS" => VerifyFlags::WITNESS,
+                "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM" => VerifyFlags::DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM,
+                "MINIMALIF" => VerifyFlags::MINIMALIF,
+                "NULLFAIL" => VerifyFlags::NULLFAIL,
+                "WITNESS_PUBKEYTYPE" => VerifyFlags::WITNESS_PUBKEYTYPE,
+                "CONST_SCRIPTCODE" => VerifyFlags::CONST_SCRIPTCODE,
+                "TAPROOT" => VerifyFlags::TAPROOT,
+                "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION" => VerifyFlags::DISCOURAGE_UPGRADABLE_TAPROOT_VERSION,
+                "DISCOURAGE_OP_SUCCESS" => VerifyFlags::DISCOURAGE_OP_SUCCESS,
+                "DISCOURAGE_UPGRADABLE_PUBKEYTYPE" => VerifyFlags::DISCOURAGE_UPGRADABLE_PUBKEYTYPE,
+                _ => panic!("unknown flag {}", name),
+            };
+        }
+        result
+    }
+
+    fn parse_error(text: &str) -> Result<(), Error> {
+        let error = match text {
+            "OK" => return Ok(()),
+            "EVAL_FALSE" => Error::ScriptEvalFalse,
+            "OP_RETURN" => Error::ScriptOpReturn,
+            "SCRIPT_SIZE" => Error::ScriptSize,
+            "PUSH_SIZE" => Error::ScriptPushSize,
+            "OP_COUNT" => Error::ScriptOpCount,
+            "STACK_SIZE" => Error::ScriptStackSize,
+            "SIG_COUNT" => Error::ScriptSigCount,
+            "PUBKEY_COUNT" => Error::ScriptPubKeyCount,
+            "VERIFY" => Error::ScriptVerify,
+            "EQUALVERIFY" => Error::ScriptEqualVerify,
+            "CHECKMULTISIGVERIFY" => Error::ScriptCheckMultiSigVerify,
+            "CHECKSIGVERIFY" => Error::ScriptCheckSigVerify,
+            "NUMEQUALVERIFY" => Error::ScriptNumEqualVerify,
+            "BAD_OPCODE" => Error::ScriptBadOpcode,
+            "DISABLED_OPCODE" => Error::ScriptDisabledOpcode,
+            "INVALID_STACK_OPERATION" => Error::ScriptInvalidStackOperation,
+            "INVALID_ALTSTACK_OPERATION" => Error::ScriptInvalidAltStackOperation,
+            "UNBALANCED_CONDITIONAL" => Error::ScriptUnbalancedConditional,
+            "NEGATIVE_LOCKTIME" => Error::ScriptNegativeLockTime,
+            "UNSATISFIED_LOCKTIME" => Error::ScriptUnsatisfiedLockTime,
+            "SIG_HASHTYPE" => Error::ScriptSigHashType,
+            "SIG_DER" => Error::ScriptSigDer,
+            "MINIMALDATA" => Error::ScriptMinimalData,
+            "SIG_PUSHONLY" => Error::ScriptSigPushOnly,
+            "SIG_HIGH_S" => Error::ScriptSigHighS,
+            "SIG_NULLDUMMY" => Error::ScriptSigNullDummy,
+            "PUBKEYTYPE" => Error::ScriptPubKeyType,
+            "CLEANSTACK" => Error::ScriptCleanStack,
+            "MINIMALIF" => Error::ScriptMinimalIf,
+            "NULLFAIL" => Error::ScriptNullFail,
+            "SCRIPTNUM" => Error::ScriptNum,
+            "DISCOURAGE_UPGRADABLE_NOPS" => Error::ScriptDiscourageUpgradableNops,
+            "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM" => Error::ScriptDiscourageUpgradableWitnessProgram,
+            "WITNESS_PROGRAM_WRONG_LENGTH" => Error::ScriptWitnessProgramWrongLength,
+            "WITNESS_PROGRAM_WITNESS_EMPTY" => Error::ScriptWitnessProgramWitnessEmpty,
+            "WITNESS_PROGRAM_MISMATCH" => Error::ScriptWitnessProgramMismatch,
+            "WITNESS_MALLEATED" => Error::ScriptWitnessMalleated,
+            "WITNESS_MALLEATED_P2SH" => Error::ScriptWitnessMalleatedP2sh,
+            "WITNESS_UNEXPECTED" => Error::ScriptWitnessUnexpected,
+            "WITNESS_PUBKEYTYPE" => Error::ScriptWitnessPubKeyType,
+            "OP_CODESEPARATOR" => Error::ScriptOpCodeSeparator,
+            "SIG_FINDANDDELETE" => Error::ScriptSigFindAndDelete,
+            "SCHNORR_SIG_SIZE" => Error::ScriptSchnorrSigSize,
+            "SCHNORR_SIG_HASHTYPE" => Error::ScriptSchnorrSigHashType,
+            "SCHNORR_SIG" => Error::ScriptSchnorrSig,
+            "TAPROOT_WRONG_CONTROL_SIZE" => Error::ScriptTaprootWrongControlSize,
+            "TAPSCRIPT_VALIDATION_WEIGHT" => Error::ScriptTapscriptValidationWeight,
+            "TAPSCRIPT_CHECKMULTISIG" => Error::ScriptTapscriptCheckMultiSig,
+            "TAPSCRIPT_MINIMALIF" => Error::ScriptTapscriptMinimalIf,
+            "TAPSCRIPT_EMPTY_PUBKEY" => Error::ScriptTapscriptEmptyPubKey,
+            "DISCOURAGE_OP_SUCCESS" => Error::ScriptDiscourageOpSuccess,
+            "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION" => Error::ScriptDiscourageUpgradableTaprootVersion,
+            "DISCOURAGE_UPGRADABLE_PUBKEYTYPE" => Error::ScriptDiscourageUpgradablePubKeyType,
+            _ => panic!("unknown error {}", text),
+        };
+        Err(error)
+    }
+
+    /// The transaction spending `script_pubkey`, the output of a coinbase like transaction.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/script_tests.cpp BuildSpendingTransaction
+    fn spending_transaction(script_sig: Script, witness: Vec<Witness>, script_pubkey: Script, amount: u64) -> Transaction {
+
+        let credit = Transaction {
+            version: 1,
+            flag: None,
+            inputs: vec![TxIn {
+                previous: OutPoint { transaction_hash: [0; 32], index: 0xffff_ffff },
+                signature: Script::new(vec![0x00, 0x00]),
+                sequence: 0xffff_ffff,
+                witness: vec![],
+            }],
+            outputs: vec![TxOut { amount: amount, script_pubkey: script_pubkey }],
+            locktime: 0,
+        };
+
+        Transaction {
+            version: 1,
+            flag: None,
+            inputs: vec![TxIn {
+                previous: OutPoint { transaction_hash: credit.txid().hash, index: 0 },
+                signature: script_sig,
+                sequence: 0xffff_ffff,
+                witness: witness,
+            }],
+            outputs: vec![TxOut { amount: amount, script_pubkey: Script::default() }],
+            locktime: 0,
+        }
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/script_tests.json
+    #[test]
+    fn when_core_script_tests_then_same_result() {
+
+        let vectors : Vec<Value> = serde_json::from_str(include_str!("../tests/data/script_tests.json")).unwrap();
+        let mut count = 0;
+        for vector in vectors {
+
+            let mut vector = vector.as_array().unwrap().clone();
+            if vector.len() == 1 {
+                continue; // comment
+            }
+
+            let mut witness = Vec::new();
+            let mut amount = 0;
+            if vector[0].is_array() {
+                witness = vector.remove(0).as_array().unwrap().clone();
+                amount = (witness.pop().unwrap().as_f64().unwrap() * 100_000_000.0).round() as u64;
+            }
+
+            let script_sig = vector[0].as_str().unwrap();
+            let script_pubkey = vector[1].as_str().unwrap();
+            let flags = vector[2].as_str().unwrap();
+            let expected = vector[3].as_str().unwrap();
+
+            // taproot spends of a single leaf tree with the generator point as internal key
+            let mut tapscript = None;
+            let mut witness : Vec<Witness> = witness.iter().map(|item| {
+                let item = item.as_str().unwrap();
+                match item.strip_prefix("#SCRIPT# ") {
+                    Some(text) => {
+                        let script = parse_script(text);
+                        tapscript = Some(script.clone());
+                        Witness { data: script.content }
+                    },
+                    None if item == "#CONTROLBLOCK#" => Witness { data: vec![] },
+                    None => Witness { data: hex::decode(item).unwrap() },
+                }
+            }).collect();
+            let mut script_pubkey = script_pubkey.to_string();
+            if let Some(tapscript) = tapscript {
+                let root = tapleaf_hash(TAPROOT_LEAF_TAPSCRIPT, &tapscript);
+                let (output_key, parity) = tweak_key(&GENERATOR_X, Some(&root)).unwrap();
+                let control = witness.iter_mut().find(|item| item.data.is_empty()).unwrap();
+                control.data.push(TAPROOT_LEAF_TAPSCRIPT | parity);
+                control.data.extend_from_slice(&GENERATOR_X);
+                script_pubkey = script_pubkey.replace("#TAPROOTOUTPUT#", &format!("0x{}", hex::encode(output_key)));
+            }
+
+            let script_sig = parse_script(script_sig);
+            let script_pubkey = parse_script(&script_pubkey);
+
+            let spending = spending_transaction(script_sig.clone(), witness, script_pubkey.clone(), amount);
+            let spent_outputs = vec![TxOut { amount: amount, script_pubkey: script_pubkey.clone() }];
+            let cache = SigHashCache::with_spent_outputs(&spending, &spent_outputs);
+            let checker = TransactionChecker::new(&spending, 0, &spent_outputs, &cache);
+            let result = verify_script(&script_sig, &script_pubkey, &spending.inputs[0].witness, parse_flags(flags), &checker);
+            assert_eq!(result, parse_error(expected), "{:?}", vector);
+            count += 1;
+        }
+        assert!(count > 1100, "{}", count);
+    }
+
+    /// x coordinate of the generator point, the public key of the private key 1
+    const GENERATOR_X : [u8; 32] = [
+        0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+        0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+    ];
+
+    struct ValidSignatures;
+
+    impl SignatureChecker for ValidSignatures {
+        fn check_ecdsa_signature(&self, signature: &[u8], _pubkey: &[u8], _script_code: &Script, _sigversion: SigVersion) -> bool {
+            !signature.is_empty()
+        }
+
+        fn check_schnorr_signature(&self, signature: &[u8], _pubkey: &[u8], _sigversion: SigVersion, _execdata: &ExecutionData) -> Result<(), Error> {
+            if signature.len() == 64 { Ok(()) } else { Err(Error::ScriptSchnorrSig) }
+        }
+    }
+
+    /// The witness spending the output of a single leaf tree of `script`, with the
+    /// generator point as internal key, and its scriptPubKey.
+    fn tapscript_spend(inputs: &[&[u8]], script: &Script, leaf_version: u8) -> (Vec<Witness>, Script) {
+
+        let root = tapleaf_hash(leaf_version, script);
+        let (output_key, parity) = tweak_key(&GENERATOR_X, Some(&root)).unwrap();
+        let mut witness : Vec<Witness> = inputs.iter().map(|input| Witness { data: input.to_vec() }).collect();
+        witness.push(Witness { data: script.content.clone() });
+        witness.push(Witness { data: [&[leaf_version | parity][..], &GENERATOR_X[..]].concat() });
+
+        let mut script_pubkey = Script::default();
+        script_pubkey.push_opcode(Opcode::Op1);
+        script_pubkey.push_data(&output_key);
+        (witness, script_pubkey)
+    }
+
+    fn verify_tapscript(inputs: &[&[u8]], script: &str, flags: VerifyFlags) -> Result<(), Error> {
+        let (witness, script_pubkey) = tapscript_spend(inputs, &parse_script(script), TAPROOT_LEAF_TAPSCRIPT);
+        verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures)
+    }
+
+    /// Push of a 32 bytes public key
+    const PUSH_XONLY : &str = "0x20 0x0202020202020202020202020202020202020202020202020202020202020202";
+
+    #[test]
+    fn when_tapscript_checksigadd_then_valid_signatures_counted() {
+
+        let flags = VerifyFlags::consensus();
+        let signature = [0x01; 64];
+        let script = format!("{0} CHECKSIG {0} CHECKSIGADD {0} CHECKSIGADD 2 NUMEQUAL", PUSH_XONLY);
+        assert_eq!(verify_tapscript(&[&signature, &[], &signature], &script, flags), Ok(()));
+        assert_eq!(verify_tapscript(&[&[], &[], &signature], &script, flags), Err(Error::ScriptEvalFalse));
+        // a non empty signature must be valid
+        assert_eq!(verify_tapscript(&[&signature, &[0x01], &signature], &script, flags), Err(Error::ScriptSchnorrSig));
+
+        let mut stack = vec![signature.to_vec(), vec![], vec![0x02; 32]];
+        let checksigadd = parse_script("CHECKSIGADD");
+        assert_eq!(eval_script(&mut stack, &checksigadd, flags, &ValidSignatures, SigVersion::WitnessV0, &mut ExecutionData::default()), Err(Error::ScriptBadOpcode));
+    }
+
+    #[test]
+    fn when_tapscript_then_unknown_pubkey_type_discouraged() {
+
+        let flags = VerifyFlags::consensus();
+        let script = "0x21 0x020202020202020202020202020202020202020202020202020202020202020202 CHECKSIG";
+        assert_eq!(verify_tapscript(&[&[0x01]], script, flags), Ok(()));
+        assert_eq!(verify_tapscript(&[&[0x01]], script, flags | VerifyFlags::DISCOURAGE_UPGRADABLE_PUBKEYTYPE), Err(Error::ScriptDiscourageUpgradablePubKeyType));
+        assert_eq!(verify_tapscript(&[&[0x01]], "0 CHECKSIG", flags), Err(Error::ScriptTapscriptEmptyPubKey));
+    }
+
+    #[test]
+    fn when_tapscript_signatures_over_budget_then_validation_weight() {
+
+        // each check of the same signature adds 35 bytes to the witness but consumes 50
+        let flags = VerifyFlags::consensus();
+        let signature = [0x01; 64];
+        let checks = |count: usize| format!("{}{} CHECKSIG", format!("DUP {} CHECKSIGVERIFY ", PUSH_XONLY).repeat(count), PUSH_XONLY);
+        assert_eq!(verify_tapscript(&[&signature], &checks(8), flags), Ok(()));
+        assert_eq!(verify_tapscript(&[&signature], &checks(20), flags), Err(Error::ScriptTapscriptValidationWeight));
+        // empty signatures are free
+        let empty = format!("{}{} CHECKSIG NOT", format!("DUP {} CHECKSIG DROP ", PUSH_XONLY).repeat(20), PUSH_XONLY);
+        assert_eq!(verify_tapscript(&[&[]], &empty, flags), Ok(()));
+
+        let mut execdata = ExecutionData { validation_weight_left: 99, ..ExecutionData::default() };
+        let checksig = parse_script(&format!("{} CHECKSIG", PUSH_XONLY));
+        let mut stack = vec![signature.to_vec()];
+        assert_eq!(eval_script(&mut stack, &checksig, flags, &ValidSignatures, SigVersion::Tapscript, &mut execdata), Ok(()));
+        assert_eq!(execdata.validation_weight_left, 49);
+        let mut stack = vec![signature.to_vec()];
+        assert_eq!(eval_script(&mut stack, &checksig, flags, &ValidSignatures, SigVersion::Tapscript, &mut execdata), Err(Error::ScriptTapscriptValidationWeight));
+    }
+
+    #[test]
+    fn when_tapscript_then_checkmultisig_disabled_and_if_minimal() {
+
+        let flags = VerifyFlags::consensus();
+        assert_eq!(verify_tapscript(&[], "0 0 0 CHECKMULTISIG", flags), Err(Error::ScriptTapscriptCheckMultiSig));
+        assert_eq!(verify_tapscript(&[&[0x02]], "IF 1 ENDIF", flags), Err(Error::ScriptTapscriptMinimalIf));
+        assert_eq!(verify_tapscript(&[&[0x01]], "IF 1 ENDIF", flags), Ok(()));
+        // no limit of 201 opcodes
+        assert_eq!(verify_tapscript(&[], &format!("1{}", " NOP".repeat(300)), flags), Ok(()));
+    }
+
+    #[test]
+    fn when_tapscript_op_success_then_valid() {
+
+        let flags = VerifyFlags::consensus();
+        // OP_CAT, then a truncated push
+        assert_eq!(verify_tapscript(&[], "0 0x7e RETURN 0x4c", flags), Ok(()));
+        assert_eq!(verify_tapscript(&[], "0 0x7e", flags | VerifyFlags::DISCOURAGE_OP_SUCCESS), Err(Error::ScriptDiscourageOpSuccess));
+        // a truncated push before
+        assert_eq!(verify_tapscript(&[], "0x4c 0x7e", flags), Err(Error::ScriptBadOpcode));
+    }
+
+    #[test]
+    fn when_taproot_script_path_then_control_block_checked() {
+
+        let flags = VerifyFlags::consensus();
+        let script = parse_script("1");
+        let (mut witness, script_pubkey) = tapscript_spend(&[], &script, TAPROOT_LEAF_TAPSCRIPT);
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Ok(()));
+
+        // an annex is ignored
+        witness.push(Witness { data: vec![ANNEX_TAG, 0x01] });
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Ok(()));
+        witness.pop();
+
+        // a wrong parity
+        let control = witness.len() - 1;
+        witness[control].data[0] ^= 0x01;
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Err(Error::ScriptWitnessProgramMismatch));
+        witness[control].data.push(0x00);
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Err(Error::ScriptTaprootWrongControlSize));
+
+        // not verified without the flag
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags - VerifyFlags::TAPROOT, &ValidSignatures), Ok(()));
+    }
+
+    #[test]
+    fn when_taproot_unknown_leaf_version_then_valid() {
+
+        let flags = VerifyFlags::consensus();
+        let (witness, script_pubkey) = tapscript_spend(&[], &parse_script("0"), 0xc2);
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Ok(()));
+        let flags = flags | VerifyFlags::DISCOURAGE_UPGRADABLE_TAPROOT_VERSION;
+        assert_eq!(verify_script(&Script::default(), &script_pubkey, &witness, flags, &ValidSignatures), Err(Error::ScriptDiscourageUpgradableTaprootVersion));
+    }
+
+    #[test]
+    fn when_multisig_then_dummy_and_keys_popped() {
+
+        let flags = VerifyFlags::NULLDUMMY;
+        let script = parse_script("0 0x01 0x30 0x01 0x30 2 'a' 'b' 'c' 3 CHECKMULTISIG");
+        let mut stack = Vec::new();
+        assert_eq!(eval_script(&mut stack, &script, flags, &ValidSignatures, SigVersion::Base, &mut ExecutionData::default()), Ok(()));
+        assert_eq!(stack, vec![vec![1]]);
+
+        let script = parse_script("1 0x01 0x30 1 'a' 1 CHECKMULTISIG");
+        let mut stack = Vec::new();
+        assert_eq!(eval_script(&mut stack, &script, flags, &ValidSignatures, SigVersion::Base, &mut ExecutionData::default()), Err(Error::ScriptSigNullDummy));
+
+        let script = parse_script("0 0 1 'a' 1 CHECKMULTISIGVERIFY");
+        let mut stack = Vec::new();
+        assert_eq!(eval_script(&mut stack, &script, flags, &ValidSignatures, SigVersion::Base, &mut ExecutionData::default()), Err(Error::ScriptCheckMultiSigVerify));
+    }
+
+    #[test]
+    fn when_locktime_then_checked_against_transaction() {
+
+        let mut spending = spending_transaction(Script::default(), vec![], Script::default(), 0);
+        spending.locktime = 100;
+        spending.inputs[0].sequence = 0;
+        let cache = SigHashCache::new(&spending);
+        let checker = TransactionChecker::new(&spending, 0, &[], &cache);
+        assert!(checker.check_locktime(100));
+        assert!(!checker.check_locktime(101));
+        assert!(!checker.check_locktime(500_000_000));
+
+        spending.inputs[0].sequence = 0xffff_ffff;
+        let cache = SigHashCache::new(&spending);
+        let checker = TransactionChecker::new(&spending, 0, &[], &cache);
+        assert!(!checker.check_locktime(100));
+
+        spending.version = 2;
+        spending.inputs[0].sequence = 10;
+        let cache = SigHashCache::new(&spending);
+        let checker = TransactionChecker::new(&spending, 0, &[], &cache);
+        assert!(checker.check_sequence(10));
+        assert!(!checker.check_sequence(11));
+        assert!(!checker.check_sequence(10 | 1 << 22));
+    }
+
+    #[test]
+    fn when_num_then_minimal_and_bounded() {
+        assert_eq!(read_num(&[0x01, 0x00], false, 4), Ok(1));
+        assert_eq!(read_num(&[0x01, 0x00], true, 4), Err(Error::ScriptNum));
+        assert_eq!(read_num(&[0x80], true, 4), Err(Error::ScriptNum));
+        assert_eq!(read_num(&[0xff, 0x80], true, 4), Ok(-255));
+        assert_eq!(read_num(&[0xff, 0xff, 0xff, 0xff, 0x00], true, 4), Err(Error::ScriptNum));
+        assert_eq!(read_num(&[0xff, 0xff, 0xff, 0xff, 0x00], true, 5), Ok(0xffff_ffff));
+    }
+
+    #[test]
+    fn when_negative_zero_then_false() {
+        assert!(!cast_to_bool(&[]));
+        assert!(!cast_to_bool(&[0x00, 0x00]));
+        assert!(!cast_to_bool(&[0x00, 0x80]));
+        assert!(cast_to_bool(&[0x80, 0x00]));
+        assert!(cast_to_bool(&[0x00, 0x01]));
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/script_tests.cpp script_FindAndDelete
+    #[test]
+    fn when_find_and_delete_then_only_at_instruction_boundaries() {
+
+        for (script, signature, expected, found) in [
+            ("0x0302ff03", "02ff03", "", true),
+            ("0x0302ff030302ff03", "02ff03", "", true),
+            ("0x0302ff030302ff03", "ff", "0x0302ff030302ff03", false),
+            ("0x0302ff0302ff03", "02ff03", "0x02ff03", true),
+            ("0x0002feed", "feed", "0x00", true),
+            // 02feed is inside the push of 3 bytes
+            ("0x0302feed", "feed", "0x0302feed", false),
+            ("0x02feed5169", "feed", "0x5169", true),
+            // an empty signature is pushed with OP_0, the truncated push is kept
+            ("0x00030000", "", "0x030000", true),
+        ] {
+            let mut script = parse_script(script);
+            assert_eq!(find_and_delete(&mut script, &hex::decode(signature).unwrap()), found, "{}", signature);
+            assert_eq!(script, parse_script(expected));
+        }
+    }
+}
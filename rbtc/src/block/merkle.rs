@@ -0,0 +1,174 @@
+use crate::block::block::Block;
+use crate::utils::sha256::Sha256;
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#Merkle_Trees
+///
+/// # Merkle tree
+///
+/// The leaves are the transaction hashes. Each level is built by hashing pairs of
+/// consecutive hashes with a double sha256; a level with an odd number of hashes
+/// pairs its last hash with itself.
+///
+/// ```
+///              root = H(H12 | H33)
+///             /                  \
+///      H12 = H(H1 | H2)     H33 = H(H3 | H3)
+///        /        \            /
+///      H1          H2        H3
+/// ```
+///
+/// ## CVE-2012-2459
+///
+/// Because of the odd leaf duplication, the transaction lists [1,2,3] and [1,2,3,3]
+/// give the same root. A block with a mutated transaction list still carries a valid
+/// looking merkle root. The mutation is detected when two identical hashes are paired
+/// at any level, which is what Core reports as `mutated`.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/consensus/merkle.cpp
+pub struct MerkleRoot {
+    pub root: Sha256,
+    pub mutated: bool,
+}
+
+/// Header prefix of the witness commitment output (BIP141):
+/// OP_RETURN, push 36 bytes, 0xaa21a9ed
+const WITNESS_COMMITMENT_HEADER : [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+impl MerkleRoot {
+
+    /// Computes the merkle root of the given leaves. An empty list gives the zero hash.
+    pub fn compute(leaves: Vec<Sha256>) -> MerkleRoot {
+
+        let mut hashes = leaves;
+        let mut mutated = false;
+
+        if hashes.is_empty() {
+            return MerkleRoot { root: Sha256::default(), mutated: false };
+        }
+
+        while hashes.len() > 1 {
+            // identical siblings mean the tree can be rewritten with a duplicated subtree
+            for pair in hashes.chunks(2) {
+                if pair.len() == 2 && pair[0] == pair[1] {
+                    mutated = true;
+                }
+            }
+            if hashes.len() % 2 == 1 {
+                let last = hashes[hashes.len() - 1];
+                hashes.push(last);
+            }
+            hashes = hashes.chunks(2)
+                .map(|pair| MerkleRoot::parent(&pair[0], &pair[1]))
+                .collect();
+        }
+
+        MerkleRoot {
+            root: hashes[0],
+            mutated: mutated
+        }
+    }
+
+    /// Merkle root of the block transactions, built from the txids.
+    pub fn block(block: &Block) -> MerkleRoot {
+        let leaves = block.transactions.iter()
+            .map(|tx| tx.txid())
+            .collect();
+        MerkleRoot::compute(leaves)
+    }
+
+    /// Witness merkle root (BIP141), built from the wtxids with the coinbase
+    /// wtxid replaced by the zero hash.
+    pub fn witness(block: &Block) -> MerkleRoot {
+        let leaves = block.transactions.iter()
+            .enumerate()
+            .map(|(i, tx)| if i == 0 { Sha256::default() } else { tx.wtxid() })
+            .collect();
+        MerkleRoot::compute(leaves)
+    }
+
+    /// Witness commitment (BIP141), double sha256 of the witness root followed by
+    /// the witness reserved value found in the coinbase input witness.
+    pub fn witness_commitment(witness_root: &Sha256, reserved: &[u8]) -> Sha256 {
+        let mut data : Vec<u8> = Vec::with_capacity(64);
+        data.extend_from_slice(&witness_root.hash);
+        data.extend_from_slice(reserved);
+        Sha256::double(&data)
+    }
+
+    /// Finds the commitment in the coinbase outputs. When several outputs match,
+    /// the last one is the commitment.
+    pub fn find_witness_commitment(block: &Block) -> Option<Sha256> {
+        let coinbase = block.transactions.get(0)?;
+        coinbase.outputs.iter()
+            .rev()
+            .map(|output| &output.script_pubkey.content)
+            .find(|script| script.len() >= 38 && script[0..6] == WITNESS_COMMITMENT_HEADER)
+            .map(|script| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&script[6..38]);
+                Sha256::new(hash)
+            })
+    }
+
+    fn parent(left: &Sha256, right: &Sha256) -> Sha256 {
+        let mut data : Vec<u8> = Vec::with_capacity(64);
+        data.extend_from_slice(&left.hash);
+        data.extend_from_slice(&right.hash);
+        Sha256::double(&data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::merkle::MerkleRoot;
+    use crate::utils::sha256::Sha256;
+
+    use std::str::FromStr;
+
+    fn leaf(b: u8) -> Sha256 {
+        Sha256::new([b; 32])
+    }
+
+    #[test]
+    fn when_compute_empty_then_zero() {
+        let result = MerkleRoot::compute(Vec::new());
+        assert_eq!(result.root, Sha256::default());
+        assert!(!result.mutated);
+    }
+
+    #[test]
+    fn when_compute_single_then_leaf() {
+        let result = MerkleRoot::compute(vec![leaf(1)]);
+        assert_eq!(result.root, leaf(1));
+        assert!(!result.mutated);
+    }
+
+    #[test]
+    fn when_compute_odd_then_last_duplicated() {
+        let odd = MerkleRoot::compute(vec![leaf(1), leaf(2), leaf(3)]);
+        let even = MerkleRoot::compute(vec![leaf(1), leaf(2), leaf(3), leaf(3)]);
+        assert_eq!(odd.root, even.root);
+        assert!(!odd.mutated);
+        assert!(even.mutated);
+    }
+
+    #[test]
+    fn when_compute_duplicated_subtree_then_mutated() {
+        let original = MerkleRoot::compute(vec![leaf(1), leaf(2), leaf(3), leaf(4), leaf(5), leaf(6)]);
+        let mutated = MerkleRoot::compute(vec![leaf(1), leaf(2), leaf(3), leaf(4), leaf(5), leaf(6), leaf(5), leaf(6)]);
+        assert_eq!(original.root, mutated.root);
+        assert!(!original.mutated);
+        assert!(mutated.mutated);
+    }
+
+    #[test]
+    fn when_compute_two_txids_then_known_root() {
+        // block 00000000b0c5a240b2a61d2e75692224efd4cbecdf6eaf4cc2cf477ca7c270e7
+        let t1 = Sha256::from_str("77dfc2fe598419b00641c296181a96cf16943697f573480b023b77cce82ada21").unwrap();
+        let t2 = Sha256::from_str("a3b0e9e7cddbbe78270fa4182a7675ff00b92872d8df7d14265a2b1e379a9d33").unwrap();
+        let result = MerkleRoot::compute(vec![t1, t2]);
+        assert_eq!(result.root.to_string(), "4c917a410f4e899195f816081844e56aceda71c4cc4fe634aebe9437e57344bf");
+        assert!(!result.mutated);
+    }
+}
@@ -1,7 +1,18 @@
 pub mod block;
+pub mod check;
+pub mod header;
+pub mod merkle;
+pub mod pow;
 pub mod transaction;
 pub mod txin;
 pub mod txout;
 pub mod script;
+pub mod interpreter;
+pub mod sighash;
+pub mod signature;
+pub mod taproot;
+pub mod verify;
+pub mod opcode;
+pub mod standard;
 pub mod witness;
 pub mod outpoint;
\ No newline at end of file
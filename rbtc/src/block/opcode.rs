@@ -0,0 +1,647 @@
+use std::fmt;
+
+/// https://en.bitcoin.it/wiki/Script
+///
+/// # Opcode
+///
+/// Script is a stack based language, read from left to right, one byte opcode at a time.
+/// The opcodes from 0x00 to 0x4e push data on the stack, the data following the opcode
+/// in the script. The other ones act on the stack.
+///
+/// ```
+/// +-----------+-----------------+--------------------------------------------------+
+/// | Opcode    | Name            | Pushes                                           |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x00      | OP_0            | an empty array                                   |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x01-0x4b |                 | the next opcode bytes                            |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x4c      | OP_PUSHDATA1    | the next byte is the number of bytes to push     |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x4d      | OP_PUSHDATA2    | the next 2 bytes (LE) are the number of bytes    |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x4e      | OP_PUSHDATA4    | the next 4 bytes (LE) are the number of bytes    |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x4f      | OP_1NEGATE      | the number -1                                    |
+/// +-----------+-----------------+--------------------------------------------------+
+/// | 0x51-0x60 | OP_1 to OP_16   | the number 1 to 16                               |
+/// +-----------+-----------------+--------------------------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/script.h
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/script.cpp GetOpName
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    // push value
+    Op0,
+    /// 0x01 to 0x4b, push the next n bytes
+    PushBytes(u8),
+    PushData1,
+    PushData2,
+    PushData4,
+    Op1Negate,
+    Reserved,
+    Op1,
+    Op2,
+    Op3,
+    Op4,
+    Op5,
+    Op6,
+    Op7,
+    Op8,
+    Op9,
+    Op10,
+    Op11,
+    Op12,
+    Op13,
+    Op14,
+    Op15,
+    Op16,
+
+    // control
+    Nop,
+    Ver,
+    If,
+    NotIf,
+    VerIf,
+    VerNotIf,
+    Else,
+    EndIf,
+    Verify,
+    Return,
+
+    // stack ops
+    ToAltStack,
+    FromAltStack,
+    Drop2,
+    Dup2,
+    Dup3,
+    Over2,
+    Rot2,
+    Swap2,
+    IfDup,
+    Depth,
+    Drop,
+    Dup,
+    Nip,
+    Over,
+    Pick,
+    Roll,
+    Rot,
+    Swap,
+    Tuck,
+
+    // splice ops
+    Cat,
+    Substr,
+    Left,
+    Right,
+    Size,
+
+    // bit logic
+    Invert,
+    And,
+    Or,
+    Xor,
+    Equal,
+    EqualVerify,
+    Reserved1,
+    Reserved2,
+
+    // numeric
+    Add1,
+    Sub1,
+    Mul2,
+    Div2,
+    Negate,
+    Abs,
+    Not,
+    NotEqual0,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    LShift,
+    RShift,
+    BoolAnd,
+    BoolOr,
+    NumEqual,
+    NumEqualVerify,
+    NumNotEqual,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+    Min,
+    Max,
+    Within,
+
+    // crypto
+    Ripemd160,
+    Sha1,
+    Sha256,
+    Hash160,
+    Hash256,
+    CodeSeparator,
+    CheckSig,
+    CheckSigVerify,
+    CheckMultiSig,
+    CheckMultiSigVerify,
+
+    // expansion
+    Nop1,
+    CheckLockTimeVerify,
+    CheckSequenceVerify,
+    Nop4,
+    Nop5,
+    Nop6,
+    Nop7,
+    Nop8,
+    Nop9,
+    Nop10,
+
+    // BIP342 tapscript
+    CheckSigAdd,
+
+    /// 0xbb to 0xfe, not assigned
+    Unknown(u8),
+    InvalidOpcode,
+}
+
+impl Opcode {
+
+    pub fn from_u8(value: u8) -> Opcode {
+        match value {
+            0x00 => Opcode::Op0,
+            0x01..=0x4b => Opcode::PushBytes(value),
+            0x4c => Opcode::PushData1,
+            0x4d => Opcode::PushData2,
+            0x4e => Opcode::PushData4,
+            0x4f => Opcode::Op1Negate,
+            0x50 => Opcode::Reserved,
+            0x51 => Opcode::Op1,
+            0x52 => Opcode::Op2,
+            0x53 => Opcode::Op3,
+            0x54 => Opcode::Op4,
+            0x55 => Opcode::Op5,
+            0x56 => Opcode::Op6,
+            0x57 => Opcode::Op7,
+            0x58 => Opcode::Op8,
+            0x59 => Opcode::Op9,
+            0x5a => Opcode::Op10,
+            0x5b => Opcode::Op11,
+            0x5c => Opcode::Op12,
+            0x5d => Opcode::Op13,
+            0x5e => Opcode::Op14,
+            0x5f => Opcode::Op15,
+            0x60 => Opcode::Op16,
+            0x61 => Opcode::Nop,
+            0x62 => Opcode::Ver,
+            0x63 => Opcode::If,
+            0x64 => Opcode::NotIf,
+            0x65 => Opcode::VerIf,
+            0x66 => Opcode::VerNotIf,
+            0x67 => Opcode::Else,
+            0x68 => Opcode::EndIf,
+            0x69 => Opcode::Verify,
+            0x6a => Opcode::Return,
+            0x6b => Opcode::ToAltStack,
+            0x6c => Opcode::FromAltStack,
+            0x6d => Opcode::Drop2,
+            0x6e => Opcode::Dup2,
+            0x6f => Opcode::Dup3,
+            0x70 => Opcode::Over2,
+            0x71 => Opcode::Rot2,
+            0x72 => Opcode::Swap2,
+            0x73 => Opcode::IfDup,
+            0x74 => Opcode::Depth,
+            0x75 => Opcode::Drop,
+            0x76 => Opcode::Dup,
+            0x77 => Opcode::Nip,
+            0x78 => Opcode::Over,
+            0x79 => Opcode::Pick,
+            0x7a => Opcode::Roll,
+            0x7b => Opcode::Rot,
+            0x7c => Opcode::Swap,
+            0x7d => Opcode::Tuck,
+            0x7e => Opcode::Cat,
+            0x7f => Opcode::Substr,
+            0x80 => Opcode::Left,
+            0x81 => Opcode::Right,
+            0x82 => Opcode::Size,
+            0x83 => Opcode::Invert,
+            0x84 => Opcode::And,
+            0x85 => Opcode::Or,
+            0x86 => Opcode::Xor,
+            0x87 => Opcode::Equal,
+            0x88 => Opcode::EqualVerify,
+            0x89 => Opcode::Reserved1,
+            0x8a => Opcode::Reserved2,
+            0x8b => Opcode::Add1,
+            0x8c => Opcode::Sub1,
+            0x8d => Opcode::Mul2,
+            0x8e => Opcode::Div2,
+            0x8f => Opcode::Negate,
+            0x90 => Opcode::Abs,
+            0x91 => Opcode::Not,
+            0x92 => Opcode::NotEqual0,
+            0x93 => Opcode::Add,
+            0x94 => Opcode::Sub,
+            0x95 => Opcode::Mul,
+            0x96 => Opcode::Div,
+            0x97 => Opcode::Mod,
+            0x98 => Opcode::LShift,
+            0x99 => Opcode::RShift,
+            0x9a => Opcode::BoolAnd,
+            0x9b => Opcode::BoolOr,
+            0x9c => Opcode::NumEqual,
+            0x9d => Opcode::NumEqualVerify,
+            0x9e => Opcode::NumNotEqual,
+            0x9f => Opcode::LessThan,
+            0xa0 => Opcode::GreaterThan,
+            0xa1 => Opcode::LessThanOrEqual,
+            0xa2 => Opcode::GreaterThanOrEqual,
+            0xa3 => Opcode::Min,
+            0xa4 => Opcode::Max,
+            0xa5 => Opcode::Within,
+            0xa6 => Opcode::Ripemd160,
+            0xa7 => Opcode::Sha1,
+            0xa8 => Opcode::Sha256,
+            0xa9 => Opcode::Hash160,
+            0xaa => Opcode::Hash256,
+            0xab => Opcode::CodeSeparator,
+            0xac => Opcode::CheckSig,
+            0xad => Opcode::CheckSigVerify,
+            0xae => Opcode::CheckMultiSig,
+            0xaf => Opcode::CheckMultiSigVerify,
+            0xb0 => Opcode::Nop1,
+            0xb1 => Opcode::CheckLockTimeVerify,
+            0xb2 => Opcode::CheckSequenceVerify,
+            0xb3 => Opcode::Nop4,
+            0xb4 => Opcode::Nop5,
+            0xb5 => Opcode::Nop6,
+            0xb6 => Opcode::Nop7,
+            0xb7 => Opcode::Nop8,
+            0xb8 => Opcode::Nop9,
+            0xb9 => Opcode::Nop10,
+            0xba => Opcode::CheckSigAdd,
+            0xbb..=0xfe => Opcode::Unknown(value),
+            0xff => Opcode::InvalidOpcode,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Opcode::Op0 => 0x00,
+            Opcode::PushBytes(value) => value,
+            Opcode::PushData1 => 0x4c,
+            Opcode::PushData2 => 0x4d,
+            Opcode::PushData4 => 0x4e,
+            Opcode::Op1Negate => 0x4f,
+            Opcode::Reserved => 0x50,
+            Opcode::Op1 => 0x51,
+            Opcode::Op2 => 0x52,
+            Opcode::Op3 => 0x53,
+            Opcode::Op4 => 0x54,
+            Opcode::Op5 => 0x55,
+            Opcode::Op6 => 0x56,
+            Opcode::Op7 => 0x57,
+            Opcode::Op8 => 0x58,
+            Opcode::Op9 => 0x59,
+            Opcode::Op10 => 0x5a,
+            Opcode::Op11 => 0x5b,
+            Opcode::Op12 => 0x5c,
+            Opcode::Op13 => 0x5d,
+            Opcode::Op14 => 0x5e,
+            Opcode::Op15 => 0x5f,
+            Opcode::Op16 => 0x60,
+            Opcode::Nop => 0x61,
+            Opcode::Ver => 0x62,
+            Opcode::If => 0x63,
+            Opcode::NotIf => 0x64,
+            Opcode::VerIf => 0x65,
+            Opcode::VerNotIf => 0x66,
+            Opcode::Else => 0x67,
+            Opcode::EndIf => 0x68,
+            Opcode::Verify => 0x69,
+            Opcode::Return => 0x6a,
+            Opcode::ToAltStack => 0x6b,
+            Opcode::FromAltStack => 0x6c,
+            Opcode::Drop2 => 0x6d,
+            Opcode::Dup2 => 0x6e,
+            Opcode::Dup3 => 0x6f,
+            Opcode::Over2 => 0x70,
+            Opcode::Rot2 => 0x71,
+            Opcode::Swap2 => 0x72,
+            Opcode::IfDup => 0x73,
+            Opcode::Depth => 0x74,
+            Opcode::Drop => 0x75,
+            Opcode::Dup => 0x76,
+            Opcode::Nip => 0x77,
+            Opcode::Over => 0x78,
+            Opcode::Pick => 0x79,
+            Opcode::Roll => 0x7a,
+            Opcode::Rot => 0x7b,
+            Opcode::Swap => 0x7c,
+            Opcode::Tuck => 0x7d,
+            Opcode::Cat => 0x7e,
+            Opcode::Substr => 0x7f,
+            Opcode::Left => 0x80,
+            Opcode::Right => 0x81,
+            Opcode::Size => 0x82,
+            Opcode::Invert => 0x83,
+            Opcode::And => 0x84,
+            Opcode::Or => 0x85,
+            Opcode::Xor => 0x86,
+            Opcode::Equal => 0x87,
+            Opcode::EqualVerify => 0x88,
+            Opcode::Reserved1 => 0x89,
+            Opcode::Reserved2 => 0x8a,
+            Opcode::Add1 => 0x8b,
+            Opcode::Sub1 => 0x8c,
+            Opcode::Mul2 => 0x8d,
+            Opcode::Div2 => 0x8e,
+            Opcode::Negate => 0x8f,
+            Opcode::Abs => 0x90,
+            Opcode::Not => 0x91,
+            Opcode::NotEqual0 => 0x92,
+            Opcode::Add => 0x93,
+            Opcode::Sub => 0x94,
+            Opcode::Mul => 0x95,
+            Opcode::Div => 0x96,
+            Opcode::Mod => 0x97,
+            Opcode::LShift => 0x98,
+            Opcode::RShift => 0x99,
+            Opcode::BoolAnd => 0x9a,
+            Opcode::BoolOr => 0x9b,
+            Opcode::NumEqual => 0x9c,
+            Opcode::NumEqualVerify => 0x9d,
+            Opcode::NumNotEqual => 0x9e,
+            Opcode::LessThan => 0x9f,
+            Opcode::GreaterThan => 0xa0,
+            Opcode::LessThanOrEqual => 0xa1,
+            Opcode::GreaterThanOrEqual => 0xa2,
+            Opcode::Min => 0xa3,
+            Opcode::Max => 0xa4,
+            Opcode::Within => 0xa5,
+            Opcode::Ripemd160 => 0xa6,
+            Opcode::Sha1 => 0xa7,
+            Opcode::Sha256 => 0xa8,
+            Opcode::Hash160 => 0xa9,
+            Opcode::Hash256 => 0xaa,
+            Opcode::CodeSeparator => 0xab,
+            Opcode::CheckSig => 0xac,
+            Opcode::CheckSigVerify => 0xad,
+            Opcode::CheckMultiSig => 0xae,
+            Opcode::CheckMultiSigVerify => 0xaf,
+            Opcode::Nop1 => 0xb0,
+            Opcode::CheckLockTimeVerify => 0xb1,
+            Opcode::CheckSequenceVerify => 0xb2,
+            Opcode::Nop4 => 0xb3,
+            Opcode::Nop5 => 0xb4,
+            Opcode::Nop6 => 0xb5,
+            Opcode::Nop7 => 0xb6,
+            Opcode::Nop8 => 0xb7,
+            Opcode::Nop9 => 0xb8,
+            Opcode::Nop10 => 0xb9,
+            Opcode::CheckSigAdd => 0xba,
+            Opcode::Unknown(value) => value,
+            Opcode::InvalidOpcode => 0xff,
+        }
+    }
+
+    /// Name as displayed by Core, the small numbers are displayed as their value.
+    pub fn name(self) -> &'static str {
+        match self {
+            Opcode::Op0 => "0",
+            Opcode::Op1Negate => "-1",
+            Opcode::Op1 => "1",
+            Opcode::Op2 => "2",
+            Opcode::Op3 => "3",
+            Opcode::Op4 => "4",
+            Opcode::Op5 => "5",
+            Opcode::Op6 => "6",
+            Opcode::Op7 => "7",
+            Opcode::Op8 => "8",
+            Opcode::Op9 => "9",
+            Opcode::Op10 => "10",
+            Opcode::Op11 => "11",
+            Opcode::Op12 => "12",
+            Opcode::Op13 => "13",
+            Opcode::Op14 => "14",
+            Opcode::Op15 => "15",
+            Opcode::Op16 => "16",
+            Opcode::PushData1 => "OP_PUSHDATA1",
+            Opcode::PushData2 => "OP_PUSHDATA2",
+            Opcode::PushData4 => "OP_PUSHDATA4",
+            Opcode::Reserved => "OP_RESERVED",
+            Opcode::Nop => "OP_NOP",
+            Opcode::Ver => "OP_VER",
+            Opcode::If => "OP_IF",
+            Opcode::NotIf => "OP_NOTIF",
+            Opcode::VerIf => "OP_VERIF",
+            Opcode::VerNotIf => "OP_VERNOTIF",
+            Opcode::Else => "OP_ELSE",
+            Opcode::EndIf => "OP_ENDIF",
+            Opcode::Verify => "OP_VERIFY",
+            Opcode::Return => "OP_RETURN",
+            Opcode::ToAltStack => "OP_TOALTSTACK",
+            Opcode::FromAltStack => "OP_FROMALTSTACK",
+            Opcode::Drop2 => "OP_2DROP",
+            Opcode::Dup2 => "OP_2DUP",
+            Opcode::Dup3 => "OP_3DUP",
+            Opcode::Over2 => "OP_2OVER",
+            Opcode::Rot2 => "OP_2ROT",
+            Opcode::Swap2 => "OP_2SWAP",
+            Opcode::IfDup => "OP_IFDUP",
+            Opcode::Depth => "OP_DEPTH",
+            Opcode::Drop => "OP_DROP",
+            Opcode::Dup => "OP_DUP",
+            Opcode::Nip => "OP_NIP",
+            Opcode::Over => "OP_OVER",
+            Opcode::Pick => "OP_PICK",
+            Opcode::Roll => "OP_ROLL",
+            Opcode::Rot => "OP_ROT",
+            Opcode::Swap => "OP_SWAP",
+            Opcode::Tuck => "OP_TUCK",
+            Opcode::Cat => "OP_CAT",
+            Opcode::Substr => "OP_SUBSTR",
+            Opcode::Left => "OP_LEFT",
+            Opcode::Right => "OP_RIGHT",
+            Opcode::Size => "OP_SIZE",
+            Opcode::Invert => "OP_INVERT",
+            Opcode::And => "OP_AND",
+            Opcode::Or => "OP_OR",
+            Opcode::Xor => "OP_XOR",
+            Opcode::Equal => "OP_EQUAL",
+            Opcode::EqualVerify => "OP_EQUALVERIFY",
+            Opcode::Reserved1 => "OP_RESERVED1",
+            Opcode::Reserved2 => "OP_RESERVED2",
+            Opcode::Add1 => "OP_1ADD",
+            Opcode::Sub1 => "OP_1SUB",
+            Opcode::Mul2 => "OP_2MUL",
+            Opcode::Div2 => "OP_2DIV",
+            Opcode::Negate => "OP_NEGATE",
+            Opcode::Abs => "OP_ABS",
+            Opcode::Not => "OP_NOT",
+            Opcode::NotEqual0 => "OP_0NOTEQUAL",
+            Opcode::Add => "OP_ADD",
+            Opcode::Sub => "OP_SUB",
+            Opcode::Mul => "OP_MUL",
+            Opcode::Div => "OP_DIV",
+            Opcode::Mod => "OP_MOD",
+            Opcode::LShift => "OP_LSHIFT",
+            Opcode::RShift => "OP_RSHIFT",
+            Opcode::BoolAnd => "OP_BOOLAND",
+            Opcode::BoolOr => "OP_BOOLOR",
+            Opcode::NumEqual => "OP_NUMEQUAL",
+            Opcode::NumEqualVerify => "OP_NUMEQUALVERIFY",
+            Opcode::NumNotEqual => "OP_NUMNOTEQUAL",
+            Opcode::LessThan => "OP_LESSTHAN",
+            Opcode::GreaterThan => "OP_GREATERTHAN",
+            Opcode::LessThanOrEqual => "OP_LESSTHANOREQUAL",
+            Opcode::GreaterThanOrEqual => "OP_GREATERTHANOREQUAL",
+            Opcode::Min => "OP_MIN",
+            Opcode::Max => "OP_MAX",
+            Opcode::Within => "OP_WITHIN",
+            Opcode::Ripemd160 => "OP_RIPEMD160",
+            Opcode::Sha1 => "OP_SHA1",
+            Opcode::Sha256 => "OP_SHA256",
+            Opcode::Hash160 => "OP_HASH160",
+            Opcode::Hash256 => "OP_HASH256",
+            Opcode::CodeSeparator => "OP_CODESEPARATOR",
+            Opcode::CheckSig => "OP_CHECKSIG",
+            Opcode::CheckSigVerify => "OP_CHECKSIGVERIFY",
+            Opcode::CheckMultiSig => "OP_CHECKMULTISIG",
+            Opcode::CheckMultiSigVerify => "OP_CHECKMULTISIGVERIFY",
+            Opcode::Nop1 => "OP_NOP1",
+            Opcode::CheckLockTimeVerify => "OP_CHECKLOCKTIMEVERIFY",
+            Opcode::CheckSequenceVerify => "OP_CHECKSEQUENCEVERIFY",
+            Opcode::Nop4 => "OP_NOP4",
+            Opcode::Nop5 => "OP_NOP5",
+            Opcode::Nop6 => "OP_NOP6",
+            Opcode::Nop7 => "OP_NOP7",
+            Opcode::Nop8 => "OP_NOP8",
+            Opcode::Nop9 => "OP_NOP9",
+            Opcode::Nop10 => "OP_NOP10",
+            Opcode::CheckSigAdd => "OP_CHECKSIGADD",
+            Opcode::InvalidOpcode => "OP_INVALIDOPCODE",
+            Opcode::PushBytes(_) => "OP_UNKNOWN",
+            Opcode::Unknown(_) => "OP_UNKNOWN",
+        }
+    }
+
+    /// Opcode of a name, with or without the `OP_` prefix, as read by Core `ParseScript`.
+    pub fn from_name(name: &str) -> Option<Opcode> {
+
+        let name = name.strip_prefix("OP_").unwrap_or(name);
+        match name {
+            "0" | "FALSE" => return Some(Opcode::Op0),
+            "1NEGATE" => return Some(Opcode::Op1Negate),
+            "TRUE" => return Some(Opcode::Op1),
+            _ => {}
+        }
+        if let Ok(value) = name.parse::<i64>() {
+            return match value {
+                1..=16 => Opcode::from_small_int(value),
+                _ => None,
+            };
+        }
+
+        (0x4c..=0xffu8)
+            .map(Opcode::from_u8)
+            .filter(|opcode| !opcode.is_unknown())
+            .find(|opcode| opcode.name().starts_with("OP_") && &opcode.name()[3..] == name)
+    }
+
+    /// Pushes data: OP_0, the direct pushes and OP_PUSHDATA1/2/4.
+    pub fn is_push(self) -> bool {
+        self.to_u8() <= Opcode::PushData4.to_u8()
+    }
+
+    fn is_unknown(self) -> bool {
+        matches!(self, Opcode::PushBytes(_) | Opcode::Unknown(_))
+    }
+
+    /// Value of OP_0, OP_1NEGATE and OP_1 to OP_16.
+    pub fn small_int(self) -> Option<i64> {
+        match self {
+            Opcode::Op0 => Some(0),
+            Opcode::Op1Negate => Some(-1),
+            _ => {
+                let value = self.to_u8();
+                if value >= Opcode::Op1.to_u8() && value <= Opcode::Op16.to_u8() {
+                    Some((value - Opcode::Op1.to_u8() + 1) as i64)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Opcode pushing -1 to 16, the values not needing any data.
+    pub fn from_small_int(value: i64) -> Option<Opcode> {
+        match value {
+            0 => Some(Opcode::Op0),
+            -1 => Some(Opcode::Op1Negate),
+            1..=16 => Some(Opcode::from_u8(Opcode::Op1.to_u8() + value as u8 - 1)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::opcode::Opcode;
+
+    #[test]
+    fn when_every_byte_then_same_byte() {
+        for value in 0..=0xffu8 {
+            assert_eq!(Opcode::from_u8(value).to_u8(), value);
+        }
+    }
+
+    #[test]
+    fn when_name_then_same_as_core() {
+        assert_eq!(Opcode::Op0.name(), "0");
+        assert_eq!(Opcode::Op1Negate.name(), "-1");
+        assert_eq!(Opcode::Op16.name(), "16");
+        assert_eq!(Opcode::Dup.name(), "OP_DUP");
+        assert_eq!(Opcode::CheckLockTimeVerify.name(), "OP_CHECKLOCKTIMEVERIFY");
+        assert_eq!(Opcode::Unknown(0xbb).name(), "OP_UNKNOWN");
+        assert_eq!(Opcode::InvalidOpcode.name(), "OP_INVALIDOPCODE");
+    }
+
+    #[test]
+    fn when_from_name_then_opcode() {
+        assert_eq!(Opcode::from_name("OP_CHECKSIG"), Some(Opcode::CheckSig));
+        assert_eq!(Opcode::from_name("CHECKSIG"), Some(Opcode::CheckSig));
+        assert_eq!(Opcode::from_name("NOP2"), None);
+        assert_eq!(Opcode::from_name("CHECKLOCKTIMEVERIFY"), Some(Opcode::CheckLockTimeVerify));
+        assert_eq!(Opcode::from_name("OP_0"), Some(Opcode::Op0));
+        assert_eq!(Opcode::from_name("OP_16"), Some(Opcode::Op16));
+        assert_eq!(Opcode::from_name("OP_17"), None);
+        assert_eq!(Opcode::from_name("OP_UNKNOWN"), None);
+        assert_eq!(Opcode::from_name("OP_PUSHDATA1"), Some(Opcode::PushData1));
+    }
+
+    #[test]
+    fn when_small_int_then_value() {
+        assert_eq!(Opcode::Op0.small_int(), Some(0));
+        assert_eq!(Opcode::Op1Negate.small_int(), Some(-1));
+        assert_eq!(Opcode::Op7.small_int(), Some(7));
+        assert_eq!(Opcode::Nop.small_int(), None);
+        assert_eq!(Opcode::from_small_int(16), Some(Opcode::Op16));
+        assert_eq!(Opcode::from_small_int(17), None);
+        assert!(Opcode::PushData4.is_push());
+        assert!(!Opcode::Op1Negate.is_push());
+    }
+}
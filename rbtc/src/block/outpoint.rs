@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 // https://github.com/bitcoin/bitcoin/blob/master/src/primitives/transaction.h
@@ -13,19 +13,39 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 //     uint256 hash;
 //     uint32_t n;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct OutPoint {
     pub transaction_hash: [u8; 32],
     pub index: u32,
 }
 
+impl OutPoint {
+
+    /// The outpoint spent by a coinbase, a zero hash and the index 0xffffffff.
+    pub fn is_null(&self) -> bool {
+        self.transaction_hash == [0; 32] && self.index == u32::MAX
+    }
+}
+
+impl Encodable for OutPoint {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.transaction_hash.encode(w).map_err(|e| Error::OutPointTransactionHash.caused_by(e))?;
+        self.index.encode(w).map_err(|e| Error::OutPointIndex.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
 impl Decodable for OutPoint {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<OutPoint, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<OutPoint, Error> {
         
         trace!("decode");
 
-        let transaction_hash = <[u8; 32]>::decode(r).map_err(|_| Error::OutPointTransactionHash)?;
-        let index = u32::decode(r).map_err(|_| Error::OutPointIndex)?;
+        let transaction_hash = <[u8; 32]>::decode(r).map_err(|e| Error::OutPointTransactionHash.caused_by(e))?;
+        let index = u32::decode(r).map_err(|e| Error::OutPointIndex.caused_by(e))?;
 
         let result = OutPoint {
             transaction_hash: transaction_hash,
@@ -0,0 +1,162 @@
+//! https://en.bitcoin.it/wiki/Target
+//!
+//! # Proof of work
+//!
+//! A block is valid when its hash, read as a 256 bits number, is lower or equal to
+//! the target encoded in the header `bits`. The target itself must be positive and
+//! no higher than the network proof of work limit.
+//!
+//! The work of a block is the expected number of hashes needed to find it,
+//! `2^256 / (target + 1)`, and the chain with the most cumulated work is the best chain.
+//!
+// https://github.com/bitcoin/bitcoin/blob/master/src/pow.cpp
+// https://github.com/bitcoin/bitcoin/blob/master/src/chain.cpp GetBlockProof
+
+use crate::encode::error::Error;
+use crate::chain::params::ConsensusParams;
+use crate::utils::sha256::Sha256;
+use crate::utils::uint256::Uint256;
+
+/// Decodes `bits` into a target, rejecting the negative, overflowing and zero encodings.
+pub fn target(bits: u32) -> Result<Uint256, Error> {
+
+    let (target, negative, overflow) = Uint256::from_compact(bits);
+    if negative {
+        return Err(Error::PowTargetNegative);
+    }
+    if overflow {
+        return Err(Error::PowTargetOverflow);
+    }
+    if target.is_zero() {
+        return Err(Error::PowTargetZero);
+    }
+    Ok(target)
+}
+
+/// Work represented by a block with the given `bits`, zero when the bits are invalid.
+pub fn work(bits: u32) -> Uint256 {
+
+    match target(bits) {
+        // 2^256 / (target + 1) does not fit, but it is equal to ~target / (target + 1) + 1
+        Ok(target) => (!target / (target + Uint256::from_u64(1))) + Uint256::from_u64(1),
+        Err(_) => Uint256::zero(),
+    }
+}
+
+/// Difficulty as displayed by Core, how many times harder than the 0x1d00ffff target.
+pub fn difficulty(bits: u32) -> f64 {
+
+    let mut shift = (bits >> 24) & 0xff;
+    let mut difficulty = 0x0000ffff as f64 / (bits & 0x00ffffff) as f64;
+    while shift < 29 {
+        difficulty *= 256.0;
+        shift += 1;
+    }
+    while shift > 29 {
+        difficulty /= 256.0;
+        shift -= 1;
+    }
+    difficulty
+}
+
+/// Checks the hash against the target of `bits`, the target being capped by `pow_limit`.
+pub fn check_proof_of_work(hash: &Sha256, bits: u32, pow_limit: &Uint256) -> Result<(), Error> {
+
+    let target = target(bits)?;
+    if target > *pow_limit {
+        return Err(Error::PowTargetAboveLimit);
+    }
+    if Uint256::from_hash(hash) > target {
+        return Err(Error::PowHashAboveTarget);
+    }
+    Ok(())
+}
+
+/// Target of the first block of a difficulty period, from the last block of the previous
+/// period and the time of its first block. The timespan is clamped to a factor of 4 either way.
+pub fn calculate_next_work_required(last_bits: u32, last_time: u32, first_time: u32, params: &ConsensusParams) -> u32 {
+
+    if params.pow_no_retargeting {
+        return last_bits;
+    }
+
+    let timespan = params.pow_target_timespan as i64;
+    let mut actual = last_time as i64 - first_time as i64;
+    if actual < timespan / 4 {
+        actual = timespan / 4;
+    }
+    if actual > timespan * 4 {
+        actual = timespan * 4;
+    }
+
+    let (target, _, _) = Uint256::from_compact(last_bits);
+    let mut next = target * actual as u64 / Uint256::from_u64(timespan as u64);
+    if next > params.pow_limit {
+        next = params.pow_limit;
+    }
+    next.to_compact(false)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::pow;
+    use crate::chain::params::ConsensusParams;
+    use crate::encode::error::Error;
+    use crate::utils::sha256::Sha256;
+    use crate::utils::uint256::Uint256;
+
+    use std::str::FromStr;
+
+    fn mainnet_limit() -> Uint256 {
+        Uint256::from_str("00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap()
+    }
+
+    #[test]
+    fn when_target_invalid_then_error() {
+        assert_eq!(pow::target(0x04923456), Err(Error::PowTargetNegative));
+        assert_eq!(pow::target(0xff123456), Err(Error::PowTargetOverflow));
+        assert_eq!(pow::target(0x01003456), Err(Error::PowTargetZero));
+    }
+
+    #[test]
+    fn when_genesis_bits_then_work() {
+        assert_eq!(pow::work(0x1d00ffff), Uint256::from_u64(0x100010001));
+        assert_eq!(pow::work(0x04923456), Uint256::zero());
+    }
+
+    #[test]
+    fn when_difficulty_then_same_as_core() {
+        assert_eq!(pow::difficulty(0x1d00ffff), 1.0);
+        assert!((pow::difficulty(0x1b0404cb) - 16307.420938523983).abs() < 1e-9);
+        assert!((pow::difficulty(0x12345678) - 5913134931067755359633408.0).abs() / 5913134931067755359633408.0 < 1e-12);
+    }
+
+    #[test]
+    fn when_check_proof_of_work_then_compare_with_target() {
+        let genesis = Sha256::from_str("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+        assert_eq!(pow::check_proof_of_work(&genesis, 0x1d00ffff, &mainnet_limit()), Ok(()));
+        assert_eq!(pow::check_proof_of_work(&genesis, 0x1b0404cb, &mainnet_limit()), Err(Error::PowHashAboveTarget));
+        assert_eq!(pow::check_proof_of_work(&genesis, 0x1d01ffff, &mainnet_limit()), Err(Error::PowTargetAboveLimit));
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/pow_tests.cpp
+    #[test]
+    fn when_calculate_next_work_required_then_same_as_core() {
+        let params = ConsensusParams::mainnet();
+        // block 32255 to 32256
+        assert_eq!(pow::calculate_next_work_required(0x1d00ffff, 1262152739, 1261130161, &params), 0x1d00d86a);
+        // capped by the pow limit
+        assert_eq!(pow::calculate_next_work_required(0x1d00ffff, 1233061996, 1231006505, &params), 0x1d00ffff);
+        // timespan clamped to a quarter
+        assert_eq!(pow::calculate_next_work_required(0x1c05a3f4, 1279297671, 1279008237, &params), 0x1c0168fd);
+        // timespan clamped to four times
+        assert_eq!(pow::calculate_next_work_required(0x1c387f6f, 1269211443, 1263163443, &params), 0x1d00e1fd);
+    }
+
+    #[test]
+    fn when_no_retargeting_then_same_bits() {
+        let params = ConsensusParams::regtest();
+        assert_eq!(pow::calculate_next_work_required(0x207fffff, 1296688602, 1296688602, &params), 0x207fffff);
+    }
+}
@@ -1,19 +1,382 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
-
-use std::io::{Read, Write, Cursor};
-
-#[derive(Debug)]
+use crate::encode::varint::VarInt;
+use crate::block::opcode::Opcode;
+use crate::block::interpreter::MAX_SCRIPT_SIZE;
+use crate::block::standard::ScriptType;
+
+use std::fmt;
+use std::io::{Read, Write};
+
+/// https://en.bitcoin.it/wiki/Script
+///
+/// # Script
+///
+/// A list of instructions recorded with each transaction output (scriptPubKey) and
+/// input (scriptSig), describing how the next person wanting to spend the output
+/// can gain access to it.
+///
+/// Displayed as Core `asm`: the pushes of up to 4 bytes as numbers, the longer ones
+/// as hex, and the other opcodes by name.
+///
+/// ```
+/// 76a914...88ac  <=>  OP_DUP OP_HASH160 62e907b15cbf27d5425399ebf6f0fb50ebb88f18 OP_EQUALVERIFY OP_CHECKSIG
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Script {
     pub content: Vec<u8>
 }
 
+/// One opcode of a script, with the data it pushes, empty for the non push opcodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction<'a> {
+    pub opcode: Opcode,
+    pub data: &'a [u8],
+}
+
+/// Keys of a OP_CHECKMULTISIG counted as signature operations when not known
+const MAX_PUBKEYS_PER_MULTISIG : usize = 20;
+
+/// Iterates the instructions of a script. A push running past the end of the
+/// script yields `Error::ScriptPushTruncated`, then the iteration stops.
+pub struct Instructions<'a> {
+    content: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Instructions<'a> {
+
+    pub fn new(content: &'a [u8]) -> Instructions<'a> {
+        Instructions {
+            content: content,
+            position: 0,
+        }
+    }
+
+    /// Offset of the next instruction in the script.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn read_len(&mut self, size: usize) -> Result<usize, Error> {
+
+        if self.content.len() - self.position < size {
+            return Err(Error::ScriptPushTruncated);
+        }
+        let mut len : usize = 0;
+        for (i, byte) in self.content[self.position..self.position + size].iter().enumerate() {
+            len |= (*byte as usize) << (8 * i);
+        }
+        self.position += size;
+        Ok(len)
+    }
+
+    fn next_instruction(&mut self) -> Result<Instruction<'a>, Error> {
+
+        let opcode = Opcode::from_u8(self.content[self.position]);
+        self.position += 1;
+
+        let len = match opcode {
+            Opcode::PushBytes(len) => len as usize,
+            Opcode::PushData1 => self.read_len(1)?,
+            Opcode::PushData2 => self.read_len(2)?,
+            Opcode::PushData4 => self.read_len(4)?,
+            _ => 0,
+        };
+        if self.content.len() - self.position < len {
+            return Err(Error::ScriptPushTruncated);
+        }
+
+        let data = &self.content[self.position..self.position + len];
+        self.position += len;
+
+        let result = Instruction {
+            opcode: opcode,
+            data: data,
+        };
+        Ok(result)
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        if self.position >= self.content.len() {
+            return None;
+        }
+        let result = self.next_instruction();
+        if result.is_err() {
+            self.position = self.content.len();
+        }
+        Some(result)
+    }
+}
+
+impl Script {
+
+    pub fn new(content: Vec<u8>) -> Script {
+        Script {
+            content: content
+        }
+    }
+
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions::new(&self.content)
+    }
+
+    pub fn push_opcode(&mut self, opcode: Opcode) {
+        self.content.push(opcode.to_u8());
+    }
+
+    /// Pushes the data with the smallest push opcode.
+    pub fn push_data(&mut self, data: &[u8]) {
+
+        let len = data.len();
+        if len < Opcode::PushData1.to_u8() as usize {
+            self.content.push(len as u8);
+        } else if len <= 0xff {
+            self.content.push(Opcode::PushData1.to_u8());
+            self.content.push(len as u8);
+        } else if len <= 0xffff {
+            self.content.push(Opcode::PushData2.to_u8());
+            self.content.extend_from_slice(&(len as u16).to_le_bytes());
+        } else {
+            self.content.push(Opcode::PushData4.to_u8());
+            self.content.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+        self.content.extend_from_slice(data);
+    }
+
+    /// Pushes a number, with OP_1NEGATE, OP_0 and OP_1 to OP_16 when possible.
+    pub fn push_int(&mut self, value: i64) {
+        match Opcode::from_small_int(value) {
+            Some(opcode) => self.push_opcode(opcode),
+            None => self.push_data(&encode_num(value)),
+        }
+    }
+
+    /// Kind of output script, with the keys, hashes or data it holds.
+    pub fn classify(&self) -> ScriptType {
+        ScriptType::from_script(self)
+    }
+
+    /// OP_HASH160 <20 bytes> OP_EQUAL, BIP16.
+    pub fn is_p2sh(&self) -> bool {
+        self.content.len() == 23
+            && self.content[0] == Opcode::Hash160.to_u8()
+            && self.content[1] == 20
+            && self.content[22] == Opcode::Equal.to_u8()
+    }
+
+    /// Starts with OP_RETURN or is over 10 000 bytes, no scriptSig can spend it and the
+    /// output never enters the UTXO set.
+    pub fn is_unspendable(&self) -> bool {
+        self.content.first() == Some(&Opcode::Return.to_u8()) || self.content.len() > MAX_SCRIPT_SIZE
+    }
+
+    /// Only pushes data, OP_RESERVED and OP_1 to OP_16 being counted as pushes.
+    pub fn is_push_only(&self) -> bool {
+        self.instructions().all(|instruction| match instruction {
+            Ok(instruction) => instruction.opcode.to_u8() <= Opcode::Op16.to_u8(),
+            Err(_) => false,
+        })
+    }
+
+    /// Signature operations of the script: 1 for OP_CHECKSIG(VERIFY), 20 for
+    /// OP_CHECKMULTISIG(VERIFY), or the key count pushed by the OP_1 to OP_16 just before
+    /// when `accurate`. A truncated push stops the count.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/script/script.cpp GetSigOpCount
+    pub fn sigop_count(&self, accurate: bool) -> usize {
+
+        let mut result = 0;
+        let mut last = Opcode::InvalidOpcode;
+        for instruction in self.instructions() {
+            let opcode = match instruction {
+                Ok(instruction) => instruction.opcode,
+                Err(_) => break,
+            };
+            match opcode {
+                Opcode::CheckSig | Opcode::CheckSigVerify => result += 1,
+                Opcode::CheckMultiSig | Opcode::CheckMultiSigVerify => {
+                    result += match last.small_int() {
+                        Some(keys) if accurate && keys >= 1 => keys as usize,
+                        _ => MAX_PUBKEYS_PER_MULTISIG,
+                    };
+                },
+                _ => {},
+            }
+            last = opcode;
+        }
+        result
+    }
+
+    /// The data of the last instruction of a push only script, empty for OP_0 and OP_1
+    /// to OP_16, like the redeem script of a P2SH spend. None when the script is not push only.
+    pub fn last_push(&self) -> Option<&[u8]> {
+
+        let mut result : &[u8] = &[];
+        for instruction in self.instructions() {
+            match instruction {
+                Ok(instruction) if instruction.opcode.to_u8() <= Opcode::Op16.to_u8() => result = instruction.data,
+                _ => return None,
+            }
+        }
+        Some(result)
+    }
+
+    /// Version and program of a witness program, BIP141: a version opcode,
+    /// OP_0 or OP_1 to OP_16, followed by a single push of 2 to 40 bytes.
+    pub fn witness_program(&self) -> Option<(u8, &[u8])> {
+
+        let content = &self.content;
+        if content.len() < 4 || content.len() > 42 {
+            return None;
+        }
+        let version = match Opcode::from_u8(content[0]).small_int() {
+            Some(version) if version >= 0 => version as u8,
+            _ => return None,
+        };
+        if content[1] as usize + 2 != content.len() {
+            return None;
+        }
+        Some((version, &content[2..]))
+    }
+
+    /// Reads a script displayed as Core `asm`. A token of digits without a leading zero
+    /// is a number when it fits in 4 bytes, like Core displays the pushes of up to 4
+    /// bytes. Otherwise hex tokens are pushed as data and the others are read as opcode
+    /// names.
+    pub fn from_asm(asm: &str) -> Result<Script, Error> {
+
+        trace!("from_asm");
+
+        let mut result = Script::default();
+        for token in asm.split_whitespace() {
+
+            if let Some(value) = parse_asm_num(token) {
+                result.push_int(value);
+            } else if let Some(opcode) = Opcode::from_name(token) {
+                result.push_opcode(opcode);
+            } else if token.len() % 2 == 0 {
+                let data = hex::decode(token).map_err(|e| Error::ScriptAsm.caused_by(e))?;
+                result.push_data(&data);
+            } else {
+                return Err(Error::ScriptAsm);
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn parse_asm_num(token: &str) -> Option<i64> {
+
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // a number is displayed without leading zero, the hex of a push may start with one
+    if digits.len() > 1 && digits.starts_with('0') {
+        return None;
+    }
+    match token.parse::<i64>() {
+        Ok(value) if (-0x7fffffff..=0x7fffffff).contains(&value) => Some(value),
+        _ => None,
+    }
+}
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/script/script.h CScriptNum::serialize
+///
+/// Numbers are little endian, with the sign in the highest bit of the last byte,
+/// zero being the empty array.
+pub fn encode_num(value: i64) -> Vec<u8> {
+
+    let mut result = Vec::new();
+    if value == 0 {
+        return result;
+    }
+
+    let negative = value < 0;
+    let mut absolute = value.unsigned_abs();
+    while absolute > 0 {
+        result.push((absolute & 0xff) as u8);
+        absolute >>= 8;
+    }
+
+    let last = result.len() - 1;
+    if result[last] & 0x80 != 0 {
+        result.push(if negative { 0x80 } else { 0x00 });
+    } else if negative {
+        result[last] |= 0x80;
+    }
+    result
+}
+
+/// Reads a number, without checking it is minimally encoded.
+pub fn decode_num(data: &[u8]) -> i64 {
+
+    if data.is_empty() {
+        return 0;
+    }
+
+    let mut result : i64 = 0;
+    for (i, byte) in data.iter().enumerate() {
+        result |= (*byte as i64) << (8 * i);
+    }
+
+    let last = data.len() - 1;
+    if data[last] & 0x80 != 0 {
+        return -(result & !(0x80 << (8 * last)));
+    }
+    result
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        for (i, instruction) in self.instructions().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match instruction {
+                Err(_) => return write!(f, "[error]"),
+                Ok(instruction) => {
+                    if !instruction.opcode.is_push() {
+                        write!(f, "{}", instruction.opcode)?;
+                    } else if instruction.data.len() <= 4 {
+                        write!(f, "{}", decode_num(instruction.data))?;
+                    } else {
+                        write!(f, "{}", hex::encode(instruction.data))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Encodable for Script {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let varint = VarInt::new(self.content.len() as u64);
+        varint.encode(w).map_err(|e| Error::Script.caused_by(e))?;
+        self.content.encode(w).map_err(|e| Error::Script.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
 impl Decodable for Script {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Script, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Script, Error> {
 
         trace!("decode");
 
-        let content = <Vec<u8>>::decode(r).map_err(|_| Error::Script)?;
+        let content = <Vec<u8>>::decode(r).map_err(|e| Error::Script.caused_by(e))?;
         let result = Script {
             content: content
         };
@@ -28,15 +391,195 @@ mod test {
 
     use crate::encode::error::Error;
     use crate::encode::encode::{Encodable, Decodable};
-    use crate::block::script::Script;
+    use crate::block::script::{Script, Instruction, encode_num, decode_num};
+    use crate::block::opcode::Opcode;
+    use crate::chain::chainparams::ChainParams;
 
     use std::io::Cursor;
 
+    #[test]
+    fn when_p2pkh_then_asm() {
+
+        let script = Script::new(hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap());
+        let asm = "OP_DUP OP_HASH160 62e907b15cbf27d5425399ebf6f0fb50ebb88f18 OP_EQUALVERIFY OP_CHECKSIG";
+        assert_eq!(script.to_string(), asm);
+        assert_eq!(Script::from_asm(asm), Ok(script));
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/sigopcount_tests.cpp
+    #[test]
+    fn when_sigop_count_then_multisig_accurate_or_20() {
+
+        let script = Script::from_asm("OP_CHECKSIG OP_CHECKSIGVERIFY").unwrap();
+        assert_eq!(script.sigop_count(false), 2);
+
+        let multisig = Script::from_asm("1 0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798 2 OP_CHECKMULTISIG").unwrap();
+        assert_eq!(multisig.sigop_count(true), 2);
+        assert_eq!(multisig.sigop_count(false), 20);
+
+        // the key count is not a small int, 0 keys is counted as 20
+        assert_eq!(Script::from_asm("17 OP_CHECKMULTISIGVERIFY").unwrap().sigop_count(true), 20);
+        assert_eq!(Script::from_asm("0 OP_CHECKMULTISIG").unwrap().sigop_count(true), 20);
+
+        // counting stops at the truncated push
+        assert_eq!(Script::new(vec![0xac, 0x4c, 0x02, 0xac]).sigop_count(false), 1);
+    }
+
+    #[test]
+    fn when_push_only_then_last_push() {
+
+        assert_eq!(Script::from_asm("0 ac51").unwrap().last_push(), Some(&[0xac, 0x51][..]));
+        assert_eq!(Script::from_asm("ac51 16").unwrap().last_push(), Some(&[][..]));
+        assert_eq!(Script::default().last_push(), Some(&[][..]));
+        assert_eq!(Script::from_asm("ac51 OP_NOP").unwrap().last_push(), None);
+        assert_eq!(Script::new(vec![0x4c]).last_push(), None);
+    }
+
+    #[test]
+    fn when_op_return_or_oversize_then_unspendable() {
+
+        assert!(Script::from_asm("OP_RETURN 89abcdef01").unwrap().is_unspendable());
+        assert!(Script::new(vec![0x51; 10_001]).is_unspendable());
+        assert!(!Script::new(vec![0x51; 10_000]).is_unspendable());
+        assert!(!Script::from_asm("1 OP_RETURN").unwrap().is_unspendable());
+    }
+
+    #[test]
+    fn when_genesis_coinbase_then_asm() {
+
+        let block = ChainParams::mainnet().genesis_block();
+        let tx = block.transactions.get(0).unwrap();
+        assert_eq!(tx.inputs[0].signature.to_string(), "486604799 4 5468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73");
+        assert_eq!(tx.outputs[0].script_pubkey.to_string(), "04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f OP_CHECKSIG");
+    }
+
+    #[test]
+    fn when_push_forms_then_instructions() {
+
+        let mut content = vec![0x00, 0x02, 0xAB, 0xCD, 0x4c, 0x01, 0xEF, 0x4d, 0x01, 0x00, 0x12, 0x4e, 0x01, 0x00, 0x00, 0x00, 0x34];
+        content.extend(vec![0x4f, 0x51, 0x60, 0x87, 0xbb]);
+        let script = Script::new(content);
+
+        let instructions : Vec<Instruction> = script.instructions().map(|i| i.unwrap()).collect();
+        assert_eq!(instructions, vec![
+            Instruction { opcode: Opcode::Op0, data: &[] },
+            Instruction { opcode: Opcode::PushBytes(2), data: &[0xAB, 0xCD] },
+            Instruction { opcode: Opcode::PushData1, data: &[0xEF] },
+            Instruction { opcode: Opcode::PushData2, data: &[0x12] },
+            Instruction { opcode: Opcode::PushData4, data: &[0x34] },
+            Instruction { opcode: Opcode::Op1Negate, data: &[] },
+            Instruction { opcode: Opcode::Op1, data: &[] },
+            Instruction { opcode: Opcode::Op16, data: &[] },
+            Instruction { opcode: Opcode::Equal, data: &[] },
+            Instruction { opcode: Opcode::Unknown(0xbb), data: &[] },
+        ]);
+        assert_eq!(script.to_string(), "0 -19883 -111 18 52 -1 1 16 OP_EQUAL OP_UNKNOWN");
+    }
+
+    #[test]
+    fn when_push_truncated_then_error() {
+
+        for content in [
+            vec![0x02, 0xAB],
+            vec![0x4c],
+            vec![0x4c, 0x02, 0xAB],
+            vec![0x4d, 0x01],
+            vec![0x4d, 0x02, 0x00, 0xAB],
+            vec![0x4e, 0x01, 0x00, 0x00],
+            vec![0x4e, 0x02, 0x00, 0x00, 0x00, 0xAB],
+        ] {
+            let mut script = Script::new(vec![0x76]);
+            script.content.extend(content);
+
+            let mut instructions = script.instructions();
+            assert_eq!(instructions.next(), Some(Ok(Instruction { opcode: Opcode::Dup, data: &[] })));
+            assert_eq!(instructions.next(), Some(Err(Error::ScriptPushTruncated)));
+            assert_eq!(instructions.next(), None);
+            assert_eq!(script.to_string(), "OP_DUP [error]");
+        }
+    }
+
+    #[test]
+    fn when_push_data_then_smallest_opcode() {
+
+        for (len, prefix) in [
+            (0, vec![0x00]),
+            (75, vec![0x4b]),
+            (76, vec![0x4c, 0x4c]),
+            (256, vec![0x4d, 0x00, 0x01]),
+            (65536, vec![0x4e, 0x00, 0x00, 0x01, 0x00]),
+        ] {
+            let mut script = Script::default();
+            script.push_data(&vec![0xAB; len]);
+            assert_eq!(script.content[..prefix.len()], prefix[..]);
+            assert_eq!(script.content.len(), prefix.len() + len);
+
+            let instructions : Vec<Instruction> = script.instructions().map(|i| i.unwrap()).collect();
+            assert_eq!(instructions.len(), 1);
+            assert_eq!(instructions[0].data.len(), len);
+        }
+    }
+
+    #[test]
+    fn when_number_then_same_as_core() {
+
+        for (value, encoded) in vec![
+            (0, vec![]),
+            (1, vec![0x01]),
+            (-1, vec![0x81]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x00]),
+            (-128, vec![0x80, 0x80]),
+            (255, vec![0xff, 0x00]),
+            (256, vec![0x00, 0x01]),
+            (-32768, vec![0x00, 0x80, 0x80]),
+            (2147483647, vec![0xff, 0xff, 0xff, 0x7f]),
+            (-2147483647, vec![0xff, 0xff, 0xff, 0xff]),
+        ] {
+            assert_eq!(encode_num(value), encoded);
+            assert_eq!(decode_num(&encoded), value);
+        }
+    }
+
+    #[test]
+    fn when_from_asm_then_numbers_and_opcodes() {
+
+        let script = Script::from_asm("0 -1 16 17 -2 1000 NOP CHECKSIG OP_RETURN 0a0b0c0d0e").unwrap();
+        assert_eq!(hex::encode(&script.content), "004f600111018202e80361ac6a050a0b0c0d0e");
+        assert_eq!(script.to_string(), "0 -1 16 17 -2 1000 OP_NOP OP_CHECKSIG OP_RETURN 0a0b0c0d0e");
+
+        assert_eq!(Script::from_asm("OP_NOTANOPCODE"), Err(Error::ScriptAsm));
+        assert_eq!(Script::from_asm("123456789").unwrap().content, vec![0x04, 0x15, 0xcd, 0x5b, 0x07]);
+        assert_eq!(Script::from_asm("abc"), Err(Error::ScriptAsm));
+        assert_eq!(Script::from_asm(""), Ok(Script::default()));
+    }
+
+    #[test]
+    fn when_digit_only_data_push_then_asm_round_trip() {
+
+        let mut script = Script::default();
+        script.push_data(&[0x01, 0x02, 0x03, 0x04, 0x05]);
+        script.push_data(&[0x12, 0x34, 0x56, 0x78, 0x90, 0x12]);
+        assert_eq!(script.to_string(), "0102030405 123456789012");
+        assert_eq!(Script::from_asm(&script.to_string()), Ok(script));
+    }
+
+    #[test]
+    fn when_ten_digits_number_then_asm_round_trip() {
+
+        let mut script = Script::default();
+        script.push_int(1_500_000_000);
+        script.push_opcode(Opcode::CheckLockTimeVerify);
+        script.push_int(2_147_483_647);
+        assert_eq!(script.to_string(), "1500000000 OP_CHECKLOCKTIMEVERIFY 2147483647");
+        assert_eq!(Script::from_asm(&script.to_string()), Ok(script));
+    }
+
     #[test]
     fn decode_0x00_then_1_byte() {
 
         let data : Vec<u8> = vec![0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_ok());
         assert_eq!(c.position(), 1);
@@ -49,7 +592,7 @@ mod test {
     fn decode_0x01_then_1_byte() {
 
         let data : Vec<u8> = vec![0x01, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_ok());
         assert_eq!(c.position(), 2);
@@ -62,7 +605,7 @@ mod test {
     fn decode_0x02_then_2_byte() {
 
         let data : Vec<u8> = vec![0x02, 0x00, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_ok());
         assert_eq!(c.position(), 3);
@@ -75,7 +618,7 @@ mod test {
     fn decode_0x10_then_10_byte() {
 
         let data : Vec<u8> = vec![0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00 ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_ok());
         assert_eq!(c.position(), 0x11);
@@ -89,7 +632,7 @@ mod test {
     fn decode_invalid_size_then_fail() {
 
         let data : Vec<u8> = vec![0x01 ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_err());
         assert_eq!(c.position(), 0x01);
@@ -107,7 +650,7 @@ mod test {
     fn decode_invalid_content_then_fail() {
 
         let data : Vec<u8> = vec![ ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let parsescript = Script::decode(&mut c);
         assert!(parsescript.is_err());
 
@@ -118,4 +661,29 @@ mod test {
         }
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn encode_0x02_then_3_byte() {
+
+        let script = Script {
+            content: vec![0xAB, 0xCD]
+        };
+        let mut data : Vec<u8> = Vec::new();
+        let result = script.encode(&mut data);
+        assert!(result.is_ok());
+        assert_eq!(data, vec![0x02, 0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn encode_decode_then_same() {
+
+        let data : Vec<u8> = vec![0x03, 0x01, 0x02, 0x03];
+        let mut c = Cursor::new(&data);
+        let script = Script::decode(&mut c).unwrap();
+
+        let mut result : Vec<u8> = Vec::new();
+        let encoded = script.encode(&mut result);
+        assert!(encoded.is_ok());
+        assert_eq!(data, result);
+    }
+}
@@ -0,0 +1,616 @@
+use crate::encode::error::Error;
+use crate::encode::encode::Encodable;
+use crate::encode::varint::VarInt;
+use crate::block::opcode::Opcode;
+use crate::block::script::Script;
+use crate::block::transaction::Transaction;
+use crate::block::txout::TxOut;
+use crate::block::interpreter::{ExecutionData, SigVersion};
+use crate::utils::sha256::Sha256;
+
+/// Taproot only, signs like ALL without a hash type byte in the signature
+pub const SIGHASH_DEFAULT : u32 = 0x00;
+/// Signs all the inputs and outputs
+pub const SIGHASH_ALL : u32 = 0x01;
+/// Signs the inputs, none of the outputs
+pub const SIGHASH_NONE : u32 = 0x02;
+/// Signs the inputs and the output of the same index as the signed input
+pub const SIGHASH_SINGLE : u32 = 0x03;
+/// Combined with the above, signs only the input being signed
+pub const SIGHASH_ANYONECANPAY : u32 = 0x80;
+
+/// Bits of the hash type selecting ALL, NONE or SINGLE
+const SIGHASH_OUTPUT_MASK : u32 = 0x1f;
+/// Bits of the taproot hash type selecting ALL, NONE or SINGLE
+const TAPROOT_OUTPUT_MASK : u32 = 0x03;
+/// Version of the public keys of the tapscript signatures, BIP342
+const TAPROOT_KEY_VERSION : u8 = 0x00;
+
+/// https://en.bitcoin.it/wiki/OP_CHECKSIG
+///
+/// # Legacy signature hash
+///
+/// The double sha256 of a copy of the transaction, modified according to the hash type,
+/// the last byte of the signature, followed by the hash type on 4 bytes:
+///
+/// ```
+/// +----------------+-----------------------------------------------------------------+
+/// | Field          | Serialized                                                      |
+/// +----------------+-----------------------------------------------------------------+
+/// | version        | as is                                                           |
+/// +----------------+-----------------------------------------------------------------+
+/// | inputs         | the signed input with the script code as scriptSig, the others  |
+/// |                | with an empty scriptSig (NONE, SINGLE: and a 0 sequence), only  |
+/// |                | the signed input with ANYONECANPAY                              |
+/// +----------------+-----------------------------------------------------------------+
+/// | outputs        | ALL: all, NONE: none, SINGLE: up to the signed input index, the |
+/// |                | ones before being empty with an amount of -1                    |
+/// +----------------+-----------------------------------------------------------------+
+/// | lock_time      | as is                                                           |
+/// +----------------+-----------------------------------------------------------------+
+/// | hash type      | 4 bytes                                                         |
+/// +----------------+-----------------------------------------------------------------+
+/// ```
+///
+/// The script code is the script being executed from its last OP_CODESEPARATOR, with
+/// the remaining OP_CODESEPARATOR removed.
+///
+/// An input index out of range, or SINGLE without an output of the input index, signs
+/// the number 1 instead of failing, a bug of the first client kept by consensus.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp SignatureHash
+pub fn legacy_sighash(transaction: &Transaction, index: usize, script_code: &Script, hashtype: u32) -> Sha256 {
+
+    trace!("legacy_sighash");
+
+    let mut one = [0u8; 32];
+    one[0] = 1;
+    if index >= transaction.inputs.len() {
+        return Sha256::new(one);
+    }
+
+    let output_type = hashtype & SIGHASH_OUTPUT_MASK;
+    let anyone_can_pay = hashtype & SIGHASH_ANYONECANPAY != 0;
+    if output_type == SIGHASH_SINGLE && index >= transaction.outputs.len() {
+        return Sha256::new(one);
+    }
+
+    let script_code = remove_code_separators(script_code);
+    let empty = Script::default();
+
+    // writing into a Vec<u8> cannot fail
+    let mut data : Vec<u8> = Vec::new();
+    transaction.version.encode(&mut data).unwrap();
+
+    let inputs : Vec<usize> = if anyone_can_pay { vec![index] } else { (0..transaction.inputs.len()).collect() };
+    write_count(&mut data, inputs.len());
+    for i in inputs {
+        let input = &transaction.inputs[i];
+        input.previous.encode(&mut data).unwrap();
+        if i == index {
+            script_code.encode(&mut data).unwrap();
+            input.sequence.encode(&mut data).unwrap();
+        } else {
+            empty.encode(&mut data).unwrap();
+            // the other inputs can be updated when their outputs are not signed
+            let sequence = if output_type == SIGHASH_NONE || output_type == SIGHASH_SINGLE { 0 } else { input.sequence };
+            sequence.encode(&mut data).unwrap();
+        }
+    }
+
+    match output_type {
+        SIGHASH_NONE => write_count(&mut data, 0),
+        SIGHASH_SINGLE => {
+            write_count(&mut data, index + 1);
+            for _ in 0..index {
+                u64::MAX.encode(&mut data).unwrap();
+                empty.encode(&mut data).unwrap();
+            }
+            transaction.outputs[index].encode(&mut data).unwrap();
+        },
+        _ => transaction.outputs.encode(&mut data).unwrap(),
+    }
+
+    transaction.locktime.encode(&mut data).unwrap();
+    hashtype.encode(&mut data).unwrap();
+    Sha256::double(&data)
+}
+
+fn write_count(data: &mut Vec<u8>, count: usize) {
+    VarInt::new(count as u64).encode(data).unwrap();
+}
+
+/// The script without its OP_CODESEPARATOR, the bytes after an invalid push kept as is.
+fn remove_code_separators(script: &Script) -> Script {
+
+    let mut result = Vec::with_capacity(script.content.len());
+    let mut instructions = script.instructions();
+    let mut start = 0;
+    while let Some(Ok(instruction)) = instructions.next() {
+        let end = instructions.position();
+        if instruction.opcode != Opcode::CodeSeparator {
+            result.extend_from_slice(&script.content[start..end]);
+        }
+        start = end;
+    }
+    result.extend_from_slice(&script.content[start..]);
+    Script::new(result)
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki
+///
+/// # Segwit v0 signature hash
+///
+/// The legacy hash serializes the whole transaction for each input, hashing a transaction
+/// is quadratic in its number of inputs. BIP143 signs hashes of the inputs and outputs
+/// instead, computed once per transaction, and the amount of the spent output:
+///
+/// ```
+/// +----------------+--------------------------------------------------------------+
+/// | Field          | Serialized                                                   |
+/// +----------------+--------------------------------------------------------------+
+/// | version        | 4 bytes                                                      |
+/// +----------------+--------------------------------------------------------------+
+/// | hashPrevouts   | 32 bytes, double sha256 of the outpoints, 0 if ANYONECANPAY  |
+/// +----------------+--------------------------------------------------------------+
+/// | hashSequence   | 32 bytes, double sha256 of the sequences, 0 if ANYONECANPAY, |
+/// |                | SINGLE or NONE                                               |
+/// +----------------+--------------------------------------------------------------+
+/// | outpoint       | 36 bytes, of the signed input                                |
+/// +----------------+--------------------------------------------------------------+
+/// | scriptCode     | var_str, of the signed input                                 |
+/// +----------------+--------------------------------------------------------------+
+/// | amount         | 8 bytes, of the spent output                                 |
+/// +----------------+--------------------------------------------------------------+
+/// | sequence       | 4 bytes, of the signed input                                 |
+/// +----------------+--------------------------------------------------------------+
+/// | hashOutputs    | 32 bytes, double sha256 of the outputs, of the output of the |
+/// |                | input index with SINGLE, 0 with NONE or without such output  |
+/// +----------------+--------------------------------------------------------------+
+/// | lock_time      | 4 bytes                                                      |
+/// +----------------+--------------------------------------------------------------+
+/// | hash type      | 4 bytes                                                      |
+/// +----------------+--------------------------------------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.h PrecomputedTransactionData
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigHashCache {
+    pub hash_prevouts: Sha256,
+    pub hash_sequence: Sha256,
+    pub hash_outputs: Sha256,
+    /// BIP341, single sha256 of the outpoints
+    pub sha_prevouts: Sha256,
+    /// BIP341, single sha256 of the sequences
+    pub sha_sequences: Sha256,
+    /// BIP341, single sha256 of the outputs
+    pub sha_outputs: Sha256,
+    /// BIP341, single sha256 of the amounts of the spent outputs, when known
+    pub sha_amounts: Option<Sha256>,
+    /// BIP341, single sha256 of the scriptPubKeys of the spent outputs, when known
+    pub sha_scriptpubkeys: Option<Sha256>,
+}
+
+impl SigHashCache {
+
+    /// The hashes of the transaction alone, enough for the legacy and segwit v0 signatures.
+    pub fn new(transaction: &Transaction) -> SigHashCache {
+
+        let mut prevouts : Vec<u8> = Vec::new();
+        let mut sequences : Vec<u8> = Vec::new();
+        for input in &transaction.inputs {
+            input.previous.encode(&mut prevouts).unwrap();
+            input.sequence.encode(&mut sequences).unwrap();
+        }
+        let mut outputs : Vec<u8> = Vec::new();
+        for output in &transaction.outputs {
+            output.encode(&mut outputs).unwrap();
+        }
+
+        let sha_prevouts = Sha256::single(&prevouts);
+        let sha_sequences = Sha256::single(&sequences);
+        let sha_outputs = Sha256::single(&outputs);
+        SigHashCache {
+            hash_prevouts: Sha256::single(&sha_prevouts.hash),
+            hash_sequence: Sha256::single(&sha_sequences.hash),
+            hash_outputs: Sha256::single(&sha_outputs.hash),
+            sha_prevouts: sha_prevouts,
+            sha_sequences: sha_sequences,
+            sha_outputs: sha_outputs,
+            sha_amounts: None,
+            sha_scriptpubkeys: None,
+        }
+    }
+
+    /// The hashes of the transaction and of the outputs spent by its inputs, in the
+    /// order of the inputs, needed by the taproot signatures.
+    pub fn with_spent_outputs(transaction: &Transaction, spent_outputs: &[TxOut]) -> SigHashCache {
+
+        let mut amounts : Vec<u8> = Vec::new();
+        let mut script_pubkeys : Vec<u8> = Vec::new();
+        for output in spent_outputs {
+            output.amount.encode(&mut amounts).unwrap();
+            output.script_pubkey.encode(&mut script_pubkeys).unwrap();
+        }
+
+        let mut cache = SigHashCache::new(transaction);
+        cache.sha_amounts = Some(Sha256::single(&amounts));
+        cache.sha_scriptpubkeys = Some(Sha256::single(&script_pubkeys));
+        cache
+    }
+
+    /// The signature hash of the input `index`, spending `amount` with `script_code`: the
+    /// witness script for P2WSH, `OP_DUP OP_HASH160 <program> OP_EQUALVERIFY OP_CHECKSIG`
+    /// for P2WPKH. An input index out of range is an error, unlike the legacy hash.
+    pub fn segwit_v0_sighash(&self, transaction: &Transaction, index: usize, script_code: &Script, amount: u64, hashtype: u32) -> Result<Sha256, Error> {
+
+        trace!("segwit_v0_sighash");
+
+        if index >= transaction.inputs.len() {
+            return Err(Error::ScriptSigHashInputIndex);
+        }
+
+        let output_type = hashtype & SIGHASH_OUTPUT_MASK;
+        let anyone_can_pay = hashtype & SIGHASH_ANYONECANPAY != 0;
+        let zero = Sha256::default();
+
+        let hash_prevouts = if anyone_can_pay { zero } else { self.hash_prevouts };
+        let hash_sequence = if anyone_can_pay || output_type == SIGHASH_SINGLE || output_type == SIGHASH_NONE {
+            zero
+        } else {
+            self.hash_sequence
+        };
+        let hash_outputs = if output_type != SIGHASH_SINGLE && output_type != SIGHASH_NONE {
+            self.hash_outputs
+        } else if output_type == SIGHASH_SINGLE && index < transaction.outputs.len() {
+            let mut output : Vec<u8> = Vec::new();
+            transaction.outputs[index].encode(&mut output).unwrap();
+            Sha256::double(&output)
+        } else {
+            zero
+        };
+
+        let input = &transaction.inputs[index];
+        let mut data : Vec<u8> = Vec::new();
+        transaction.version.encode(&mut data).unwrap();
+        hash_prevouts.encode(&mut data).unwrap();
+        hash_sequence.encode(&mut data).unwrap();
+        input.previous.encode(&mut data).unwrap();
+        script_code.encode(&mut data).unwrap();
+        amount.encode(&mut data).unwrap();
+        input.sequence.encode(&mut data).unwrap();
+        hash_outputs.encode(&mut data).unwrap();
+        transaction.locktime.encode(&mut data).unwrap();
+        hashtype.encode(&mut data).unwrap();
+        Ok(Sha256::double(&data))
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#common-signature-message
+    ///
+    /// # Taproot signature hash
+    ///
+    /// The tagged hash `TapSighash` of a 0x00 epoch byte and of the message, committing to
+    /// the amounts and scriptPubKeys of all the spent outputs:
+    ///
+    /// ```
+    /// +-------------------+-------------------------------------------------------------+
+    /// | Field             | Serialized                                                  |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | hash type         | 1 byte                                                      |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | version           | 4 bytes                                                     |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | lock_time         | 4 bytes                                                     |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | sha_prevouts      | 32 bytes each, single sha256 of the outpoints, the amounts  |
+    /// | sha_amounts       | and scriptPubKeys spent, and the sequences, not with        |
+    /// | sha_scriptpubkeys | ANYONECANPAY                                                |
+    /// | sha_sequences     |                                                             |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | sha_outputs       | 32 bytes, single sha256 of the outputs, not with NONE or    |
+    /// |                   | SINGLE                                                      |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | spend_type        | 1 byte, 1 with an annex                                     |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | input             | ANYONECANPAY: outpoint, amount, scriptPubKey and sequence   |
+    /// |                   | of the signed input, else its index on 4 bytes              |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | sha_annex         | 32 bytes, single sha256 of the annex, if any                |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | sha_single_output | 32 bytes, single sha256 of the output of the input index,   |
+    /// |                   | with SINGLE                                                 |
+    /// +-------------------+-------------------------------------------------------------+
+    /// ```
+    ///
+    /// The signatures of a tapscript extend the message, BIP342, with a spend_type of 2
+    /// (3 with an annex):
+    ///
+    /// ```
+    /// +-------------------+-------------------------------------------------------------+
+    /// | tapleaf_hash      | 32 bytes, the leaf of the executed script                   |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | key_version       | 1 byte, 0x00                                                |
+    /// +-------------------+-------------------------------------------------------------+
+    /// | codesep_pos       | 4 bytes, opcode position of the last executed               |
+    /// |                   | OP_CODESEPARATOR, 0xffffffff if none                        |
+    /// +-------------------+-------------------------------------------------------------+
+    /// ```
+    ///
+    /// A hash type other than DEFAULT, ALL, NONE or SINGLE, with or without ANYONECANPAY,
+    /// and SINGLE without an output of the input index fail, like a cache without the
+    /// spent outputs.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp SignatureHashSchnorr
+    pub fn taproot_sighash(&self, transaction: &Transaction, index: usize, spent_outputs: &[TxOut], hashtype: u32, sigversion: SigVersion, execdata: &ExecutionData) -> Result<Sha256, Error> {
+        let message = self.taproot_signature_message(transaction, index, spent_outputs, hashtype, sigversion, execdata)?;
+        Ok(Sha256::tagged("TapSighash", &message))
+    }
+
+    /// The epoch and the message hashed by `taproot_sighash`.
+    pub fn taproot_signature_message(&self, transaction: &Transaction, index: usize, spent_outputs: &[TxOut], hashtype: u32, sigversion: SigVersion, execdata: &ExecutionData) -> Result<Vec<u8>, Error> {
+
+        trace!("taproot_signature_message");
+
+        let output_type = if hashtype == SIGHASH_DEFAULT { SIGHASH_ALL } else { hashtype & TAPROOT_OUTPUT_MASK };
+        let anyone_can_pay = hashtype & SIGHASH_ANYONECANPAY != 0;
+        if !matches!(hashtype, 0x00..=0x03 | 0x81..=0x83) {
+            return Err(Error::ScriptSchnorrSigHashType);
+        }
+        let (sha_amounts, sha_scriptpubkeys) = match (self.sha_amounts, self.sha_scriptpubkeys) {
+            (Some(sha_amounts), Some(sha_scriptpubkeys)) => (sha_amounts, sha_scriptpubkeys),
+            _ => return Err(Error::ScriptSchnorrSigHashType),
+        };
+        if index >= transaction.inputs.len() || spent_outputs.len() != transaction.inputs.len() {
+            return Err(Error::ScriptSchnorrSigHashType);
+        }
+        if output_type == SIGHASH_SINGLE && index >= transaction.outputs.len() {
+            return Err(Error::ScriptSchnorrSigHashType);
+        }
+        let leaf = match sigversion {
+            SigVersion::Tapscript => Some(execdata.tapleaf_hash.ok_or(Error::ScriptSchnorrSigHashType)?),
+            SigVersion::Taproot => None,
+            _ => return Err(Error::ScriptSchnorrSigHashType),
+        };
+
+        // the epoch, for future versions of the message
+        let mut data : Vec<u8> = vec![0x00];
+        (hashtype as u8).encode(&mut data).unwrap();
+        transaction.version.encode(&mut data).unwrap();
+        transaction.locktime.encode(&mut data).unwrap();
+
+        if !anyone_can_pay {
+            self.sha_prevouts.encode(&mut data).unwrap();
+            sha_amounts.encode(&mut data).unwrap();
+            sha_scriptpubkeys.encode(&mut data).unwrap();
+            self.sha_sequences.encode(&mut data).unwrap();
+        }
+        if output_type != SIGHASH_NONE && output_type != SIGHASH_SINGLE {
+            self.sha_outputs.encode(&mut data).unwrap();
+        }
+
+        let ext_flag = if leaf.is_some() { 1u8 } else { 0u8 };
+        let spend_type = ext_flag * 2 + if execdata.annex.is_some() { 1u8 } else { 0u8 };
+        spend_type.encode(&mut data).unwrap();
+
+        let input = &transaction.inputs[index];
+        if anyone_can_pay {
+            let spent = &spent_outputs[index];
+            input.previous.encode(&mut data).unwrap();
+            spent.amount.encode(&mut data).unwrap();
+            spent.script_pubkey.encode(&mut data).unwrap();
+            input.sequence.encode(&mut data).unwrap();
+        } else {
+            (index as u32).encode(&mut data).unwrap();
+        }
+
+        if let Some(annex) = &execdata.annex {
+            let mut serialized : Vec<u8> = Vec::new();
+            write_count(&mut serialized, annex.len());
+            serialized.extend_from_slice(annex);
+            Sha256::single(&serialized).encode(&mut data).unwrap();
+        }
+
+        if output_type == SIGHASH_SINGLE {
+            let mut output : Vec<u8> = Vec::new();
+            transaction.outputs[index].encode(&mut output).unwrap();
+            Sha256::single(&output).encode(&mut data).unwrap();
+        }
+
+        if let Some(tapleaf_hash) = leaf {
+            tapleaf_hash.encode(&mut data).unwrap();
+            TAPROOT_KEY_VERSION.encode(&mut data).unwrap();
+            execdata.codeseparator_position.encode(&mut data).unwrap();
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::Decodable;
+    use crate::block::interpreter::{ExecutionData, SigVersion};
+    use crate::block::script::Script;
+    use crate::block::sighash::{legacy_sighash, SigHashCache};
+    use crate::block::sighash::{SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE, SIGHASH_ANYONECANPAY};
+    use crate::block::transaction::Transaction;
+    use crate::block::txout::TxOut;
+    use crate::utils::sha256::Sha256;
+
+    use serde_json::Value;
+    use std::io::Cursor;
+
+    fn transaction(data: &str) -> Transaction {
+        let bytes = hex::decode(data).unwrap();
+        let mut r = Cursor::new(&bytes);
+        Transaction::decode(&mut r).unwrap()
+    }
+
+    fn script(data: &str) -> Script {
+        Script::new(hex::decode(data).unwrap())
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/sighash.json
+    #[test]
+    fn when_core_sighash_vectors_then_same_hash() {
+
+        let vectors : Vec<Value> = serde_json::from_str(include_str!("../tests/data/sighash.json")).unwrap();
+        let mut count = 0;
+        for vector in vectors.iter().filter(|vector| vector.as_array().unwrap().len() == 5) {
+
+            let tx = transaction(vector[0].as_str().unwrap());
+            let script_code = script(vector[1].as_str().unwrap());
+            let index = vector[2].as_u64().unwrap() as usize;
+            let hashtype = vector[3].as_i64().unwrap() as u32;
+
+            let hash = legacy_sighash(&tx, index, &script_code, hashtype);
+            assert_eq!(hash.to_string(), vector[4].as_str().unwrap(), "{}", vector);
+            count += 1;
+        }
+        assert_eq!(count, 500);
+    }
+
+    #[test]
+    fn when_single_without_output_then_one() {
+
+        // 2 inputs, 1 output
+        let tx = transaction("0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff01202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac11000000");
+        let one = "0000000000000000000000000000000000000000000000000000000000000001";
+        assert_eq!(legacy_sighash(&tx, 1, &Script::default(), SIGHASH_SINGLE).to_string(), one);
+        assert_eq!(legacy_sighash(&tx, 1, &Script::default(), SIGHASH_SINGLE | SIGHASH_ANYONECANPAY).to_string(), one);
+        assert_eq!(legacy_sighash(&tx, 2, &Script::default(), SIGHASH_ALL).to_string(), one);
+        assert_ne!(legacy_sighash(&tx, 0, &Script::default(), SIGHASH_SINGLE).to_string(), one);
+    }
+
+    #[test]
+    fn when_code_separator_then_removed_from_script_code() {
+
+        let tx = transaction("01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff00ffffffff0100000000000000000000000000");
+        let with = script("ab51ab52");
+        let without = script("5152");
+        assert_eq!(legacy_sighash(&tx, 0, &with, SIGHASH_ALL), legacy_sighash(&tx, 0, &without, SIGHASH_ALL));
+        // the truncated push is kept
+        assert_ne!(legacy_sighash(&tx, 0, &script("ab4c"), SIGHASH_ALL), legacy_sighash(&tx, 0, &Script::default(), SIGHASH_ALL));
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#native-p2wpkh
+    #[test]
+    fn when_bip143_native_p2wpkh_then_same_hash() {
+
+        let tx = transaction("0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000");
+        let cache = SigHashCache::new(&tx);
+        assert_eq!(hex::encode(cache.hash_prevouts.hash), "96b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37");
+        assert_eq!(hex::encode(cache.hash_sequence.hash), "52b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3b");
+        assert_eq!(hex::encode(cache.hash_outputs.hash), "863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e5");
+
+        let script_code = script("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac");
+        let hash = cache.segwit_v0_sighash(&tx, 1, &script_code, 600_000_000, SIGHASH_ALL).unwrap();
+        assert_eq!(hex::encode(hash.hash), "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670");
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#p2sh-p2wpkh
+    #[test]
+    fn when_bip143_p2sh_p2wpkh_then_same_hash() {
+
+        let tx = transaction("0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a54770100000000feffffff02b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac0008af2f000000001976a914fd270b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac92040000");
+        let cache = SigHashCache::new(&tx);
+        assert_eq!(hex::encode(cache.hash_prevouts.hash), "b0287b4a252ac05af83d2dcef00ba313af78a3e9c329afa216eb3aa2a7b4613a");
+        assert_eq!(hex::encode(cache.hash_sequence.hash), "18606b350cd8bf565266bc352f0caddcf01e8fa789dd8a15386327cf8cabe198");
+        assert_eq!(hex::encode(cache.hash_outputs.hash), "de984f44532e2173ca0d64314fcefe6d30da6f8cf27bafa706da61df8a226c83");
+
+        let script_code = script("76a91479091972186c449eb1ded22b78e40d009bdf008988ac");
+        let hash = cache.segwit_v0_sighash(&tx, 0, &script_code, 1_000_000_000, SIGHASH_ALL).unwrap();
+        assert_eq!(hex::encode(hash.hash), "64f3b0f4dd2bb3aa1ce8566d220cc74dda9df97d8490cc81d89d735c92e59fb6");
+    }
+
+    #[test]
+    fn when_segwit_v0_index_out_of_range_then_error() {
+
+        let tx = transaction("0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a54770100000000feffffff02b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac0008af2f000000001976a914fd270b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac92040000");
+        let cache = SigHashCache::new(&tx);
+        let script_code = script("76a91479091972186c449eb1ded22b78e40d009bdf008988ac");
+        assert_eq!(cache.segwit_v0_sighash(&tx, 1, &script_code, 1_000_000_000, SIGHASH_ALL), Err(Error::ScriptSigHashInputIndex));
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#p2sh-p2wsh
+    #[test]
+    fn when_bip143_p2sh_p2wsh_then_same_hash_for_each_type() {
+
+        let tx = transaction("010000000136641869ca081e70f394c6948e8af409e18b619df2ed74aa106c1ca29787b96e0100000000ffffffff0200e9a435000000001976a914389ffce9cd9ae88dcc0631e88a821ffdbe9bfe2688acc0832f05000000001976a9147480a33f950689af511e6e84c138dbbd3c3ee41588ac00000000");
+        let witness_script = script("56210307b8ae49ac90a048e9b53357a2354b3334e9c8bee813ecb98e99a7e07e8c3ba32103b28f0c28bfab54554ae8c658ac5c3e0ce6e79ad336331f78c428dd43eea8449b21034b8113d703413d57761b8b9781957b8c0ac1dfe69f492580ca4195f50376ba4a21033400f6afecb833092a9a21cfdf1ed1376e58c5d1f47de74683123987e967a8f42103a6d48b1131e94ba04d9737d61acdaa1322008af9602b3b14862c07a1789aac162102d8b661b0b3302ee2f162b09e07a55ad5dfbe673a9f01d9f0c19617681024306b56ae");
+        let cache = SigHashCache::new(&tx);
+        assert_eq!(hex::encode(cache.hash_prevouts.hash), "74afdc312af5183c4198a40ca3c1a275b485496dd3929bca388c4b5e31f7aaa0");
+        assert_eq!(hex::encode(cache.hash_sequence.hash), "3bb13029ce7b1f559ef5e747fcac439f1455a2ec7c5f09b72290795e70665044");
+        assert_eq!(hex::encode(cache.hash_outputs.hash), "bc4d309071414bed932f98832b27b4d76dad7e6c1346f487a8fdbb8eb90307cc");
+
+        for (hashtype, expected) in [
+            (SIGHASH_ALL, "185c0be5263dce5b4bb50a047973c1b6272bfbd0103a89444597dc40b248ee7c"),
+            (SIGHASH_NONE, "e9733bc60ea13c95c6527066bb975a2ff29a925e80aa14c213f686cbae5d2f36"),
+            (SIGHASH_SINGLE, "1e1f1c303dc025bd664acb72e583e933fae4cff9148bf78c157d1e8f78530aea"),
+            (SIGHASH_ALL | SIGHASH_ANYONECANPAY, "2a67f03e63a6a422125878b40b82da593be8d4efaafe88ee528af6e5a9955c6e"),
+            (SIGHASH_NONE | SIGHASH_ANYONECANPAY, "781ba15f3779d5542ce8ecb5c18716733a5ee42a6f51488ec96154934e2c890a"),
+            (SIGHASH_SINGLE | SIGHASH_ANYONECANPAY, "511e8e52ed574121fc1b654970395502128263f62662e076dc6baf05c2e6a99b"),
+        ] {
+            let hash = cache.segwit_v0_sighash(&tx, 0, &witness_script, 987_654_321, hashtype).unwrap();
+            assert_eq!(hex::encode(hash.hash), expected, "{}", hashtype);
+        }
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+    #[test]
+    fn when_bip341_key_path_spending_then_same_message_and_hash() {
+
+        let vectors : Value = serde_json::from_str(include_str!("../tests/data/bip341_wallet_vectors.json")).unwrap();
+        let vector = &vectors["keyPathSpending"][0];
+
+        let tx = transaction(vector["given"]["rawUnsignedTx"].as_str().unwrap());
+        let spent : Vec<TxOut> = vector["given"]["utxosSpent"].as_array().unwrap().iter().map(|utxo| TxOut {
+            amount: utxo["amountSats"].as_u64().unwrap(),
+            script_pubkey: script(utxo["scriptPubKey"].as_str().unwrap()),
+        }).collect();
+
+        let cache = SigHashCache::with_spent_outputs(&tx, &spent);
+        let intermediary = &vector["intermediary"];
+        let hex = |hash: &Sha256| hex::encode(hash.hash);
+        assert_eq!(hex(cache.sha_amounts.as_ref().unwrap()), intermediary["hashAmounts"]);
+        assert_eq!(hex(&cache.sha_outputs), intermediary["hashOutputs"]);
+        assert_eq!(hex(&cache.sha_prevouts), intermediary["hashPrevouts"]);
+        assert_eq!(hex(cache.sha_scriptpubkeys.as_ref().unwrap()), intermediary["hashScriptPubkeys"]);
+        assert_eq!(hex(&cache.sha_sequences), intermediary["hashSequences"]);
+
+        let execdata = ExecutionData::default();
+        for input in vector["inputSpending"].as_array().unwrap() {
+            let index = input["given"]["txinIndex"].as_u64().unwrap() as usize;
+            let hashtype = input["given"]["hashType"].as_u64().unwrap() as u32;
+
+            let message = cache.taproot_signature_message(&tx, index, &spent, hashtype, SigVersion::Taproot, &execdata).unwrap();
+            assert_eq!(hex::encode(message), input["intermediary"]["sigMsg"], "{}", index);
+            let hash = cache.taproot_sighash(&tx, index, &spent, hashtype, SigVersion::Taproot, &execdata).unwrap();
+            assert_eq!(hex(&hash), input["intermediary"]["sigHash"], "{}", index);
+        }
+    }
+
+    #[test]
+    fn when_tapscript_then_leaf_committed() {
+
+        let tx = transaction("01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff00ffffffff0100000000000000000000000000");
+        let spent = vec![TxOut { amount: 1, script_pubkey: script("5120") }];
+        let cache = SigHashCache::with_spent_outputs(&tx, &spent);
+
+        let mut execdata = ExecutionData::default();
+        let key_path = cache.taproot_signature_message(&tx, 0, &spent, 0, SigVersion::Taproot, &execdata).unwrap();
+        assert_eq!(cache.taproot_signature_message(&tx, 0, &spent, 0, SigVersion::Tapscript, &execdata), Err(Error::ScriptSchnorrSigHashType));
+
+        execdata.tapleaf_hash = Some(Sha256::new([0xaa; 32]));
+        let script_path = cache.taproot_signature_message(&tx, 0, &spent, 0, SigVersion::Tapscript, &execdata).unwrap();
+        // spend type with the extension flag, then the leaf hash, key version and code separator position
+        assert_eq!(script_path.len(), key_path.len() + 37);
+        let spend_type = key_path.len() - 5;
+        assert_eq!(key_path[spend_type], 0x00);
+        assert_eq!(script_path[spend_type], 0x02);
+        assert_eq!(script_path[script_path.len() - 37..script_path.len() - 5], [0xaa; 32]);
+        assert_eq!(script_path[script_path.len() - 5..], [0x00, 0xff, 0xff, 0xff, 0xff]);
+        assert_ne!(cache.taproot_sighash(&tx, 0, &spent, 0, SigVersion::Tapscript, &execdata), cache.taproot_sighash(&tx, 0, &spent, 0, SigVersion::Taproot, &execdata));
+    }
+}
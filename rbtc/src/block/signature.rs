@@ -0,0 +1,231 @@
+use crate::utils::sha256::Sha256;
+
+use secp256k1::{Secp256k1, Message, PublicKey, XOnlyPublicKey};
+use secp256k1::ecdsa;
+use secp256k1::schnorr;
+
+/// Half the order of the secp256k1 curve, the highest S value of a low S signature
+const HALF_ORDER : [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0066.mediawiki
+///
+/// # Strict DER
+///
+/// An ECDSA signature of a script is DER encoded, followed by the hash type byte:
+///
+/// ```
+/// +-------+---------+------+-------+-------+------+-------+-------+-----------+
+/// | 0x30  | len     | 0x02 | len R | R     | 0x02 | len S | S     | hash type |
+/// +-------+---------+------+-------+-------+------+-------+-------+-----------+
+/// | 1     | 1       | 1    | 1     | 1-33  | 1    | 1     | 1-33  | 1         |
+/// +-------+---------+------+-------+-------+------+-------+-------+-----------+
+/// ```
+///
+/// R and S are positive big endian integers, without leading zero bytes unless the
+/// next byte has its highest bit set.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp IsValidSignatureEncoding
+pub fn is_valid_der(signature: &[u8]) -> bool {
+
+    let len = signature.len();
+    if !(9..=73).contains(&len) || signature[0] != 0x30 || signature[1] as usize != len - 3 {
+        return false;
+    }
+
+    let len_r = signature[3] as usize;
+    if 5 + len_r >= len {
+        return false;
+    }
+    let len_s = signature[5 + len_r] as usize;
+    if len_r + len_s + 7 != len {
+        return false;
+    }
+
+    if signature[2] != 0x02 || len_r == 0 || signature[4] & 0x80 != 0 {
+        return false;
+    }
+    if len_r > 1 && signature[4] == 0 && signature[5] & 0x80 == 0 {
+        return false;
+    }
+
+    if signature[len_r + 4] != 0x02 || len_s == 0 || signature[len_r + 6] & 0x80 != 0 {
+        return false;
+    }
+    if len_s > 1 && signature[len_r + 6] == 0 && signature[len_r + 7] & 0x80 == 0 {
+        return false;
+    }
+    true
+}
+
+/// S at most half the curve order, BIP62: (R, S) and (R, order - S) are both valid,
+/// only the low one is standard so that a third party can not malleate the signature.
+///
+/// The signature must be strict DER.
+pub fn is_low_s(signature: &[u8]) -> bool {
+
+    let len_r = signature[3] as usize;
+    let len_s = signature[5 + len_r] as usize;
+    let s = &signature[6 + len_r..6 + len_r + len_s];
+    let s = &s[s.iter().take_while(|b| **b == 0).count()..];
+    s.len() < 32 || (s.len() == 32 && s <= &HALF_ORDER[..])
+}
+
+/// SIGHASH_ALL, SIGHASH_NONE or SIGHASH_SINGLE, with or without SIGHASH_ANYONECANPAY.
+pub fn is_defined_hashtype(signature: &[u8]) -> bool {
+    match signature.last() {
+        Some(hashtype) => (1..=3).contains(&(hashtype & !0x80)),
+        None => false,
+    }
+}
+
+/// Verifies an ECDSA signature, without its hash type byte, of `sighash`.
+///
+/// Like Core, the signature is parsed the lax way and its S normalized: strict DER
+/// and low S are rules of the script flags, not of the signature check.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/pubkey.cpp CPubKey::Verify
+pub fn verify_ecdsa(signature: &[u8], pubkey: &[u8], sighash: &Sha256) -> bool {
+
+    let secp = Secp256k1::verification_only();
+    let pubkey = match PublicKey::from_slice(pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return false,
+    };
+    let mut signature = match ecdsa::Signature::from_der_lax(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    signature.normalize_s();
+
+    let message = Message::from_digest(sighash.hash);
+    secp.verify_ecdsa(&message, &signature, &pubkey).is_ok()
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+///
+/// Verifies a 64 bytes Schnorr signature of `sighash` by a 32 bytes x-only public key.
+pub fn verify_schnorr(signature: &[u8], pubkey: &[u8], sighash: &Sha256) -> bool {
+
+    let secp = Secp256k1::verification_only();
+    let pubkey = match XOnlyPublicKey::from_slice(pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return false,
+    };
+    let signature = match schnorr::Signature::from_slice(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    let message = Message::from_digest(sighash.hash);
+    secp.verify_schnorr(&signature, &message, &pubkey).is_ok()
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::signature::{is_valid_der, is_low_s, is_defined_hashtype, verify_ecdsa, verify_schnorr};
+    use crate::utils::sha256::Sha256;
+
+    use std::convert::TryInto;
+
+    // signature of the first input of f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16,
+    // the first bitcoin transaction, from block 9 to Hal Finney
+    const SIGNATURE : &str = "304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901";
+    const PUBKEY : &str = "0411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3";
+    const SIGHASH : &str = "7a05c6145f10101e9d6325494245adf1297d80f8f38d4d576d57cdba220bcb19";
+
+    fn hash(data: &str) -> Sha256 {
+        Sha256::new(hex::decode(data).unwrap()[..].try_into().unwrap())
+    }
+
+    #[test]
+    fn when_first_transaction_then_ecdsa_valid() {
+
+        let signature = hex::decode(SIGNATURE).unwrap();
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let sighash = hash(SIGHASH);
+
+        assert!(is_valid_der(&signature));
+        assert!(is_low_s(&signature));
+        assert!(is_defined_hashtype(&signature));
+
+        let der = &signature[..signature.len() - 1];
+        assert!(verify_ecdsa(der, &pubkey, &sighash));
+        assert!(!verify_ecdsa(der, &pubkey, &Sha256::default()));
+        assert!(!verify_ecdsa(der, &pubkey[1..], &sighash));
+        assert!(!verify_ecdsa(&der[1..], &pubkey, &sighash));
+    }
+
+    #[test]
+    fn when_not_strict_der_then_invalid() {
+
+        let signature = hex::decode(SIGNATURE).unwrap();
+        assert!(!is_valid_der(&signature[..8]));
+
+        // wrong total length, sequence tag, integer tag, R length
+        for (index, value) in [(1, 0x45), (0, 0x31), (2, 0x03), (3, 0x21), (36, 0x03)] {
+            let mut tampered = signature.clone();
+            tampered[index] = value;
+            assert!(!is_valid_der(&tampered), "{}", index);
+        }
+
+        // negative R, R padded with a zero not needed
+        let mut negative = signature.clone();
+        negative[4] = 0x80;
+        assert!(!is_valid_der(&negative));
+        let padded = hex::decode("3045022100004e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901").unwrap();
+        assert!(!is_valid_der(&padded));
+    }
+
+    #[test]
+    fn when_high_s_then_not_low_s() {
+
+        // S = order - 0x181522ec...
+        let high = hex::decode("304502204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd41022100e7eadd137135f821b79f5b5322ed6f6137921779f39c5a19b7b03ce459a9243801").unwrap();
+        assert!(is_valid_der(&high));
+        assert!(!is_low_s(&high));
+
+        // the high S is valid, normalized before the check
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let sighash = hash(SIGHASH);
+        assert!(verify_ecdsa(&high[..high.len() - 1], &pubkey, &sighash));
+    }
+
+    #[test]
+    fn when_undefined_hashtype_then_invalid() {
+        assert!(is_defined_hashtype(&[0x30, 0x81]));
+        assert!(is_defined_hashtype(&[0x30, 0x03]));
+        assert!(!is_defined_hashtype(&[0x30, 0x00]));
+        assert!(!is_defined_hashtype(&[0x30, 0x04]));
+        assert!(!is_defined_hashtype(&[0x30, 0x84]));
+        assert!(!is_defined_hashtype(&[]));
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+    #[test]
+    fn when_bip340_vectors_then_same_result() {
+
+        for (pubkey, message, signature, valid) in [
+            ("F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9", "0000000000000000000000000000000000000000000000000000000000000000",
+             "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0", true),
+            ("DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+             "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A", true),
+            ("D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9", "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+             "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4", true),
+            // negated message
+            ("DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+             "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD", false),
+            // public key not on the curve
+            ("EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+             "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B", false),
+        ] {
+            let pubkey = hex::decode(pubkey).unwrap();
+            let message = hash(message);
+            let signature = hex::decode(signature).unwrap();
+            assert_eq!(verify_schnorr(&signature, &pubkey, &message), valid, "{}", hex::encode(&signature));
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::block::opcode::Opcode;
+use crate::block::script::{Script, Instructions};
+
+/// https://en.bitcoin.it/wiki/Script#Standard_Transaction_to_Bitcoin_address_.28pay-to-pubkey-hash.29
+///
+/// # Standard output scripts
+///
+/// The templates of scriptPubKey relayed by the nodes, with the keys, hashes or
+/// data they hold.
+///
+/// ```
+/// +-----------------------+-------------------------------------------------------------+
+/// | Type                  | scriptPubKey                                                |
+/// +-----------------------+-------------------------------------------------------------+
+/// | pubkey                | <pubkey> OP_CHECKSIG                                        |
+/// +-----------------------+-------------------------------------------------------------+
+/// | pubkeyhash            | OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG     |
+/// +-----------------------+-------------------------------------------------------------+
+/// | scripthash            | OP_HASH160 <20 bytes> OP_EQUAL                              |
+/// +-----------------------+-------------------------------------------------------------+
+/// | multisig              | <m> <pubkey>... <n> OP_CHECKMULTISIG                        |
+/// +-----------------------+-------------------------------------------------------------+
+/// | nulldata              | OP_RETURN <pushes>...                                       |
+/// +-----------------------+-------------------------------------------------------------+
+/// | witness_v0_keyhash    | OP_0 <20 bytes>                                             |
+/// +-----------------------+-------------------------------------------------------------+
+/// | witness_v0_scripthash | OP_0 <32 bytes>                                             |
+/// +-----------------------+-------------------------------------------------------------+
+/// | witness_v1_taproot    | OP_1 <32 bytes>                                             |
+/// +-----------------------+-------------------------------------------------------------+
+/// | witness_unknown       | OP_1 to OP_16 <2 to 40 bytes>, except taproot               |
+/// +-----------------------+-------------------------------------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/script/solver.cpp
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptType {
+    NonStandard,
+    PubKey(Vec<u8>),
+    PubKeyHash([u8; 20]),
+    ScriptHash([u8; 20]),
+    MultiSig {
+        required: u8,
+        pubkeys: Vec<Vec<u8>>,
+    },
+    /// The data of each push following OP_RETURN
+    NullData(Vec<Vec<u8>>),
+    WitnessV0KeyHash([u8; 20]),
+    WitnessV0ScriptHash([u8; 32]),
+    WitnessV1Taproot([u8; 32]),
+    WitnessUnknown {
+        version: u8,
+        program: Vec<u8>,
+    },
+}
+
+impl ScriptType {
+
+    /// Classifies a scriptPubKey, in the same order as Core `Solver`.
+    pub fn from_script(script: &Script) -> ScriptType {
+
+        trace!("from_script");
+
+        let content = &script.content;
+
+        if script.is_p2sh() {
+            let mut hash = [0u8; 20];
+            hash.copy_from_slice(&content[2..22]);
+            return ScriptType::ScriptHash(hash);
+        }
+
+        if let Some((version, program)) = script.witness_program() {
+            return match (version, program.len()) {
+                (0, 20) => {
+                    let mut hash = [0u8; 20];
+                    hash.copy_from_slice(program);
+                    ScriptType::WitnessV0KeyHash(hash)
+                },
+                (0, 32) => {
+                    let mut hash = [0u8; 32];
+                    hash.copy_from_slice(program);
+                    ScriptType::WitnessV0ScriptHash(hash)
+                },
+                (0, _) => ScriptType::NonStandard,
+                (1, 32) => {
+                    let mut key = [0u8; 32];
+                    key.copy_from_slice(program);
+                    ScriptType::WitnessV1Taproot(key)
+                },
+                _ => ScriptType::WitnessUnknown {
+                    version: version,
+                    program: program.to_vec(),
+                },
+            };
+        }
+
+        if content.first() == Some(&Opcode::Return.to_u8()) {
+            return match null_data(&content[1..]) {
+                Some(pushes) => ScriptType::NullData(pushes),
+                None => ScriptType::NonStandard,
+            };
+        }
+
+        if let Some(pubkey) = pay_to_pubkey(content) {
+            return ScriptType::PubKey(pubkey.to_vec());
+        }
+
+        if content.len() == 25
+            && content[0] == Opcode::Dup.to_u8()
+            && content[1] == Opcode::Hash160.to_u8()
+            && content[2] == 20
+            && content[23] == Opcode::EqualVerify.to_u8()
+            && content[24] == Opcode::CheckSig.to_u8() {
+            let mut hash = [0u8; 20];
+            hash.copy_from_slice(&content[3..23]);
+            return ScriptType::PubKeyHash(hash);
+        }
+
+        if let Some(multisig) = multisig(script) {
+            return multisig;
+        }
+
+        ScriptType::NonStandard
+    }
+
+    /// Name of the type, as displayed by Core.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptType::NonStandard => "nonstandard",
+            ScriptType::PubKey(_) => "pubkey",
+            ScriptType::PubKeyHash(_) => "pubkeyhash",
+            ScriptType::ScriptHash(_) => "scripthash",
+            ScriptType::MultiSig { .. } => "multisig",
+            ScriptType::NullData(_) => "nulldata",
+            ScriptType::WitnessV0KeyHash(_) => "witness_v0_keyhash",
+            ScriptType::WitnessV0ScriptHash(_) => "witness_v0_scripthash",
+            ScriptType::WitnessV1Taproot(_) => "witness_v1_taproot",
+            ScriptType::WitnessUnknown { .. } => "witness_unknown",
+        }
+    }
+}
+
+/// A compressed public key starts with 0x02 or 0x03 and has 33 bytes, an
+/// uncompressed (or hybrid) one starts with 0x04, 0x06 or 0x07 and has 65 bytes.
+pub fn is_valid_pubkey_size(data: &[u8]) -> bool {
+    match data.first() {
+        Some(0x02) | Some(0x03) => data.len() == 33,
+        Some(0x04) | Some(0x06) | Some(0x07) => data.len() == 65,
+        _ => false,
+    }
+}
+
+fn pay_to_pubkey(content: &[u8]) -> Option<&[u8]> {
+
+    for size in &[65usize, 33] {
+        if content.len() == size + 2
+            && content[0] as usize == *size
+            && content[size + 1] == Opcode::CheckSig.to_u8() {
+            let pubkey = &content[1..size + 1];
+            if is_valid_pubkey_size(pubkey) {
+                return Some(pubkey);
+            }
+        }
+    }
+    None
+}
+
+fn null_data(content: &[u8]) -> Option<Vec<Vec<u8>>> {
+
+    let mut result = Vec::new();
+    for instruction in Instructions::new(content) {
+        let instruction = instruction.ok()?;
+        // push only, OP_RESERVED being allowed like in Core IsPushOnly
+        if instruction.opcode.to_u8() > Opcode::Op16.to_u8() {
+            return None;
+        }
+        result.push(instruction.data.to_vec());
+    }
+    Some(result)
+}
+
+fn multisig(script: &Script) -> Option<ScriptType> {
+
+    let instructions = script.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+    if instructions.len() < 4 {
+        return None;
+    }
+
+    let last = instructions.len() - 1;
+    if instructions[last].opcode != Opcode::CheckMultiSig {
+        return None;
+    }
+
+    let required = instructions[0].opcode.small_int().filter(|n| *n >= 1)?;
+    let count = instructions[last - 1].opcode.small_int().filter(|n| *n >= 1)?;
+
+    let pubkeys : Vec<Vec<u8>> = instructions[1..last - 1].iter()
+        .map(|instruction| instruction.data)
+        .filter(|data| is_valid_pubkey_size(data))
+        .map(|data| data.to_vec())
+        .collect();
+    if pubkeys.len() != last - 2 || pubkeys.len() as i64 != count || required > count {
+        return None;
+    }
+
+    let result = ScriptType::MultiSig {
+        required: required as u8,
+        pubkeys: pubkeys,
+    };
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::script::Script;
+    use crate::block::standard::ScriptType;
+    use crate::chain::chainparams::ChainParams;
+
+    fn classify(asm: &str) -> ScriptType {
+        Script::from_asm(asm).unwrap().classify()
+    }
+
+    const PUBKEY : &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    #[test]
+    fn when_genesis_coinbase_then_pubkey() {
+
+        let block = ChainParams::mainnet().genesis_block();
+        let output = &block.transactions.get(0).unwrap().outputs[0];
+        let pubkey = hex::decode("04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f").unwrap();
+        assert_eq!(output.script_pubkey.classify(), ScriptType::PubKey(pubkey));
+        assert_eq!(output.script_pubkey.classify().name(), "pubkey");
+    }
+
+    #[test]
+    fn when_compressed_pubkey_then_pubkey() {
+        assert_eq!(classify(&format!("{} OP_CHECKSIG", PUBKEY)), ScriptType::PubKey(hex::decode(PUBKEY).unwrap()));
+        // 0x05 is not a public key header
+        assert_eq!(classify(&format!("05{} OP_CHECKSIG", &PUBKEY[2..])), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn when_hash_templates_then_hash() {
+
+        let hash20 = "62e907b15cbf27d5425399ebf6f0fb50ebb88f18";
+        let hash32 = "1863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262";
+        let mut h20 = [0u8; 20];
+        h20.copy_from_slice(&hex::decode(hash20).unwrap());
+        let mut h32 = [0u8; 32];
+        h32.copy_from_slice(&hex::decode(hash32).unwrap());
+
+        assert_eq!(classify(&format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", hash20)), ScriptType::PubKeyHash(h20));
+        assert_eq!(classify(&format!("OP_HASH160 {} OP_EQUAL", hash20)), ScriptType::ScriptHash(h20));
+        assert_eq!(classify(&format!("0 {}", hash20)), ScriptType::WitnessV0KeyHash(h20));
+        assert_eq!(classify(&format!("0 {}", hash32)), ScriptType::WitnessV0ScriptHash(h32));
+        assert_eq!(classify(&format!("1 {}", hash32)), ScriptType::WitnessV1Taproot(h32));
+    }
+
+    #[test]
+    fn when_other_witness_program_then_unknown_or_nonstandard() {
+
+        assert_eq!(classify("1 0a0b0c0d0e"), ScriptType::WitnessUnknown { version: 1, program: vec![10, 11, 12, 13, 14] });
+        assert_eq!(classify("16 0a0b0c0d0e"), ScriptType::WitnessUnknown { version: 16, program: vec![10, 11, 12, 13, 14] });
+        // version 0 only has 20 and 32 bytes programs
+        assert_eq!(classify("0 0a0b0c0d0e"), ScriptType::NonStandard);
+        // a 41 bytes program is not a witness program
+        assert_eq!(classify(&format!("1 {}", "ab".repeat(41))), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn when_op_return_then_null_data() {
+
+        assert_eq!(classify("OP_RETURN"), ScriptType::NullData(vec![]));
+        assert_eq!(classify("OP_RETURN 0 0a0b0c0d0e"), ScriptType::NullData(vec![vec![], vec![0x0a, 0x0b, 0x0c, 0x0d, 0x0e]]));
+        assert_eq!(classify("OP_RETURN OP_DUP"), ScriptType::NonStandard);
+        assert_eq!(Script::new(vec![0x6a, 0x02, 0x01]).classify(), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn when_multisig_then_keys() {
+
+        let key = hex::decode(PUBKEY).unwrap();
+        assert_eq!(classify(&format!("1 {} {} 2 OP_CHECKMULTISIG", PUBKEY, PUBKEY)), ScriptType::MultiSig {
+            required: 1,
+            pubkeys: vec![key.clone(), key],
+        });
+        assert_eq!(classify(&format!("1 {} {} 2 OP_CHECKMULTISIG", PUBKEY, PUBKEY)).name(), "multisig");
+        // more signatures than keys
+        assert_eq!(classify(&format!("3 {} {} 2 OP_CHECKMULTISIG", PUBKEY, PUBKEY)), ScriptType::NonStandard);
+        // key count not matching
+        assert_eq!(classify(&format!("1 {} {} 3 OP_CHECKMULTISIG", PUBKEY, PUBKEY)), ScriptType::NonStandard);
+        // not a key
+        assert_eq!(classify(&format!("1 {} 0a0b0c0d0e 2 OP_CHECKMULTISIG", PUBKEY)), ScriptType::NonStandard);
+        assert_eq!(classify(&format!("0 {} 1 OP_CHECKMULTISIG", PUBKEY)), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn when_empty_or_truncated_then_nonstandard() {
+        assert_eq!(Script::default().classify(), ScriptType::NonStandard);
+        assert_eq!(Script::new(vec![0x76, 0xa9, 0x14]).classify(), ScriptType::NonStandard);
+    }
+}
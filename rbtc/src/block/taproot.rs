@@ -0,0 +1,299 @@
+use crate::encode::error::Error;
+use crate::encode::encode::Encodable;
+use crate::block::script::Script;
+use crate::utils::sha256::Sha256;
+
+use secp256k1::{Secp256k1, XOnlyPublicKey, Scalar, Parity};
+
+/// Leaf version of the tapscripts, BIP342
+pub const TAPROOT_LEAF_TAPSCRIPT : u8 = 0xc0;
+/// Bits of the first byte of a control block holding the leaf version, the last one
+/// being the parity of the output key
+pub const TAPROOT_LEAF_MASK : u8 = 0xfe;
+
+/// Control block without merkle path: the leaf version byte and the internal key
+const TAPROOT_CONTROL_BASE_SIZE : usize = 33;
+/// Size of a node of the merkle path
+const TAPROOT_CONTROL_NODE_SIZE : usize = 32;
+/// Maximum depth of a script tree
+const TAPROOT_CONTROL_MAX_NODE_COUNT : usize = 128;
+
+/// The hash of a leaf of a script tree, `TapLeaf(leaf version || compact size || script)`.
+pub fn tapleaf_hash(leaf_version: u8, script: &Script) -> Sha256 {
+    let mut data : Vec<u8> = vec![leaf_version];
+    script.encode(&mut data).unwrap();
+    Sha256::tagged("TapLeaf", &data)
+}
+
+/// The hash of a branch of a script tree, the two children being sorted so that the
+/// merkle path does not need to tell left from right.
+pub fn tapbranch_hash(a: &Sha256, b: &Sha256) -> Sha256 {
+    let (first, second) = if a.hash <= b.hash { (a, b) } else { (b, a) };
+    let mut data : Vec<u8> = Vec::with_capacity(64);
+    data.extend_from_slice(&first.hash);
+    data.extend_from_slice(&second.hash);
+    Sha256::tagged("TapBranch", &data)
+}
+
+/// The tweak of an internal key, committing to the merkle root of the script tree, if any.
+pub fn taptweak_hash(internal_key: &[u8; 32], merkle_root: Option<&Sha256>) -> Sha256 {
+    let mut data : Vec<u8> = internal_key.to_vec();
+    if let Some(merkle_root) = merkle_root {
+        data.extend_from_slice(&merkle_root.hash);
+    }
+    Sha256::tagged("TapTweak", &data)
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#constructing-and-spending-taproot-outputs
+///
+/// # Output key
+///
+/// The x-only key of a taproot output is the internal key P tweaked by its script tree:
+///
+/// ```
+/// Q = P + int(TapTweak(P || merkle root)) G
+/// ```
+///
+/// Spending with the key path signs with the tweaked private key, spending with a script
+/// reveals P and the merkle path, from which the verifier computes Q again.
+///
+/// Returns the output key and its parity, the parity of the y coordinate being lost with
+/// the x-only key but needed in the control blocks.
+pub fn tweak_key(internal_key: &[u8; 32], merkle_root: Option<&Sha256>) -> Result<([u8; 32], u8), Error> {
+
+    let secp = Secp256k1::verification_only();
+    let key = XOnlyPublicKey::from_slice(internal_key).map_err(|e| Error::TaprootInternalKey.caused_by(e))?;
+    let tweak = taptweak_hash(internal_key, merkle_root);
+    let tweak = Scalar::from_be_bytes(tweak.hash).map_err(|e| Error::TaprootTweak.caused_by(e))?;
+    let (output_key, parity) = key.add_tweak(&secp, &tweak).map_err(|e| Error::TaprootTweak.caused_by(e))?;
+    Ok((output_key.serialize(), parity.to_u8()))
+}
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#script-validation-rules
+///
+/// # Control block
+///
+/// The last element of the witness of a script path spend, after the annex, proving that
+/// the script, the element before, is a leaf of the tree committed to by the output key:
+///
+/// ```
+/// +--------------------------+---------+------------------------------------------------+
+/// | Field                    | Size    | Description                                    |
+/// +--------------------------+---------+------------------------------------------------+
+/// | leaf version | parity    | 1       | leaf version, highest 7 bits, and parity of    |
+/// |                          |         | the output key, lowest bit                     |
+/// +--------------------------+---------+------------------------------------------------+
+/// | internal key             | 32      | x-only key P                                   |
+/// +--------------------------+---------+------------------------------------------------+
+/// | merkle branch            | 32 * m  | hashes of the siblings from the leaf to the    |
+/// |                          |         | root, m at most 128                            |
+/// +--------------------------+---------+------------------------------------------------+
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlBlock {
+    pub leaf_version: u8,
+    /// Parity of the y coordinate of the output key
+    pub output_key_parity: u8,
+    pub internal_key: [u8; 32],
+    pub merkle_branch: Vec<Sha256>,
+}
+
+impl ControlBlock {
+
+    pub fn parse(data: &[u8]) -> Result<ControlBlock, Error> {
+
+        if data.len() < TAPROOT_CONTROL_BASE_SIZE
+            || data.len() > TAPROOT_CONTROL_BASE_SIZE + TAPROOT_CONTROL_NODE_SIZE * TAPROOT_CONTROL_MAX_NODE_COUNT
+            || !(data.len() - TAPROOT_CONTROL_BASE_SIZE).is_multiple_of(TAPROOT_CONTROL_NODE_SIZE) {
+            return Err(Error::ScriptTaprootWrongControlSize);
+        }
+
+        let mut internal_key = [0u8; 32];
+        internal_key.copy_from_slice(&data[1..TAPROOT_CONTROL_BASE_SIZE]);
+        let merkle_branch = data[TAPROOT_CONTROL_BASE_SIZE..].chunks(TAPROOT_CONTROL_NODE_SIZE)
+            .map(|node| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(node);
+                Sha256::new(hash)
+            })
+            .collect();
+
+        Ok(ControlBlock {
+            leaf_version: data[0] & TAPROOT_LEAF_MASK,
+            output_key_parity: data[0] & !TAPROOT_LEAF_MASK,
+            internal_key: internal_key,
+            merkle_branch: merkle_branch,
+        })
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut result = vec![self.leaf_version | self.output_key_parity];
+        result.extend_from_slice(&self.internal_key);
+        for node in &self.merkle_branch {
+            result.extend_from_slice(&node.hash);
+        }
+        result
+    }
+
+    /// The root of the script tree, hashing the leaf with the merkle branch.
+    pub fn merkle_root(&self, leaf_hash: &Sha256) -> Sha256 {
+        self.merkle_branch.iter().fold(*leaf_hash, |hash, node| tapbranch_hash(&hash, node))
+    }
+
+    /// The leaf is in the tree of `output_key`, with the parity of the control block.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp VerifyTaprootCommitment
+    pub fn verify(&self, output_key: &[u8], leaf_hash: &Sha256) -> bool {
+
+        let secp = Secp256k1::verification_only();
+        let internal_key = match XOnlyPublicKey::from_slice(&self.internal_key) {
+            Ok(key) => key,
+            Err(_) => return false,
+        };
+        let output_key = match XOnlyPublicKey::from_slice(output_key) {
+            Ok(key) => key,
+            Err(_) => return false,
+        };
+        let tweak = taptweak_hash(&self.internal_key, Some(&self.merkle_root(leaf_hash)));
+        let tweak = match Scalar::from_be_bytes(tweak.hash) {
+            Ok(tweak) => tweak,
+            Err(_) => return false,
+        };
+        let parity = if self.output_key_parity == 0 { Parity::Even } else { Parity::Odd };
+        internal_key.tweak_add_check(&secp, &output_key, parity, tweak)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::block::script::Script;
+    use crate::block::taproot::{tapleaf_hash, tapbranch_hash, taptweak_hash, tweak_key, ControlBlock, TAPROOT_LEAF_TAPSCRIPT};
+    use crate::chain::address::Address;
+    use crate::chain::chainparams::ChainParams;
+    use crate::utils::sha256::Sha256;
+
+    use serde_json::Value;
+
+    fn key(data: &str) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&hex::decode(data).unwrap());
+        result
+    }
+
+    /// The leaf hashes, in the order of their ids, and the root of a tree of the vectors:
+    /// a leaf or an array of two trees.
+    fn tree_root(tree: &Value, leaves: &mut Vec<(u64, Sha256)>) -> Sha256 {
+        match tree.as_array() {
+            Some(children) => {
+                let left = tree_root(&children[0], leaves);
+                let right = tree_root(&children[1], leaves);
+                tapbranch_hash(&left, &right)
+            },
+            None => {
+                let script = Script::new(hex::decode(tree["script"].as_str().unwrap()).unwrap());
+                let hash = tapleaf_hash(tree["leafVersion"].as_u64().unwrap() as u8, &script);
+                leaves.push((tree["id"].as_u64().unwrap(), hash));
+                hash
+            },
+        }
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+    #[test]
+    fn when_bip341_script_pubkey_vectors_then_same_output() {
+
+        let vectors : Value = serde_json::from_str(include_str!("../tests/data/bip341_wallet_vectors.json")).unwrap();
+        let params = ChainParams::mainnet();
+        let vectors = vectors["scriptPubKey"].as_array().unwrap();
+        for vector in vectors {
+
+            let internal_key = key(vector["given"]["internalPubkey"].as_str().unwrap());
+            let intermediary = &vector["intermediary"];
+            let expected = &vector["expected"];
+
+            let mut leaves = Vec::new();
+            let merkle_root = match vector["given"]["scriptTree"] {
+                Value::Null => None,
+                ref tree => Some(tree_root(tree, &mut leaves)),
+            };
+            leaves.sort_by_key(|(id, _)| *id);
+
+            match intermediary["merkleRoot"].as_str() {
+                Some(root) => assert_eq!(hex::encode(merkle_root.unwrap().hash), root),
+                None => assert!(merkle_root.is_none()),
+            }
+            if let Some(hashes) = intermediary["leafHashes"].as_array() {
+                let computed : Vec<String> = leaves.iter().map(|(_, hash)| hex::encode(hash.hash)).collect();
+                let hashes : Vec<String> = hashes.iter().map(|hash| hash.as_str().unwrap().to_string()).collect();
+                assert_eq!(computed, hashes);
+            }
+
+            let tweak = taptweak_hash(&internal_key, merkle_root.as_ref());
+            assert_eq!(hex::encode(tweak.hash), intermediary["tweak"].as_str().unwrap());
+
+            let (output_key, parity) = tweak_key(&internal_key, merkle_root.as_ref()).unwrap();
+            assert_eq!(hex::encode(output_key), intermediary["tweakedPubkey"].as_str().unwrap());
+
+            let address = Address::WitnessProgram { version: 1, program: output_key.to_vec() };
+            assert_eq!(hex::encode(&address.script_pubkey().content), expected["scriptPubKey"].as_str().unwrap());
+            assert_eq!(address.encode(&params), expected["bip350Address"].as_str().unwrap());
+
+            // the control block of each leaf, in the order of their ids
+            if let Some(control_blocks) = expected["scriptPathControlBlocks"].as_array() {
+                for ((_, leaf_hash), data) in leaves.iter().zip(control_blocks) {
+                    let data = hex::decode(data.as_str().unwrap()).unwrap();
+                    let control = ControlBlock::parse(&data).unwrap();
+                    assert_eq!(control.internal_key, internal_key);
+                    assert_eq!(control.output_key_parity, parity);
+                    assert_eq!(control.merkle_root(leaf_hash), merkle_root.unwrap());
+                    assert!(control.verify(&output_key, leaf_hash));
+                    assert_eq!(control.to_vec(), data);
+                }
+            }
+        }
+        assert_eq!(vectors.len(), 7);
+    }
+
+    #[test]
+    fn when_control_block_wrong_size_then_fail() {
+        assert_eq!(ControlBlock::parse(&[0xc0; 32]), Err(Error::ScriptTaprootWrongControlSize));
+        assert_eq!(ControlBlock::parse(&[0xc0; 34]), Err(Error::ScriptTaprootWrongControlSize));
+        assert_eq!(ControlBlock::parse(&vec![0xc0; 33 + 32 * 129]), Err(Error::ScriptTaprootWrongControlSize));
+        assert!(ControlBlock::parse(&vec![0xc0; 33 + 32 * 128]).is_ok());
+    }
+
+    #[test]
+    fn when_control_block_tampered_then_not_verified() {
+
+        // x of the generator, the internal key of the script_tests of Core
+        let internal_key = key("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        let leaf_hash = tapleaf_hash(TAPROOT_LEAF_TAPSCRIPT, &Script::new(vec![0x51]));
+        let (output_key, parity) = tweak_key(&internal_key, Some(&leaf_hash)).unwrap();
+
+        let control = ControlBlock {
+            leaf_version: TAPROOT_LEAF_TAPSCRIPT,
+            output_key_parity: parity,
+            internal_key: internal_key,
+            merkle_branch: vec![],
+        };
+        assert!(control.verify(&output_key, &leaf_hash));
+        assert!(!control.verify(&output_key, &tapleaf_hash(TAPROOT_LEAF_TAPSCRIPT, &Script::new(vec![0x52]))));
+
+        let mut wrong_parity = control.clone();
+        wrong_parity.output_key_parity ^= 1;
+        assert!(!wrong_parity.verify(&output_key, &leaf_hash));
+
+        let mut longer = control;
+        longer.merkle_branch.push(Sha256::default());
+        assert!(!longer.verify(&output_key, &leaf_hash));
+    }
+
+    #[test]
+    fn when_internal_key_not_on_curve_then_fail() {
+        let internal_key = key("eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34");
+        assert_eq!(tweak_key(&internal_key, None), Err(Error::TaprootInternalKey));
+    }
+}
@@ -1,13 +1,15 @@
-use crate::encode::error::Error;
+use crate::encode::error::{Error, Frame};
 use crate::encode::encode::{Encodable, Decodable};
 use crate::encode::varint::VarInt;
 use crate::block::witness;
 
+use crate::block::script::Script;
 use crate::block::txout::TxOut;
 use crate::block::txin::TxIn;
 use crate::block::witness::Witness;
+use crate::utils::sha256::Sha256;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// 
@@ -33,7 +35,7 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 /// | list of outputs |  the outputs of the first transaction spend  | <out-counter>-many outputs   | 
 /// |                 |  the mined bitcoins for the block            |                              | 
 /// +-----------------+----------------------------------------------+------------------------------+ 
-/// | Vec<Witness>       |  A list of witnesses, 1 for each input,      | variable                     | 
+/// | Witnesses       |  A list of witnesses, 1 for each input,      | variable                     | 
 /// |                 |  omitted if flag above is missing	,        | see Segregated_Witness       | 
 /// +-----------------+----------------------------------------------+------------------------------+ 
 /// | lock_time       |  if non-zero and sequence numbers are        | 4 bytes                      | 
@@ -41,6 +43,12 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 /// |                 |  when transaction is final                   |                              | 
 /// +-----------------+----------------------------------------------+------------------------------+ 
 /// 
+/// https://github.com/bitcoin/bips/blob/master/bip-0144.mediawiki
+/// 
+/// Each witness is a var_int count of stack items followed by the items, each item being
+/// a var_int length followed by its bytes. There is exactly one witness per input, empty
+/// when the input has no witness, and they are stored on `TxIn::witness` once decoded.
+/// 
 // https://github.com/bitcoin/bitcoin/blob/master/src/primitives/transaction.h
 //
 // /** The basic transaction that is broadcasted on the network and contained in
@@ -48,13 +56,19 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 //  */
 // class CTransaction
 // {
+/// Marker and flag bytes (0x00 0x01) announcing the witness serialization
+const TRANSACTION_FLAG : u16 = 0x0100;
+/// Weight units of a byte outside the witness, BIP141
+pub const WITNESS_SCALE_FACTOR : usize = 4;
+/// Transactions of a block at most, MAX_BLOCK_WEIGHT / WITNESS_SCALE_FACTOR as in `check_block`
+const MAX_BLOCK_TRANSACTIONS : u64 = 1_000_000;
+
 #[derive(Debug)]
 pub struct Transaction {
     pub version: i32,
     pub flag: Option<u16>,
     pub inputs: Vec<TxIn>,
     pub outputs: Vec<TxOut>,
-    pub witness: Option<Vec<Witness>>,
     pub locktime: u32
 }
 
@@ -62,47 +76,237 @@ pub struct Transaction {
 pub struct Transactions(Vec<Transaction>);
 
 impl Transactions {
+    pub fn new(transactions: Vec<Transaction>) -> Transactions {
+        Transactions(transactions)
+    }
     pub fn len(&self) -> usize {
         self.0.len()
     }
     pub fn get(&self, index: usize) -> Option<&Transaction> {
         self.0.get(index)
     }
+    pub fn iter(&self) -> std::slice::Iter<Transaction> {
+        self.0.iter()
+    }
+}
+
+impl Transaction {
+
+    /// True when at least one input carries a non empty witness.
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+    }
+
+    /// Transaction id, the double sha256 of the transaction serialized
+    /// without witness.
+    pub fn txid(&self) -> Sha256 {
+        let mut data : Vec<u8> = Vec::new();
+        // writing into a Vec<u8> cannot fail
+        self.encode_without_witness(&mut data).unwrap();
+        Sha256::double(&data)
+    }
+
+    /// Witness transaction id (BIP141), the double sha256 of the full
+    /// serialization. Same as txid when the transaction has no witness.
+    pub fn wtxid(&self) -> Sha256 {
+        let mut data : Vec<u8> = Vec::new();
+        self.encode(&mut data).unwrap();
+        Sha256::double(&data)
+    }
+
+    /// Size of the transaction serialized without witness, as seen by a pre-segwit node.
+    pub fn stripped_size(&self) -> usize {
+        let mut data : Vec<u8> = Vec::new();
+        self.encode_without_witness(&mut data).unwrap();
+        data.len()
+    }
+
+    /// Size of the full serialization, with the witness when there is one.
+    pub fn total_size(&self) -> usize {
+        let mut data : Vec<u8> = Vec::new();
+        self.encode(&mut data).unwrap();
+        data.len()
+    }
+
+    /// Weight of the transaction, BIP141: the bytes of the witness count for 1 weight
+    /// unit, the others for 4.
+    ///
+    /// ```
+    /// weight = stripped size * 3 + total size
+    /// ```
+    pub fn weight(&self) -> usize {
+        self.stripped_size() * (WITNESS_SCALE_FACTOR - 1) + self.total_size()
+    }
+
+    /// Virtual size, the weight divided by 4 rounded up, the size fee rates are given for.
+    pub fn vsize(&self) -> usize {
+        self.weight().div_ceil(WITNESS_SCALE_FACTOR)
+    }
+
+    /// The coinbase spends a single null outpoint.
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1 && self.inputs[0].previous.is_null()
+    }
+
+    /// Signature operations of the scriptSigs and scriptPubKeys, counted the legacy way,
+    /// 20 for every OP_CHECKMULTISIG.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/consensus/tx_verify.cpp GetLegacySigOpCount
+    pub fn legacy_sigop_count(&self) -> usize {
+        let inputs : usize = self.inputs.iter().map(|input| input.signature.sigop_count(false)).sum();
+        let outputs : usize = self.outputs.iter().map(|output| output.script_pubkey.sigop_count(false)).sum();
+        inputs + outputs
+    }
+
+    /// Signature operations of the redeem scripts of the P2SH outputs spent, BIP16,
+    /// `spent_outputs` being in the order of the inputs. None for a coinbase.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/consensus/tx_verify.cpp GetP2SHSigOpCount
+    pub fn p2sh_sigop_count(&self, spent_outputs: &[TxOut]) -> Result<usize, Error> {
+
+        if self.is_coinbase() {
+            return Ok(0);
+        }
+        if spent_outputs.len() != self.inputs.len() {
+            return Err(Error::SpentOutputsCount);
+        }
+        let result = self.inputs.iter().zip(spent_outputs)
+            .filter(|(_, spent)| spent.script_pubkey.is_p2sh())
+            .map(|(input, _)| match input.signature.last_push() {
+                Some(redeem_script) => Script::new(redeem_script.to_vec()).sigop_count(true),
+                None => 0,
+            })
+            .sum();
+        Ok(result)
+    }
+
+    /// Signature operations of the segwit v0 outputs spent, native or nested in P2SH,
+    /// BIP141: 1 for a P2WPKH, the witness script ones counted accurately for a P2WSH.
+    /// The taproot signatures are limited per input by their validation weight instead.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/script/interpreter.cpp CountWitnessSigOps
+    pub fn witness_sigop_count(&self, spent_outputs: &[TxOut]) -> Result<usize, Error> {
+
+        if self.is_coinbase() {
+            return Ok(0);
+        }
+        if spent_outputs.len() != self.inputs.len() {
+            return Err(Error::SpentOutputsCount);
+        }
+        let mut result = 0;
+        for (input, spent) in self.inputs.iter().zip(spent_outputs) {
+            let program = if spent.script_pubkey.is_p2sh() {
+                input.signature.last_push().map(|redeem_script| Script::new(redeem_script.to_vec()))
+            } else {
+                Some(spent.script_pubkey.clone())
+            };
+            if let Some((version, program)) = program.as_ref().and_then(|program| program.witness_program()) {
+                result += match (version, program.len(), input.witness.last()) {
+                    (0, 20, _) => 1,
+                    (0, 32, Some(witness_script)) => Script::new(witness_script.data.clone()).sigop_count(true),
+                    _ => 0,
+                };
+            }
+        }
+        Ok(result)
+    }
+
+    /// Signature operations cost of the transaction, BIP141: the legacy and P2SH ones
+    /// count for 4, the witness ones for 1.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/consensus/tx_verify.cpp GetTransactionSigOpCost
+    pub fn sigop_cost(&self, spent_outputs: &[TxOut]) -> Result<usize, Error> {
+        let legacy = self.legacy_sigop_count() + self.p2sh_sigop_count(spent_outputs)?;
+        Ok(legacy * WITNESS_SCALE_FACTOR + self.witness_sigop_count(spent_outputs)?)
+    }
+
+    /// Serializes the transaction in the legacy format, leaving out the
+    /// marker, flag and witness fields even when they are present.
+    /// This is the serialization used to compute the txid.
+    pub fn encode_without_witness<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode_without_witness");
+
+        self.version.encode(w).map_err(|e| Error::TransactionVersion.caused_by(e))?;
+        self.inputs.encode(w)?;
+        self.outputs.encode(w)?;
+        self.locktime.encode(w).map_err(|e| Error::TransactionLockTime.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
+impl Encodable for Transaction {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let has_witness = self.has_witness();
+
+        self.version.encode(w).map_err(|e| Error::TransactionVersion.caused_by(e))?;
+
+        if has_witness {
+            TRANSACTION_FLAG.encode(w).map_err(|e| Error::TransactionFlag.caused_by(e))?;
+        }
+
+        self.inputs.encode(w)?;
+        self.outputs.encode(w)?;
+
+        if has_witness {
+            for input in &self.inputs {
+                input.witness.encode(w)?;
+            }
+        }
+
+        self.locktime.encode(w).map_err(|e| Error::TransactionLockTime.caused_by(e))?;
+
+        Ok(())
+    }
 }
 
 impl Decodable for Transaction {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Transaction, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Transaction, Error> {
 
         trace!("decode");
 
-        let version = i32::decode(r).map_err(|_| Error::TransactionVersion)?;
+        let version = i32::decode(r).map_err(|e| Error::TransactionVersion.caused_by(e))?;
 
-        let position = r.position();
-        let flag = u16::decode(r)
-            .map(|v| match v { 0x0100 => Some(v), _ => None })
-            .map_err(|_| Error::TransactionFlag)?;
-        
-        if flag.is_none() {
-            r.set_position(position);
+        // the marker is read where the input count would be: a zero count
+        // is not a valid transaction, so 0x00 announces the witness flag,
+        // any other byte is the first byte of the input count
+        let marker = u8::decode(r).map_err(|e| Error::TransactionFlag.caused_by(e))?;
+        let (flag, mut inputs) = match marker {
+            0x00 => {
+                let flag = u8::decode(r).map_err(|e| Error::TransactionFlag.caused_by(e))?;
+                if flag != 0x01 {
+                    return Err(Error::TransactionFlag);
+                }
+                (Some(TRANSACTION_FLAG), <Vec<TxIn>>::decode(r)?)
+            },
+            _ => (None, <Vec<TxIn>>::decode(&mut (&[marker][..]).chain(&mut *r))?),
         };
 
-        let inputs = <Vec<TxIn>>::decode(r)?;
         let outputs = <Vec<TxOut>>::decode(r)?;
 
-        let witnesses = match flag {
-            Some(_) => Some(<Vec<Witness>>::decode(r)?),
-            _ => None
-        };
+        if flag.is_some() {
+            for (i, input) in inputs.iter_mut().enumerate() {
+                input.witness = <Vec<Witness>>::decode(r)
+                    .map_err(|e| e.within(Frame::new("witness")).within(Frame::item("input", i)))?;
+            }
+            if inputs.iter().all(|input| input.witness.is_empty()) {
+                return Err(Error::TransactionWitnessEmpty);
+            }
+        }
 
-        let locktime = u32::decode(r).map_err(|_| Error::TransactionLockTime)?;
+        let locktime = u32::decode(r).map_err(|e| Error::TransactionLockTime.caused_by(e))?;
 
         let result = Transaction {
             version: version,
             flag: flag,
             inputs: inputs,
             outputs: outputs,
-            witness: witnesses,
             locktime: locktime
         };
         
@@ -110,17 +314,50 @@ impl Decodable for Transaction {
     }
 }
 
+impl Transactions {
+
+    pub fn encode_without_witness<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode_without_witness");
+
+        let count = VarInt::new(self.0.len() as u64);
+        count.encode(w).map_err(|e| Error::TransactionsCount.caused_by(e))?;
+        for tx in &self.0 {
+            tx.encode_without_witness(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl Encodable for Transactions {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let count = VarInt::new(self.0.len() as u64);
+        count.encode(w).map_err(|e| Error::TransactionsCount.caused_by(e))?;
+        for tx in &self.0 {
+            tx.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
 impl Decodable for Transactions {
     
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Transactions, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Transactions, Error> {
         
         trace!("decode");
 
         let mut txs : Vec<Transaction> = Vec::new();
-        let count = VarInt::decode(r).map_err(|_| Error::TransactionsCount)?;
+        let count = VarInt::decode(r).map_err(|e| Error::TransactionsCount.caused_by(e))?;
+        if count.0 > MAX_BLOCK_TRANSACTIONS {
+            return Err(Error::TransactionsTooMany);
+        }
 
-        for _ in 0..count.0 {
-            let tx = Transaction::decode(r)?;
+        for i in 0..count.0 {
+            let tx = Transaction::decode(r).map_err(|e| e.within(Frame::item("tx", i as usize)))?;
             txs.push(tx);
         }
         let result = Transactions(txs);
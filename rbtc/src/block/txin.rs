@@ -1,12 +1,13 @@
-use crate::encode::error::Error;
+use crate::encode::error::{Error, Frame};
 use crate::encode::encode::{Encodable, Decodable};
 use crate::encode::varint::VarInt;
 use crate::block::script;
 
 use crate::block::outpoint::OutPoint;
 use crate::block::script::Script;
+use crate::block::witness::Witness;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 // https://github.com/bitcoin/bitcoin/blob/master/src/primitives/transaction.h
@@ -29,39 +30,69 @@ pub struct TxIn {
     pub previous: OutPoint,
     pub signature: Script, // scriptSig
     pub sequence: u32,
+    pub witness: Vec<Witness>, // scriptWitness, only serialized through Transaction
 } 
 
+impl Encodable for Vec<TxIn> {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let varint = VarInt::new(self.len() as u64);
+        varint.encode(w).map_err(|e| Error::InputsCount.caused_by(e))?;
+        for input in self {
+            input.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
 impl Decodable for Vec<TxIn> {
     
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<TxIn>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<TxIn>, Error> {
 
         trace!("decode");
 
         let mut result : Vec<TxIn> = Vec::new();
-        let count = VarInt::decode(r).map_err(|_| Error::InputsCount)?;
-        for _ in 0..count.0 {
-            let input = TxIn::decode(r)?;
+        let count = VarInt::decode(r).map_err(|e| Error::InputsCount.caused_by(e))?;
+        for i in 0..count.0 {
+            let input = TxIn::decode(r).map_err(|e| e.within(Frame::item("input", i as usize)))?;
             result.push(input);
         }
         Ok(result)
     }
 }
 
+impl Encodable for TxIn {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.previous.encode(w).map_err(|e| Error::TxInOutPoint.caused_by(e))?;
+        self.signature.encode(w).map_err(|e| Error::Signature.caused_by(e))?;
+        self.sequence.encode(w).map_err(|e| Error::TxInSequence.caused_by(e))?;
+
+        Ok(())
+    }
+}
 
 impl Decodable for TxIn {
     
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<TxIn, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<TxIn, Error> {
         
         trace!("decode");
 
-        let previous = OutPoint::decode(r).map_err(|_| Error::TxInOutPoint)?;
-        let signature = Script::decode(r).map_err(|_| Error::Signature)?;
-        let sequence = u32::decode(r).map_err(|_| Error::TxInSequence)?;
+        let previous = OutPoint::decode(r).map_err(|e| Error::TxInOutPoint.caused_by(e))?;
+        let signature = Script::decode(r).map_err(|e| Error::Signature.caused_by(e).within(Frame::new("script")))?;
+        let sequence = u32::decode(r).map_err(|e| Error::TxInSequence.caused_by(e))?;
 
         let result = TxIn {
             previous: previous,
             signature: signature,
             sequence: sequence,
+            witness: Vec::new(),
         };
         
         Ok(result)
@@ -1,9 +1,9 @@
-use crate::encode::error::Error;
+use crate::encode::error::{Error, Frame};
 use crate::encode::encode::{Encodable, Decodable};
 use crate::encode::varint::VarInt;
 use crate::block::script;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 // https://github.com/bitcoin/bitcoin/blob/master/src/primitives/transaction.h
@@ -11,23 +11,38 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::block::script::Script;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TxOut {
     pub amount: u64,
     pub script_pubkey: Script // scriptPubKey
 } 
 
+impl Encodable for Vec<TxOut> {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let varint = VarInt::new(self.len() as u64);
+        varint.encode(w).map_err(|e| Error::OutputsCount.caused_by(e))?;
+        for output in self {
+            output.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
 impl Decodable for Vec<TxOut> {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<TxOut>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<TxOut>, Error> {
         
         trace!("decode");
 
         let mut result : Vec<TxOut> = Vec::new();
-        let count = VarInt::decode(r).map_err(|_| Error::OutputsCount)?;
+        let count = VarInt::decode(r).map_err(|e| Error::OutputsCount.caused_by(e))?;
 
-        for _ in 0..count.0 {
-            let output = TxOut::decode(r)?;
+        for i in 0..count.0 {
+            let output = TxOut::decode(r).map_err(|e| e.within(Frame::item("output", i as usize)))?;
             result.push(output);
         }
 
@@ -35,14 +50,27 @@ impl Decodable for Vec<TxOut> {
     }
 }
 
+impl Encodable for TxOut {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.amount.encode(w).map_err(|e| Error::TxOutAmount.caused_by(e))?;
+        self.script_pubkey.encode(w).map_err(|e| Error::ScriptPubKey.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
 impl Decodable for TxOut {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<TxOut, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<TxOut, Error> {
         
         trace!("decode");
 
-        let amount = u64::decode(r).map_err(|_| Error::TxOutAmount)?;
-        let script_pubkey = Script::decode(r).map_err(|_| Error::ScriptPubKey)?;
+        let amount = u64::decode(r).map_err(|e| Error::TxOutAmount.caused_by(e))?;
+        let script_pubkey = Script::decode(r).map_err(|e| Error::ScriptPubKey.caused_by(e).within(Frame::new("script")))?;
 
         let result = TxOut {
             amount: amount,
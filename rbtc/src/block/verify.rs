@@ -0,0 +1,228 @@
+use crate::encode::error::Error;
+use crate::block::interpreter::{verify_script, VerifyFlags, TransactionChecker};
+use crate::block::sighash::SigHashCache;
+use crate::block::standard::ScriptType;
+use crate::block::transaction::Transaction;
+use crate::block::txout::TxOut;
+
+use std::fmt;
+
+/// The verification of an input of a transaction against the output it spends.
+#[derive(Debug, PartialEq)]
+pub struct InputReport {
+    /// Index of the input
+    pub index: usize,
+    /// Type of the scriptPubKey spent by the input
+    pub spent_type: ScriptType,
+    /// Ok when the input satisfies the scriptPubKey, else the first rule it breaks
+    pub result: Result<(), Error>,
+}
+
+impl InputReport {
+
+    pub fn is_valid(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+impl fmt::Display for InputReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            Ok(()) => write!(f, "input {} {}: ok", self.index, self.spent_type.name()),
+            Err(error) => write!(f, "input {} {}: {:?}", self.index, self.spent_type.name(), error),
+        }
+    }
+}
+
+/// https://en.bitcoin.it/wiki/Transaction#Verification
+///
+/// # Verify a transaction
+///
+/// Verifies the scriptSig and witness of each input against the scriptPubKey of the
+/// output it spends, ECDSA signatures for the legacy and segwit v0 outputs, Schnorr
+/// signatures for the taproot key path spends and tapscripts. Nothing else of the
+/// transaction is checked: amounts, lock times of the transaction, or the outputs being
+/// unspent.
+///
+/// `spent_outputs` are the outputs spent by the inputs, in the order of the inputs, as
+/// found in the previous transactions. An input failing does not stop the others from
+/// being verified, each gets its own report.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp CheckInputScripts
+pub fn verify_transaction(transaction: &Transaction, spent_outputs: &[TxOut], flags: VerifyFlags) -> Result<Vec<InputReport>, Error> {
+
+    trace!("verify_transaction");
+
+    if spent_outputs.len() != transaction.inputs.len() {
+        return Err(Error::SpentOutputsCount);
+    }
+
+    let cache = SigHashCache::with_spent_outputs(transaction, spent_outputs);
+    let mut result = Vec::with_capacity(spent_outputs.len());
+    for (index, (input, spent)) in transaction.inputs.iter().zip(spent_outputs).enumerate() {
+        let checker = TransactionChecker::new(transaction, index, spent_outputs, &cache);
+        let report = InputReport {
+            index: index,
+            spent_type: ScriptType::from_script(&spent.script_pubkey),
+            result: verify_script(&input.signature, &spent.script_pubkey, &input.witness, flags, &checker),
+        };
+        result.push(report);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::Decodable;
+    use crate::block::interpreter::VerifyFlags;
+    use crate::block::interpreter::test::{parse_script, parse_flags};
+    use crate::block::script::Script;
+    use crate::block::standard::ScriptType;
+    use crate::block::transaction::Transaction;
+    use crate::block::txout::TxOut;
+    use crate::block::verify::verify_transaction;
+    use crate::utils::sha256::Sha256;
+
+    use serde_json::Value;
+    use std::io::Cursor;
+
+    fn transaction(data: &str) -> Transaction {
+        let bytes = hex::decode(data).unwrap();
+        let mut r = Cursor::new(&bytes);
+        Transaction::decode(&mut r).unwrap()
+    }
+
+    fn output(data: &str) -> TxOut {
+        let bytes = hex::decode(data).unwrap();
+        let mut r = Cursor::new(&bytes);
+        TxOut::decode(&mut r).unwrap()
+    }
+
+    // f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16, spending the coinbase of block 9
+    const FIRST_TRANSACTION : &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+    const BLOCK_9_COINBASE_OUTPUT : &str = "00f2052a0100000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac";
+
+    #[test]
+    fn when_first_transaction_then_input_valid() {
+
+        let tx = transaction(FIRST_TRANSACTION);
+        let spent = vec![output(BLOCK_9_COINBASE_OUTPUT)];
+        let reports = verify_transaction(&tx, &spent, VerifyFlags::consensus() | VerifyFlags::LOW_S | VerifyFlags::STRICTENC).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_valid());
+        assert!(matches!(reports[0].spent_type, ScriptType::PubKey(_)));
+        assert_eq!(reports[0].to_string(), "input 0 pubkey: ok");
+    }
+
+    #[test]
+    fn when_signature_tampered_then_input_invalid() {
+
+        let mut tx = transaction(FIRST_TRANSACTION);
+        // the last byte of R
+        tx.inputs[0].signature.content[36] ^= 0x01;
+        let spent = vec![output(BLOCK_9_COINBASE_OUTPUT)];
+        let reports = verify_transaction(&tx, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports[0].result, Err(Error::ScriptEvalFalse));
+        assert_eq!(reports[0].to_string(), "input 0 pubkey: ScriptEvalFalse");
+    }
+
+    #[test]
+    fn when_spent_outputs_missing_then_fail() {
+        let tx = transaction(FIRST_TRANSACTION);
+        assert_eq!(verify_transaction(&tx, &[], VerifyFlags::consensus()), Err(Error::SpentOutputsCount));
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/tx_valid.json
+    #[test]
+    fn when_core_valid_transactions_then_inputs_valid() {
+
+        let vectors : Vec<Value> = serde_json::from_str(include_str!("../tests/data/tx_valid.json")).unwrap();
+        let mut count = 0;
+        for vector in vectors.iter().filter(|vector| vector[0].is_array()) {
+
+            let tx = transaction(vector[1].as_str().unwrap());
+            let prevouts : Vec<(String, u32, TxOut)> = vector[0].as_array().unwrap().iter().map(|prevout| {
+                let hash = prevout[0].as_str().unwrap().to_string();
+                let index = prevout[1].as_i64().unwrap() as u32;
+                let script_pubkey = parse_script(prevout[2].as_str().unwrap());
+                let amount = prevout.get(3).map(|amount| amount.as_u64().unwrap()).unwrap_or(0);
+                (hash, index, TxOut { amount: amount, script_pubkey: script_pubkey })
+            }).collect();
+
+            let spent : Vec<TxOut> = tx.inputs.iter().map(|input| {
+                let hash = Sha256::new(input.previous.transaction_hash).to_string();
+                prevouts.iter()
+                    .find(|(h, i, _)| *h == hash && *i == input.previous.index)
+                    .map(|(_, _, output)| output.clone())
+                    .unwrap()
+            }).collect();
+
+            let excluded = vector[2].as_str().unwrap();
+            let excluded = if excluded == "NONE" { VerifyFlags::empty() } else { parse_flags(excluded) };
+            let flags = VerifyFlags::all() - excluded;
+
+            for report in verify_transaction(&tx, &spent, flags).unwrap() {
+                assert!(report.is_valid(), "{} {:?}", report, vector);
+            }
+            count += 1;
+        }
+        assert!(count > 100, "{}", count);
+    }
+
+    /// The signed transaction of the BIP341 key path spending vector and the outputs it spends.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/bip341_wallet_vectors.json
+    fn key_path_spending() -> (Transaction, Vec<TxOut>) {
+
+        let vectors : Value = serde_json::from_str(include_str!("../tests/data/bip341_wallet_vectors.json")).unwrap();
+        let vector = &vectors["keyPathSpending"][0];
+
+        let tx = transaction(vector["auxiliary"]["fullySignedTx"].as_str().unwrap());
+        let spent = vector["given"]["utxosSpent"].as_array().unwrap().iter().map(|utxo| TxOut {
+            amount: utxo["amountSats"].as_u64().unwrap(),
+            script_pubkey: Script::new(hex::decode(utxo["scriptPubKey"].as_str().unwrap()).unwrap()),
+        }).collect();
+        (tx, spent)
+    }
+
+    #[test]
+    fn when_bip341_key_path_spending_then_inputs_valid() {
+
+        let (tx, spent) = key_path_spending();
+        let reports = verify_transaction(&tx, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports.len(), 9);
+        for report in &reports {
+            assert!(report.is_valid(), "{}", report);
+        }
+        let taproot = reports.iter().filter(|report| matches!(report.spent_type, ScriptType::WitnessV1Taproot(_))).count();
+        assert_eq!(taproot, 7);
+
+        let index = reports.iter().position(|report| matches!(report.spent_type, ScriptType::WitnessV1Taproot(_))).unwrap();
+        let mut tampered = tx;
+        tampered.inputs[index].witness[0].data[0] ^= 0x01;
+        let reports = verify_transaction(&tampered, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports[index].result, Err(Error::ScriptSchnorrSig));
+        assert!(reports.iter().filter(|report| report.index != index).all(|report| report.is_valid()));
+    }
+
+    #[test]
+    fn when_taproot_signature_size_or_hashtype_invalid_then_reason() {
+
+        let (tx, spent) = key_path_spending();
+        let mut tampered = tx;
+        let index = tampered.inputs.iter().position(|input| input.witness.len() == 1).unwrap();
+        tampered.inputs[index].witness[0].data.truncate(63);
+        let reports = verify_transaction(&tampered, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports[index].result, Err(Error::ScriptSchnorrSigSize));
+
+        tampered.inputs[index].witness[0].data.resize(65, 0x00);
+        let reports = verify_transaction(&tampered, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports[index].result, Err(Error::ScriptSchnorrSigHashType));
+
+        tampered.inputs[index].witness[0].data[64] = 0x04;
+        let reports = verify_transaction(&tampered, &spent, VerifyFlags::consensus()).unwrap();
+        assert_eq!(reports[index].result, Err(Error::ScriptSchnorrSigHashType));
+    }
+}
@@ -1,8 +1,8 @@
-use crate::encode::error::Error;
-use crate::encode::encode::{Encodable, Decodable};
+use crate::encode::error::{Error, Frame};
+use crate::encode::encode::{Encodable, Decodable, MAX_SIZE, read_bytes};
 use crate::encode::varint::VarInt;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 #[derive(Debug)]
@@ -10,16 +10,31 @@ pub struct Witness {
     pub data: Vec<u8>
 }
 
+impl Encodable for Vec<Witness> {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let varint = VarInt::new(self.len() as u64);
+        varint.encode(w).map_err(|e| Error::WitnessesCount.caused_by(e))?;
+        for witness in self {
+            witness.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
 impl Decodable for Vec<Witness> {
         
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<Witness>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<Witness>, Error> {
         
         trace!("decode");
 
         let mut result: Vec<Witness> = Vec::new();
-        let count = VarInt::decode(r).map_err(|_| Error::WitnessesCount)?;
-        for _ in 0..count.0 {
-            let witness = Witness::decode(r)?;
+        let count = VarInt::decode(r).map_err(|e| Error::WitnessesCount.caused_by(e))?;
+        for i in 0..count.0 {
+            let witness = Witness::decode(r).map_err(|e| e.within(Frame::item("item", i as usize)))?;
             result.push(witness);
         }
 
@@ -27,16 +42,31 @@ impl Decodable for Vec<Witness> {
     }
 }
 
+impl Encodable for Witness {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let varlen = VarInt::new(self.data.len() as u64);
+        varlen.encode(w).map_err(|e| Error::WitnessLen.caused_by(e))?;
+        self.data.encode(w).map_err(|e| Error::WitnessData.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
 impl Decodable for Witness {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Witness, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Witness, Error> {
 
         trace!("decode");
 
-        let varlen = VarInt::decode(r).map_err(|_| Error::WitnessLen)?;
-        let mut data = vec![0u8; varlen.0 as usize];
-        let mut data_ref = data.as_mut_slice();
-        r.read_exact(&mut data_ref).map_err(|_| Error::WitnessData)?;
+        let varlen = VarInt::decode(r).map_err(|e| Error::WitnessLen.caused_by(e))?;
+        if varlen.0 > MAX_SIZE {
+            return Err(Error::WitnessTooLarge);
+        }
+        let data = read_bytes(r, varlen.0).map_err(|e| Error::WitnessData.caused_by(e))?;
 
         let result = Witness { data: data };
 
@@ -51,13 +81,14 @@ mod test {
     use crate::encode::encode::{Encodable, Decodable};
     use crate::block::witness;
     use crate::block::witness::Witness;
+    use crate::encode::encode::test::fuzz;
 
     use std::io::Cursor;
 
     #[test]
     fn decode_0x00_then_1_byte() {
         let data: Vec<u8> = vec![0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_ok());
         assert_eq!(c.position(), 1);
@@ -69,7 +100,7 @@ mod test {
     #[test]
     fn decode_0x01_then_1_byte() {
         let data: Vec<u8> = vec![0x01, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_ok());
         assert_eq!(c.position(), 2);
@@ -81,7 +112,7 @@ mod test {
     #[test]
     fn decode_0x02_then_2_byte() {
         let data: Vec<u8> = vec![0x02, 0x00, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_ok());
         assert_eq!(c.position(), 3);
@@ -96,7 +127,7 @@ mod test {
             0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00,
         ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_ok());
         assert_eq!(c.position(), 0x11);
@@ -108,7 +139,7 @@ mod test {
     #[test]
     fn decode_invalid_size_then_fail() {
         let data: Vec<u8> = vec![0x01];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_err());
         assert_eq!(c.position(), 0x01);
@@ -123,7 +154,7 @@ mod test {
     #[test]
     fn decode_invalid_content_then_fail() {
         let data: Vec<u8> = vec![];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = Witness::decode(&mut c);
         assert!(result.is_err());
 
@@ -133,4 +164,34 @@ mod test {
             panic!("should have failed");
         }
     }
+
+    #[test]
+    fn encode_0x02_then_3_byte() {
+        let witness = Witness {
+            data: vec![0xAB, 0xCD],
+        };
+        let mut data: Vec<u8> = Vec::new();
+        let result = witness.encode(&mut data);
+        assert!(result.is_ok());
+        assert_eq!(data, vec![0x02, 0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn encode_vec_then_count_prefixed() {
+        let witnesses = vec![
+            Witness { data: vec![0x01] },
+            Witness { data: vec![] },
+        ];
+        let mut data: Vec<u8> = Vec::new();
+        let result = witnesses.encode(&mut data);
+        assert!(result.is_ok());
+        assert_eq!(data, vec![0x02, 0x01, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn decode_over_max_size_then_fail() {
+        // 0x02000001 then 2^64 - 1 bytes
+        fuzz::<Witness>(&[0xfe, 0x01, 0x00, 0x00, 0x02], Error::WitnessTooLarge);
+        fuzz::<Witness>(&[0xff; 9], Error::WitnessTooLarge);
+    }
 }
@@ -0,0 +1,233 @@
+use crate::encode::error::Error;
+use crate::block::opcode::Opcode;
+use crate::block::script::Script;
+use crate::block::standard::ScriptType;
+use crate::chain::chainparams::ChainParams;
+use crate::utils::base58;
+use crate::utils::bech32;
+
+/// https://en.bitcoin.it/wiki/Invoice_address
+///
+/// # Address
+///
+/// The string form of an output script a wallet can pay to. The legacy ones are
+/// Base58Check encoded with a network version byte, the segwit ones are Bech32 (v0)
+/// or Bech32m (v1+) encoded with a network human readable part.
+///
+/// ```
+/// +-------------------+------------------------------+-------------------------------+
+/// | Address           | main                         | testnet3 / signet / regtest   |
+/// +-------------------+------------------------------+-------------------------------+
+/// | pubkeyhash        | 1...                         | m... or n...                  |
+/// +-------------------+------------------------------+-------------------------------+
+/// | scripthash        | 3...                         | 2...                          |
+/// +-------------------+------------------------------+-------------------------------+
+/// | witness program   | bc1q... (v0), bc1p... (v1)   | tb1... / tb1... / bcrt1...    |
+/// +-------------------+------------------------------+-------------------------------+
+/// ```
+///
+/// The pubkey, multisig and null data scripts have no address.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/key_io.cpp
+#[derive(Debug, Clone, PartialEq)]
+pub enum Address {
+    PubKeyHash([u8; 20]),
+    ScriptHash([u8; 20]),
+    WitnessProgram {
+        version: u8,
+        program: Vec<u8>,
+    },
+}
+
+impl Address {
+
+    /// Address of a classified script, if it has one.
+    pub fn from_script_type(script_type: &ScriptType) -> Option<Address> {
+        match script_type {
+            ScriptType::PubKeyHash(hash) => Some(Address::PubKeyHash(*hash)),
+            ScriptType::ScriptHash(hash) => Some(Address::ScriptHash(*hash)),
+            ScriptType::WitnessV0KeyHash(hash) => Some(Address::witness(0, hash)),
+            ScriptType::WitnessV0ScriptHash(hash) => Some(Address::witness(0, hash)),
+            ScriptType::WitnessV1Taproot(key) => Some(Address::witness(1, key)),
+            ScriptType::WitnessUnknown { version, program } => Some(Address::witness(*version, program)),
+            _ => None,
+        }
+    }
+
+    /// Address of an output script, if it has one.
+    pub fn from_script(script: &Script) -> Option<Address> {
+        Address::from_script_type(&script.classify())
+    }
+
+    fn witness(version: u8, program: &[u8]) -> Address {
+        Address::WitnessProgram {
+            version: version,
+            program: program.to_vec(),
+        }
+    }
+
+    /// The output script paying to the address.
+    pub fn script_pubkey(&self) -> Script {
+
+        let mut result = Script::default();
+        match self {
+            Address::PubKeyHash(hash) => {
+                result.push_opcode(Opcode::Dup);
+                result.push_opcode(Opcode::Hash160);
+                result.push_data(hash);
+                result.push_opcode(Opcode::EqualVerify);
+                result.push_opcode(Opcode::CheckSig);
+            },
+            Address::ScriptHash(hash) => {
+                result.push_opcode(Opcode::Hash160);
+                result.push_data(hash);
+                result.push_opcode(Opcode::Equal);
+            },
+            Address::WitnessProgram { version, program } => {
+                result.push_int(*version as i64);
+                result.push_data(program);
+            },
+        }
+        result
+    }
+
+    pub fn encode(&self, params: &ChainParams) -> String {
+        match self {
+            Address::PubKeyHash(hash) => Address::encode_base58(params.pubkey_address_prefix, hash),
+            Address::ScriptHash(hash) => Address::encode_base58(params.script_address_prefix, hash),
+            Address::WitnessProgram { version, program } => bech32::encode_segwit(params.bech32_hrp, *version, program),
+        }
+    }
+
+    fn encode_base58(prefix: u8, hash: &[u8; 20]) -> String {
+        let mut data = vec![prefix];
+        data.extend_from_slice(hash);
+        base58::encode_check(&data)
+    }
+
+    /// Decodes an address of the `params` network.
+    pub fn decode(address: &str, params: &ChainParams) -> Result<Address, Error> {
+
+        trace!("decode");
+
+        let hrp = format!("{}1", params.bech32_hrp);
+        let is_bech32 = address.get(..hrp.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(&hrp));
+        if is_bech32 {
+            let (version, program) = bech32::decode_segwit(params.bech32_hrp, address)?;
+            return Ok(Address::witness(version, &program));
+        }
+
+        let data = base58::decode_check(address)?;
+        if data.len() != 21 {
+            return Err(Error::AddressLength);
+        }
+
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&data[1..]);
+        if data[0] == params.pubkey_address_prefix {
+            return Ok(Address::PubKeyHash(hash));
+        }
+        if data[0] == params.script_address_prefix {
+            return Ok(Address::ScriptHash(hash));
+        }
+        Err(Error::AddressPrefix)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::block::script::Script;
+    use crate::chain::address::Address;
+    use crate::chain::chainparams::ChainParams;
+    use crate::utils::base58;
+
+    use serde_json::Value;
+
+    fn params(chain: &str) -> ChainParams {
+        match chain {
+            "main" => ChainParams::mainnet(),
+            // testnet4 shares the testnet3 prefixes
+            "test" | "testnet4" => ChainParams::testnet(),
+            "signet" => ChainParams::signet(),
+            "regtest" => ChainParams::regtest(),
+            _ => panic!("unknown chain {}", chain),
+        }
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/key_io_valid.json
+    #[test]
+    fn when_core_valid_vectors_then_same_script() {
+
+        let vectors : Vec<Value> = serde_json::from_str(include_str!("../tests/data/key_io_valid.json")).unwrap();
+        assert!(!vectors.is_empty());
+        for vector in vectors {
+
+            let encoded = vector[0].as_str().unwrap();
+            let payload = hex::decode(vector[1].as_str().unwrap()).unwrap();
+            let params = params(vector[2]["chain"].as_str().unwrap());
+
+            if vector[2]["isPrivkey"].as_bool().unwrap() {
+                // WIF: secret prefix, 32 bytes key, 0x01 when the public key is compressed
+                let mut expected = vec![params.secret_key_prefix];
+                expected.extend(&payload);
+                if vector[2]["isCompressed"].as_bool().unwrap() {
+                    expected.push(0x01);
+                }
+                assert_eq!(base58::decode_check(encoded), Ok(expected));
+                continue;
+            }
+
+            let script = Script::new(payload);
+            let address = Address::decode(encoded, &params).unwrap();
+            assert_eq!(address.script_pubkey(), script, "{}", encoded);
+            assert_eq!(Address::from_script(&script), Some(address.clone()));
+            assert_eq!(address.encode(&params), encoded);
+
+            if vector[2]["tryCaseFlip"].as_bool().unwrap_or(false) {
+                let flipped : String = encoded.chars()
+                    .map(|c| if c.is_ascii_lowercase() { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() })
+                    .collect();
+                assert_eq!(Address::decode(&flipped, &params), Ok(address));
+            }
+        }
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/key_io_invalid.json
+    #[test]
+    fn when_core_invalid_vectors_then_error() {
+
+        let vectors : Vec<Value> = serde_json::from_str(include_str!("../tests/data/key_io_invalid.json")).unwrap();
+        assert!(!vectors.is_empty());
+        for vector in vectors {
+            let encoded = vector[0].as_str().unwrap();
+            for chain in &["main", "test", "signet", "regtest"] {
+                assert!(Address::decode(encoded, &params(chain)).is_err(), "{} {}", chain, encoded);
+            }
+        }
+    }
+
+    #[test]
+    fn when_other_network_then_error() {
+
+        let testnet = "mzK2FFDEhxqHcmrJw1ysqFkVyhUULo45hZ";
+        assert!(Address::decode(testnet, &ChainParams::testnet()).is_ok());
+        assert_eq!(Address::decode(testnet, &ChainParams::mainnet()), Err(Error::AddressPrefix));
+        assert_eq!(Address::decode("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", &ChainParams::regtest()), Err(Error::Base58Char));
+        assert!(Address::decode("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", &ChainParams::signet()).is_ok());
+    }
+
+    #[test]
+    fn when_non_ascii_then_error() {
+        assert_eq!(Address::decode("bcé1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", &ChainParams::mainnet()), Err(Error::Base58Char));
+        assert_eq!(Address::decode("é", &ChainParams::mainnet()), Err(Error::Base58Char));
+    }
+
+    #[test]
+    fn when_no_address_then_none() {
+        assert_eq!(Address::from_script(&Script::from_asm("OP_RETURN 0a0b0c0d0e").unwrap()), None);
+        let genesis = ChainParams::mainnet().genesis_block();
+        assert_eq!(Address::from_script(&genesis.transactions.get(0).unwrap().outputs[0].script_pubkey), None);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::encode::varint::VarInt;
+use crate::chain::blockstore::BlockPos;
+use crate::utils::sha256::Sha256;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Where the blocks of a `BlockStore` are, by hash, built by reading the files once.
+///
+/// ```
+/// +----------+----------+----------------------------+
+/// | Size     | Name     | Type                       |
+/// +----------+----------+----------------------------+
+/// | 1+       | count    | var_int                    |
+/// +----------+----------+----------------------------+
+/// | 44 * ?   | blocks   | (Sha256, u32, u64)[]       |
+/// +----------+----------+----------------------------+
+/// ```
+///
+#[derive(Debug, Default, PartialEq)]
+pub struct BlockIndex {
+    positions: HashMap<Sha256, BlockPos>,
+}
+
+impl BlockIndex {
+
+    pub fn new() -> BlockIndex {
+        BlockIndex::default()
+    }
+
+    pub fn insert(&mut self, hash: Sha256, pos: BlockPos) {
+        self.positions.insert(hash, pos);
+    }
+
+    /// Indexes the serialized block `data`, hashing its header.
+    pub fn insert_header(&mut self, data: &[u8], pos: BlockPos) -> Result<(), Error> {
+        if data.len() < 80 {
+            return Err(Error::BlockFileSize);
+        }
+        self.insert(Sha256::double(&data[..80]), pos);
+        Ok(())
+    }
+
+    pub fn get(&self, hash: &Sha256) -> Option<BlockPos> {
+        self.positions.get(hash).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+impl Encodable for BlockIndex {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        VarInt::new(self.positions.len() as u64).encode(w).map_err(|e| Error::BlockIndexCount.caused_by(e))?;
+        for (hash, pos) in &self.positions {
+            hash.encode(w)?;
+            pos.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decodable for BlockIndex {
+
+    fn decode<R: Read>(r: &mut R) -> Result<BlockIndex, Error> {
+
+        trace!("decode");
+
+        let count = VarInt::decode(r).map_err(|e| Error::BlockIndexCount.caused_by(e))?;
+        let mut result = BlockIndex::new();
+        for _ in 0..count.0 {
+            let hash = Sha256::decode(r)?;
+            let pos = BlockPos::decode(r)?;
+            result.insert(hash, pos);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::chain::blockindex::BlockIndex;
+    use crate::chain::blockstore::BlockPos;
+    use crate::utils::sha256::Sha256;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_index_then_encode_decode() {
+
+        let mut index = BlockIndex::new();
+        index.insert(Sha256::new([1; 32]), BlockPos { file: 0, offset: 8 });
+        index.insert(Sha256::new([2; 32]), BlockPos { file: 3, offset: 0x1_0000_0008 });
+        let mut data = vec![];
+        index.encode(&mut data).unwrap();
+        assert_eq!(data.len(), 1 + 2 * 44);
+        assert_eq!(BlockIndex::decode(&mut Cursor::new(&data)), Ok(index));
+    }
+
+    #[test]
+    fn when_header_too_short_then_error() {
+        let mut index = BlockIndex::new();
+        assert_eq!(index.insert_header(&[0; 79], BlockPos { file: 0, offset: 8 }), Err(Error::BlockFileSize));
+        assert!(index.is_empty());
+    }
+}
@@ -0,0 +1,541 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::block::block::Block;
+use crate::chain::blockindex::BlockIndex;
+use crate::network::message::Magic;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write, Seek, SeekFrom, BufReader};
+use std::path::{Path, PathBuf};
+use byteorder::{LittleEndian, ByteOrder};
+
+/// Size a block file does not grow over, 128 MiB
+pub const MAX_BLOCKFILE_SIZE : u64 = 0x800_0000;
+/// Magic and size in front of each block
+const RECORD_HEADER_SIZE : u64 = 8;
+/// File of the obfuscation key, in the blocks directory
+const XOR_FILE : &str = "xor.dat";
+
+/// Where a block is stored: the number of its blk?????.dat file and the offset of the
+/// block in the file, after its magic and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockPos {
+    pub file: u32,
+    pub offset: u64,
+}
+
+/// The 8 bytes the block files are xored with, repeated from the start of each file.
+/// All zeros leaves the files in clear, as before Core 28.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct XorKey(pub [u8; 8]);
+
+impl XorKey {
+
+    /// Obfuscates, or reveals, `data` read or written at `offset` in a file.
+    pub fn apply(&self, data: &mut [u8], offset: u64) {
+        if self.0 == [0; 8] {
+            return;
+        }
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte ^= self.0[((offset + i as u64) % 8) as usize];
+        }
+    }
+}
+
+/// Reads the bytes of a block file revealed with its key, `offset` being the position
+/// in the file of the next byte read.
+pub struct XorReader<R> {
+    inner: R,
+    key: XorKey,
+    offset: u64,
+}
+
+impl<R: Read> XorReader<R> {
+
+    pub fn new(inner: R, key: XorKey, offset: u64) -> XorReader<R> {
+        XorReader {
+            inner: inner,
+            key: key,
+            offset: offset,
+        }
+    }
+
+    /// Position in the file of the next byte read.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: Read> Read for XorReader<R> {
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.key.apply(&mut buf[..len], self.offset);
+        self.offset += len as u64;
+        Ok(len)
+    }
+}
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/node/blockstorage.cpp
+///
+/// # Block store
+///
+/// The blocks of a Core `blocks` directory, appended one after the other to the
+/// blk00000.dat, blk00001.dat, ... files, each block preceded by the magic of the
+/// network and its size:
+///
+/// ```
+/// +----------+----------+----------------------------+
+/// | Size     | Name     | Type                       |
+/// +----------+----------+----------------------------+
+/// | 4        | magic    | Magic, as sent over wire   |
+/// +----------+----------+----------------------------+
+/// | 4        | size     | u32                        |
+/// +----------+----------+----------------------------+
+/// | size     | block    | Block                      |
+/// +----------+----------+----------------------------+
+/// ```
+///
+/// Core preallocates the files by chunks of zeros, skipped when reading. The bytes of
+/// the files are xored with the key of xor.dat when it holds one.
+///
+pub struct BlockStore {
+    dir: PathBuf,
+    magic: Magic,
+    key: XorKey,
+    max_file_size: u64,
+}
+
+impl BlockStore {
+
+    /// Opens the blocks directory `dir` of a network, with the key of its xor.dat if any.
+    pub fn open(dir: &Path, magic: Magic) -> Result<BlockStore, Error> {
+
+        trace!("open");
+
+        let path = dir.join(XOR_FILE);
+        let key = if path.exists() {
+            let data = fs::read(&path).map_err(|e| Error::BlockFileRead.caused_by(e))?;
+            if data.len() != 8 {
+                return Err(Error::BlockFileXorKey);
+            }
+            let mut key = [0u8; 8];
+            key.copy_from_slice(&data);
+            XorKey(key)
+        } else {
+            XorKey::default()
+        };
+
+        let result = BlockStore {
+            dir: dir.to_path_buf(),
+            magic: magic,
+            key: key,
+            max_file_size: MAX_BLOCKFILE_SIZE,
+        };
+        Ok(result)
+    }
+
+    /// Creates the blocks directory `dir` and its xor.dat. The key of an existing
+    /// directory cannot change, its files would not read anymore.
+    pub fn create(dir: &Path, magic: Magic, key: XorKey) -> Result<BlockStore, Error> {
+
+        trace!("create");
+
+        fs::create_dir_all(dir).map_err(|e| Error::BlockFileWrite.caused_by(e))?;
+        let path = dir.join(XOR_FILE);
+        if !path.exists() {
+            fs::write(&path, key.0).map_err(|e| Error::BlockFileWrite.caused_by(e))?;
+        }
+        let result = BlockStore::open(dir, magic)?;
+        if result.key != key {
+            return Err(Error::BlockFileXorKey);
+        }
+        Ok(result)
+    }
+
+    /// Files grow up to `size` bytes instead of 128 MiB.
+    pub fn with_max_file_size(mut self, size: u64) -> BlockStore {
+        self.max_file_size = size;
+        self
+    }
+
+    pub fn key(&self) -> XorKey {
+        self.key
+    }
+
+    /// Path of the blk?????.dat file `file`.
+    pub fn path(&self, file: u32) -> PathBuf {
+        self.dir.join(format!("blk{:05}.dat", file))
+    }
+
+    /// Number of block files, from blk00000.dat up to the first one missing.
+    pub fn files(&self) -> u32 {
+        let mut result = 0;
+        while self.path(result).exists() {
+            result += 1;
+        }
+        result
+    }
+
+    /// The serialized block at `pos`, revealed.
+    pub fn read_raw(&self, pos: BlockPos) -> Result<Vec<u8>, Error> {
+
+        trace!("read_raw");
+
+        let (reader, size) = self.open_record(pos)?;
+        let mut result = vec![];
+        reader.take(size).read_to_end(&mut result).map_err(|e| Error::BlockFileRead.caused_by(e))?;
+        if result.len() as u64 != size {
+            return Err(Error::BlockFileSize);
+        }
+        Ok(result)
+    }
+
+    /// The block at `pos`, decoded as it is read from its file.
+    pub fn read(&self, pos: BlockPos) -> Result<Block, Error> {
+
+        trace!("read");
+
+        let (reader, size) = self.open_record(pos)?;
+        let mut reader = reader.take(size);
+        let result = Block::decode(&mut reader).map_err(|e| e.at(reader.get_ref().offset()))?;
+        if reader.limit() != 0 {
+            return Err(Error::RemainingContent);
+        }
+        Ok(result)
+    }
+
+    /// Checks the magic and size in front of the block at `pos`, leaving the reader
+    /// on the first byte of the block.
+    fn open_record(&self, pos: BlockPos) -> Result<(XorReader<BufReader<File>>, u64), Error> {
+
+        if pos.offset < RECORD_HEADER_SIZE {
+            return Err(Error::BlockFileMagic);
+        }
+        let mut file = File::open(self.path(pos.file)).map_err(|e| Error::BlockFileOpen.caused_by(e))?;
+        let len = file.metadata().map_err(|e| Error::BlockFileRead.caused_by(e))?.len();
+        file.seek(SeekFrom::Start(pos.offset - RECORD_HEADER_SIZE)).map_err(|e| Error::BlockFileRead.caused_by(e))?;
+        let mut reader = XorReader::new(BufReader::new(file), self.key, pos.offset - RECORD_HEADER_SIZE);
+
+        let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+        reader.read_exact(&mut header).map_err(|e| Error::BlockFileRead.caused_by(e))?;
+        if header[..4] != self.magic_bytes()[..] {
+            return Err(Error::BlockFileMagic);
+        }
+        let size = LittleEndian::read_u32(&header[4..]) as u64;
+        if size > self.max_file_size || pos.offset + size > len {
+            return Err(Error::BlockFileSize);
+        }
+        Ok((reader, size))
+    }
+
+    /// Appends the block to the last file, or to a new one when it would grow over
+    /// the maximum size.
+    pub fn write(&mut self, block: &Block) -> Result<BlockPos, Error> {
+
+        trace!("write");
+
+        let mut data = vec![];
+        block.encode(&mut data)?;
+
+        let mut record = self.magic_bytes();
+        record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(&data);
+
+        let mut file = self.files().saturating_sub(1);
+        let mut len = fs::metadata(self.path(file)).map(|metadata| metadata.len()).unwrap_or(0);
+        if len > 0 && len + record.len() as u64 > self.max_file_size {
+            file += 1;
+            len = 0;
+        }
+
+        self.key.apply(&mut record, len);
+        let mut handle = OpenOptions::new().create(true).append(true).open(self.path(file)).map_err(|e| Error::BlockFileOpen.caused_by(e))?;
+        handle.write_all(&record).map_err(|e| Error::BlockFileWrite.caused_by(e))?;
+        handle.sync_data().map_err(|e| Error::BlockFileWrite.caused_by(e))?;
+
+        let result = BlockPos {
+            file: file,
+            offset: len + RECORD_HEADER_SIZE,
+        };
+        Ok(result)
+    }
+
+    /// The serialized blocks of all the files, in the order they are stored.
+    pub fn records(&self) -> BlockRecords<'_> {
+        BlockRecords {
+            store: self,
+            file: 0,
+            reader: None,
+        }
+    }
+
+    /// The blocks of all the files, in the order they are stored, which is not the
+    /// order of the chain: Core stores the blocks as they are downloaded.
+    pub fn blocks(&self) -> impl Iterator<Item = Result<(BlockPos, Block), Error>> + '_ {
+        self.records().map(|record| {
+            let (pos, data) = record?;
+            Ok((pos, Block::parse(&data)?))
+        })
+    }
+
+    /// The positions of the blocks of all the files, by hash.
+    pub fn index(&self) -> Result<BlockIndex, Error> {
+
+        trace!("index");
+
+        let mut result = BlockIndex::new();
+        for record in self.records() {
+            let (pos, data) = record?;
+            result.insert_header(&data, pos)?;
+        }
+        Ok(result)
+    }
+
+    fn magic_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        self.magic.encode(&mut result).unwrap_or_default();
+        result
+    }
+}
+
+/// Iterator over the serialized blocks of a `BlockStore`, file after file. A file is
+/// streamed and searched for the magic of the next block, which skips the zeros Core
+/// preallocates, a single block being in memory at a time. A block truncated by the
+/// end of its file is an error, the iteration going on with the next file.
+pub struct BlockRecords<'a> {
+    store: &'a BlockStore,
+    file: u32,
+    reader: Option<XorReader<BufReader<File>>>,
+}
+
+impl<'a> BlockRecords<'a> {
+
+    /// Moves past the next magic of the file, false at the end of the file.
+    fn skip_to_magic(reader: &mut XorReader<BufReader<File>>, magic: &[u8]) -> bool {
+        let mut window = vec![0u8; magic.len()];
+        let mut read = 0;
+        let mut byte = [0u8; 1];
+        while reader.read_exact(&mut byte).is_ok() {
+            window.remove(0);
+            window.push(byte[0]);
+            read += 1;
+            if read >= magic.len() && window == magic {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn next_file(&mut self) {
+        self.reader = None;
+        self.file += 1;
+    }
+}
+
+impl<'a> Iterator for BlockRecords<'a> {
+    type Item = Result<(BlockPos, Vec<u8>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        let magic = self.store.magic_bytes();
+        loop {
+            if self.reader.is_none() {
+                let path = self.store.path(self.file);
+                if !path.exists() {
+                    return None;
+                }
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(_) => {
+                        self.file += 1;
+                        return Some(Err(Error::BlockFileRead));
+                    },
+                };
+                self.reader = Some(XorReader::new(BufReader::new(file), self.store.key, 0));
+            }
+            let reader = self.reader.as_mut()?;
+
+            let mut size = [0u8; 4];
+            if !BlockRecords::skip_to_magic(reader, &magic) || reader.read_exact(&mut size).is_err() {
+                self.next_file();
+                continue;
+            }
+
+            let size = LittleEndian::read_u32(&size) as u64;
+            let pos = BlockPos {
+                file: self.file,
+                offset: reader.offset(),
+            };
+            // grows with the bytes read, a corrupted size does not allocate
+            let mut record = vec![];
+            let read = reader.take(size).read_to_end(&mut record);
+            if read.is_err() || record.len() as u64 != size {
+                self.next_file();
+                return Some(Err(Error::BlockFileSize));
+            }
+            return Some(Ok((pos, record)));
+        }
+    }
+}
+
+impl Decodable for BlockPos {
+
+    fn decode<R: Read>(r: &mut R) -> Result<BlockPos, Error> {
+
+        trace!("decode");
+
+        let file = u32::decode(r).map_err(|e| Error::BlockPosFile.caused_by(e))?;
+        let offset = u64::decode(r).map_err(|e| Error::BlockPosOffset.caused_by(e))?;
+        Ok(BlockPos { file: file, offset: offset })
+    }
+}
+
+impl Encodable for BlockPos {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.file.encode(w).map_err(|e| Error::BlockPosFile.caused_by(e))?;
+        self.offset.encode(w).map_err(|e| Error::BlockPosOffset.caused_by(e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::Encodable;
+    use crate::block::check::COIN;
+    use crate::chain::blockstore::{BlockStore, BlockPos, XorKey};
+    use crate::chain::chainparams::ChainParams;
+    use crate::chain::utxo::test::{block, coinbase};
+    use crate::network::message::Magic;
+    use crate::utils::sha256::Sha256;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// An empty directory for the blocks of a test.
+    fn directory(name: &str) -> PathBuf {
+        let result = std::env::temp_dir().join(format!("rbtc-blocks-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&result);
+        fs::create_dir_all(&result).unwrap();
+        result
+    }
+
+    /// Three regtest blocks written to `store`, and their hashes.
+    fn write_chain(store: &mut BlockStore) -> Vec<(Sha256, BlockPos)> {
+        let mut previous = Sha256::default();
+        let mut result = vec![];
+        for height in 0..3 {
+            let block = block(previous, vec![coinbase(height, 50 * COIN)]);
+            previous = block.hash();
+            result.push((previous, store.write(&block).unwrap()));
+        }
+        result
+    }
+
+    #[test]
+    fn when_core_blk_file_then_genesis() {
+
+        let dir = directory("core");
+        let genesis = ChainParams::mainnet().genesis_block();
+        let mut data = hex::decode("f9beb4d91d010000").unwrap();
+        genesis.encode(&mut data).unwrap();
+        // preallocated by Core
+        data.extend_from_slice(&[0; 1000]);
+        fs::write(dir.join("blk00000.dat"), &data).unwrap();
+
+        let store = BlockStore::open(&dir, Magic::MainNet).unwrap();
+        assert_eq!(store.key(), XorKey::default());
+        let blocks : Vec<_> = store.blocks().map(|block| block.unwrap()).collect();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].0, BlockPos { file: 0, offset: 8 });
+        assert_eq!(blocks[0].1.hash().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+
+        let index = store.index().unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get(&genesis.hash()), Some(BlockPos { file: 0, offset: 8 }));
+        assert_eq!(store.read(BlockPos { file: 0, offset: 8 }).unwrap().hash(), genesis.hash());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn when_xor_key_then_files_obfuscated() {
+
+        let dir = directory("xor");
+        let key = XorKey([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        let mut store = BlockStore::create(&dir, Magic::RegTest, key).unwrap();
+        let written = write_chain(&mut store);
+
+        let data = fs::read(dir.join("blk00000.dat")).unwrap();
+        assert_eq!(data[..4], [0xfa ^ 0x01, 0xbf ^ 0x02, 0xb5 ^ 0x03, 0xda ^ 0x04]);
+        assert_eq!(fs::read(dir.join("xor.dat")).unwrap(), key.0.to_vec());
+
+        let store = BlockStore::open(&dir, Magic::RegTest).unwrap();
+        assert_eq!(store.key(), key);
+        let read : Vec<(Sha256, BlockPos)> = store.blocks().map(|block| block.unwrap()).map(|(pos, block)| (block.hash(), pos)).collect();
+        assert_eq!(read, written);
+        for (hash, pos) in &written {
+            assert_eq!(store.read(*pos).unwrap().hash(), *hash);
+        }
+        assert_eq!(BlockStore::create(&dir, Magic::RegTest, XorKey::default()).err(), Some(Error::BlockFileXorKey));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn when_file_full_then_next_file() {
+
+        let dir = directory("full");
+        let mut store = BlockStore::create(&dir, Magic::RegTest, XorKey([0xff; 8])).unwrap().with_max_file_size(200);
+        let written = write_chain(&mut store);
+        assert_eq!(store.files(), 3);
+        assert_eq!(written.iter().map(|(_, pos)| pos.file).collect::<Vec<u32>>(), vec![0, 1, 2]);
+
+        let index = store.index().unwrap();
+        for (hash, pos) in &written {
+            assert_eq!(index.get(hash), Some(*pos));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn when_block_truncated_then_error_and_next_file() {
+
+        let dir = directory("truncated");
+        let mut store = BlockStore::create(&dir, Magic::RegTest, XorKey::default()).unwrap().with_max_file_size(200);
+        let written = write_chain(&mut store);
+        let path = store.path(1);
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 1]).unwrap();
+
+        let records : Vec<_> = store.records().collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1], Err(Error::BlockFileSize));
+        assert_eq!(records[2].as_ref().unwrap().0, written[2].1);
+        assert_eq!(store.read(written[1].1).err(), Some(Error::BlockFileSize));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn when_other_network_then_no_block() {
+
+        let dir = directory("network");
+        let mut store = BlockStore::create(&dir, Magic::RegTest, XorKey::default()).unwrap();
+        let written = write_chain(&mut store);
+
+        let mainnet = BlockStore::open(&dir, Magic::MainNet).unwrap();
+        assert_eq!(mainnet.records().count(), 0);
+        assert_eq!(mainnet.read(written[0].1).err(), Some(Error::BlockFileMagic));
+        assert_eq!(mainnet.read(BlockPos { file: 7, offset: 8 }).err(), Some(Error::BlockFileOpen));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use crate::encode::encode::Decodable;
+use crate::block::block::Block;
+use crate::block::header::BlockHeader;
+use crate::block::transaction::Transactions;
+use crate::chain::params::ConsensusParams;
+use crate::network::message::Magic;
+use crate::utils::sha256::Sha256;
+
+use std::io::Cursor;
+use std::str::FromStr;
+
+/// Transactions of every genesis block, a single coinbase paying 50 BTC to an unspendable output.
+/// "The Times 03/Jan/2009 Chancellor on brink of second bailout for banks"
+const GENESIS_TRANSACTIONS : &str = "01\
+01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff\
+4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72\
+206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff\
+0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f\
+61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+
+/// Merkle root of the genesis coinbase, shared by every network.
+const GENESIS_MERKLE_ROOT : &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/kernel/chainparams.cpp
+///
+/// # Chain parameters
+///
+/// Everything a node needs to join a network: the magic of its messages, the port
+/// its peers listen on, where to find them, the block the chain starts from, the
+/// consensus rules and the prefixes of its addresses.
+///
+/// ```
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | Parameter      | main         | testnet3     | signet       | regtest      |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | magic          | 0xD9B4BEF9   | 0x0709110B   | 0x40CF030A   | 0xDAB5BFFA   |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | default port   | 8333         | 18333        | 38333        | 18444        |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | pubkey prefix  | 0            | 111          | 111          | 111          |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | script prefix  | 5            | 196          | 196          | 196          |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | secret prefix  | 128          | 239          | 239          | 239          |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// | bech32 hrp     | bc           | tb           | tb           | bcrt         |
+/// +----------------+--------------+--------------+--------------+--------------+
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct ChainParams {
+    /// Network name, as used by Core for `-chain`
+    pub name: &'static str,
+    pub magic: Magic,
+    pub default_port: u16,
+    pub genesis: BlockHeader,
+    pub dns_seeds: Vec<&'static str>,
+    pub consensus: ConsensusParams,
+    /// Base58 version byte of pay to public key hash addresses
+    pub pubkey_address_prefix: u8,
+    /// Base58 version byte of pay to script hash addresses
+    pub script_address_prefix: u8,
+    /// Base58 version byte of WIF private keys
+    pub secret_key_prefix: u8,
+    /// Human readable part of segwit addresses
+    pub bech32_hrp: &'static str,
+}
+
+impl ChainParams {
+
+    pub fn mainnet() -> ChainParams {
+        ChainParams {
+            name: "main",
+            magic: Magic::MainNet,
+            default_port: 8333,
+            genesis: ChainParams::genesis(1231006505, 0x1d00ffff, 2083236893),
+            dns_seeds: vec![
+                "seed.bitcoin.sipa.be",
+                "dnsseed.bluematt.me",
+                "dnsseed.bitcoin.dashjr.org",
+                "seed.bitcoinstats.com",
+                "seed.bitcoin.jonasschnelli.ch",
+                "seed.btc.petertodd.org",
+                "seed.bitcoin.sprovoost.nl",
+            ],
+            consensus: ConsensusParams::mainnet(),
+            pubkey_address_prefix: 0,
+            script_address_prefix: 5,
+            secret_key_prefix: 128,
+            bech32_hrp: "bc",
+        }
+    }
+
+    pub fn testnet() -> ChainParams {
+        ChainParams {
+            name: "test",
+            magic: Magic::TestNet,
+            default_port: 18333,
+            genesis: ChainParams::genesis(1296688602, 0x1d00ffff, 414098458),
+            dns_seeds: vec![
+                "testnet-seed.bitcoin.jonasschnelli.ch",
+                "seed.tbtc.petertodd.org",
+                "seed.testnet.bitcoin.sprovoost.nl",
+                "testnet-seed.bluematt.me",
+            ],
+            consensus: ConsensusParams::testnet(),
+            pubkey_address_prefix: 111,
+            script_address_prefix: 196,
+            secret_key_prefix: 239,
+            bech32_hrp: "tb",
+        }
+    }
+
+    pub fn signet() -> ChainParams {
+        ChainParams {
+            name: "signet",
+            magic: Magic::SigNet,
+            default_port: 38333,
+            genesis: ChainParams::genesis(1598918400, 0x1e0377ae, 52613770),
+            dns_seeds: vec![
+                "seed.signet.bitcoin.sprovoost.nl",
+            ],
+            consensus: ConsensusParams::signet(),
+            pubkey_address_prefix: 111,
+            script_address_prefix: 196,
+            secret_key_prefix: 239,
+            bech32_hrp: "tb",
+        }
+    }
+
+    pub fn regtest() -> ChainParams {
+        ChainParams {
+            name: "regtest",
+            magic: Magic::RegTest,
+            default_port: 18444,
+            genesis: ChainParams::genesis(1296688602, 0x207fffff, 2),
+            dns_seeds: vec![],
+            consensus: ConsensusParams::regtest(),
+            pubkey_address_prefix: 111,
+            script_address_prefix: 196,
+            secret_key_prefix: 239,
+            bech32_hrp: "bcrt",
+        }
+    }
+
+    /// The genesis header, only the time, bits and nonce differ between networks.
+    fn genesis(time: u32, bits: u32, nonce: u32) -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            previous: [0; 32],
+            merkleroot: Sha256::from_str(GENESIS_MERKLE_ROOT).unwrap().hash,
+            time: time,
+            bits: bits,
+            nonce: nonce,
+        }
+    }
+
+    /// The genesis block, the genesis header with its coinbase.
+    pub fn genesis_block(&self) -> Block {
+
+        let bytes = hex::decode(GENESIS_TRANSACTIONS).unwrap();
+        let mut r = Cursor::new(&bytes);
+        let transactions = Transactions::decode(&mut r).unwrap();
+
+        Block {
+            header: self.genesis.clone(),
+            transactions: transactions
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::chain::chainparams::ChainParams;
+    use crate::network::message::Magic;
+
+    #[test]
+    fn when_genesis_then_hash_same_as_core() {
+        assert_eq!(ChainParams::mainnet().genesis.hash().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert_eq!(ChainParams::testnet().genesis.hash().to_string(), "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943");
+        assert_eq!(ChainParams::signet().genesis.hash().to_string(), "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6");
+        assert_eq!(ChainParams::regtest().genesis.hash().to_string(), "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206");
+    }
+
+    #[test]
+    fn when_genesis_block_then_valid() {
+        for params in [ChainParams::mainnet(), ChainParams::testnet(), ChainParams::signet(), ChainParams::regtest()] {
+            let block = params.genesis_block();
+            assert_eq!(block.transactions.len(), 1);
+            assert_eq!(block.validate_merkle(), Ok(()));
+            assert_eq!(block.check_pow(&params.consensus.pow_limit), Ok(()));
+        }
+    }
+
+    #[test]
+    fn when_network_then_magic_and_port() {
+        assert_eq!(ChainParams::mainnet().magic, Magic::MainNet);
+        assert_eq!(ChainParams::mainnet().default_port, 8333);
+        assert_eq!(ChainParams::testnet().magic, Magic::TestNet);
+        assert_eq!(ChainParams::testnet().default_port, 18333);
+        assert_eq!(ChainParams::signet().magic, Magic::SigNet);
+        assert_eq!(ChainParams::signet().default_port, 38333);
+        assert_eq!(ChainParams::regtest().magic, Magic::RegTest);
+        assert_eq!(ChainParams::regtest().default_port, 18444);
+        assert!(ChainParams::regtest().dns_seeds.is_empty());
+    }
+}
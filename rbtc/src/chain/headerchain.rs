@@ -0,0 +1,570 @@
+use crate::encode::error::Error;
+use crate::block::header::BlockHeader;
+use crate::block::pow;
+use crate::chain::params::ConsensusParams;
+use crate::chain::chainparams::ChainParams;
+use crate::network::getheaders::GetHeaders;
+use crate::utils::sha256::Sha256;
+use crate::utils::uint256::Uint256;
+
+use std::collections::HashMap;
+
+/// Number of blocks the median time past is computed on
+const MEDIAN_TIME_SPAN : usize = 11;
+
+/// A header stored in the chain, with its position and the work of the chain ending with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainEntry {
+    pub header: BlockHeader,
+    pub hash: Sha256,
+    pub height: u32,
+    pub chainwork: Uint256,
+}
+
+/// Outcome of `HeaderChain::accept` for a header passing every check.
+#[derive(Debug, PartialEq)]
+pub enum Accepted {
+    /// The header was already stored
+    Duplicate,
+    /// The header is the new tip, on top of the previous tip
+    Extended,
+    /// The header is stored on a branch with no more work than the best chain
+    Fork,
+    /// The header is the new tip of a branch that now has the most work. The
+    /// `disconnected` hashes are the former best chain blocks, from the old tip down,
+    /// the `connected` hashes are the new ones, from the fork up to the new tip.
+    Reorganized {
+        disconnected: Vec<Sha256>,
+        connected: Vec<Sha256>,
+    },
+}
+
+/// https://en.bitcoin.it/wiki/Blockchain
+///
+/// # Header chain
+///
+/// Every valid header received, linked to its parent through `previous`. The best chain
+/// is the one with the most cumulated work, not the most blocks; on equal work the first
+/// seen tip is kept.
+///
+/// ```
+///   genesis - 1 - 2 - 3 - 4         best chain, 5 blocks of work
+///                  \
+///                   3' - 4' - 5'    reorganizes the best chain once 5' is accepted
+/// ```
+///
+/// A header is accepted when:
+/// - its parent is known,
+/// - its hash matches the target of its bits, and the target is below the pow limit,
+/// - its bits are the ones required by the retargeting rules at its height,
+/// - its time is after the median time of the 11 previous blocks.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp ContextualCheckBlockHeader
+pub struct HeaderChain {
+    params: ConsensusParams,
+    entries: HashMap<Sha256, ChainEntry>,
+    /// best chain hashes, indexed by height
+    active: Vec<Sha256>,
+}
+
+impl HeaderChain {
+
+    pub fn new(genesis: BlockHeader, params: ConsensusParams) -> HeaderChain {
+
+        let hash = genesis.hash();
+        let entry = ChainEntry {
+            chainwork: genesis.work(),
+            header: genesis,
+            hash: hash,
+            height: 0,
+        };
+
+        let mut entries = HashMap::new();
+        entries.insert(hash, entry);
+
+        HeaderChain {
+            params: params,
+            entries: entries,
+            active: vec![hash],
+        }
+    }
+
+    /// A chain holding only the genesis of the network.
+    pub fn from_params(params: &ChainParams) -> HeaderChain {
+        HeaderChain::new(params.genesis.clone(), params.consensus.clone())
+    }
+
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
+
+    pub fn tip(&self) -> &ChainEntry {
+        &self.entries[&self.active[self.active.len() - 1]]
+    }
+
+    pub fn height(&self) -> u32 {
+        self.tip().height
+    }
+
+    /// Any stored header, on the best chain or not.
+    pub fn get(&self, hash: &Sha256) -> Option<&ChainEntry> {
+        self.entries.get(hash)
+    }
+
+    /// Header of the best chain at `height`.
+    pub fn at_height(&self, height: u32) -> Option<&ChainEntry> {
+        self.active.get(height as usize).map(|hash| &self.entries[hash])
+    }
+
+    pub fn is_active(&self, entry: &ChainEntry) -> bool {
+        self.active.get(entry.height as usize) == Some(&entry.hash)
+    }
+
+    /// Ancestor of the header `hash` at `height`, following its own branch.
+    pub fn ancestor(&self, hash: &Sha256, height: u32) -> Option<&ChainEntry> {
+
+        let mut entry = self.entries.get(hash)?;
+        if height > entry.height {
+            return None;
+        }
+        loop {
+            if entry.height == height {
+                return Some(entry);
+            }
+            if self.is_active(entry) {
+                return self.at_height(height);
+            }
+            entry = self.entries.get(&Sha256::new(entry.header.previous))?;
+        }
+    }
+
+    /// Median of the times of the header `hash` and its 10 ancestors.
+    pub fn median_time_past(&self, hash: &Sha256) -> u32 {
+
+        let mut times : Vec<u32> = Vec::with_capacity(MEDIAN_TIME_SPAN);
+        let mut current = self.entries.get(hash);
+        while let Some(entry) = current {
+            if times.len() == MEDIAN_TIME_SPAN {
+                break;
+            }
+            times.push(entry.header.time);
+            current = self.entries.get(&Sha256::new(entry.header.previous));
+        }
+        times.sort();
+        times[times.len() / 2]
+    }
+
+    /// Bits a header built on `parent` must carry.
+    pub fn next_work_required(&self, parent: &ChainEntry, header: &BlockHeader) -> u32 {
+
+        let interval = self.params.difficulty_adjustment_interval();
+        let limit = self.params.pow_limit.to_compact(false);
+
+        if !(parent.height + 1).is_multiple_of(interval) {
+            if self.params.pow_allow_min_difficulty_blocks {
+                // a block late by more than twice the spacing may be mined at the minimum difficulty
+                if u64::from(header.time) > u64::from(parent.header.time) + 2 * u64::from(self.params.pow_target_spacing) {
+                    return limit;
+                }
+                // otherwise the last bits that were not a minimum difficulty exception
+                let mut entry = parent;
+                while !entry.height.is_multiple_of(interval) && entry.header.bits == limit {
+                    match self.entries.get(&Sha256::new(entry.header.previous)) {
+                        Some(previous) => entry = previous,
+                        None => break,
+                    }
+                }
+                return entry.header.bits;
+            }
+            return parent.header.bits;
+        }
+
+        let first = self.ancestor(&parent.hash, parent.height + 1 - interval)
+            .expect("retarget ancestor of a stored header");
+        pow::calculate_next_work_required(parent.header.bits, parent.header.time, first.header.time, &self.params)
+    }
+
+    /// Checks and stores a header, moving the best chain when it brings more work.
+    pub fn accept(&mut self, header: BlockHeader) -> Result<Accepted, Error> {
+
+        trace!("accept");
+
+        let hash = header.hash();
+        if self.entries.contains_key(&hash) {
+            return Ok(Accepted::Duplicate);
+        }
+
+        header.check_pow(&self.params.pow_limit)?;
+
+        let entry = {
+            let parent = self.entries.get(&Sha256::new(header.previous))
+                .ok_or(Error::ChainPreviousUnknown)?;
+
+            if header.bits != self.next_work_required(parent, &header) {
+                return Err(Error::ChainBadDifficulty);
+            }
+            if header.time <= self.median_time_past(&parent.hash) {
+                return Err(Error::ChainTimeTooOld);
+            }
+
+            ChainEntry {
+                chainwork: parent.chainwork + header.work(),
+                height: parent.height + 1,
+                header: header,
+                hash: hash,
+            }
+        };
+
+        let tip = self.tip().clone();
+        let height = entry.height;
+        let more_work = entry.chainwork > tip.chainwork;
+        let extends_tip = entry.header.previous == tip.hash.hash;
+        self.entries.insert(hash, entry);
+
+        if !more_work {
+            return Ok(Accepted::Fork);
+        }
+        if extends_tip {
+            self.active.push(hash);
+            return Ok(Accepted::Extended);
+        }
+
+        // walk the new branch down to the best chain
+        let mut connected : Vec<Sha256> = Vec::new();
+        let mut current = &self.entries[&hash];
+        while !self.is_active(current) {
+            connected.push(current.hash);
+            current = &self.entries[&Sha256::new(current.header.previous)];
+        }
+        connected.reverse();
+
+        let fork = current.height as usize;
+        let disconnected : Vec<Sha256> = self.active.drain(fork + 1..).rev().collect();
+        self.active.extend(connected.iter().cloned());
+        debug_assert_eq!(self.active.len() as u32, height + 1);
+
+        Ok(Accepted::Reorganized {
+            disconnected: disconnected,
+            connected: connected,
+        })
+    }
+
+    /// Block locator of the best chain, for `GetHeaders`: the 10 last hashes, then
+    /// hashes further and further apart, ending with the genesis.
+    pub fn locator(&self) -> Vec<Sha256> {
+
+        let mut result : Vec<Sha256> = Vec::new();
+        let mut height = self.height() as i64;
+        let mut step = 1;
+        loop {
+            result.push(self.active[height as usize]);
+            if height == 0 {
+                break;
+            }
+            height = std::cmp::max(height - step, 0);
+            if result.len() > 10 {
+                step *= 2;
+            }
+        }
+        result
+    }
+
+    /// `getheaders` request for the headers following the best chain tip.
+    pub fn get_headers(&self, version: u32) -> GetHeaders {
+        GetHeaders {
+            version: version,
+            locators: self.locator(),
+            stop: Sha256::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::header::BlockHeader;
+    use crate::chain::headerchain::{HeaderChain, ChainEntry, Accepted};
+    use crate::chain::chainparams::ChainParams;
+    use crate::chain::params::ConsensusParams;
+    use crate::encode::error::Error;
+    use crate::utils::sha256::Sha256;
+    use crate::utils::uint256::Uint256;
+
+    use std::str::FromStr;
+
+    fn genesis(time: u32, bits: u32, nonce: u32) -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            previous: [0; 32],
+            merkleroot: Sha256::from_str("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b").unwrap().hash,
+            time: time,
+            bits: bits,
+            nonce: nonce,
+        }
+    }
+
+    fn regtest() -> HeaderChain {
+        HeaderChain::new(genesis(1296688602, 0x207fffff, 2), ConsensusParams::regtest())
+    }
+
+    /// Grinds the nonce until the header meets its own target.
+    fn mine(previous: &ChainEntry, time: u32, bits: u32, tag: u8) -> BlockHeader {
+        let limit = !Uint256::zero();
+        let mut header = BlockHeader {
+            version: 0x20000000,
+            previous: previous.hash.hash,
+            merkleroot: [tag; 32],
+            time: time,
+            bits: bits,
+            nonce: 0,
+        };
+        while header.check_pow(&limit).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Mines `count` headers on top of `hash`, 10 minutes apart.
+    fn extend(chain: &mut HeaderChain, hash: &Sha256, count: u32, tag: u8) -> Vec<Sha256> {
+        let mut result = Vec::new();
+        let mut previous = chain.get(hash).unwrap().clone();
+        for _ in 0..count {
+            let header = mine(&previous, previous.header.time + 600, previous.header.bits, tag);
+            chain.accept(header.clone()).unwrap();
+            previous = chain.get(&header.hash()).unwrap().clone();
+            result.push(previous.hash);
+        }
+        result
+    }
+
+    #[test]
+    fn when_from_params_then_genesis_tip() {
+        let params = ChainParams::signet();
+        let chain = HeaderChain::from_params(&params);
+        assert_eq!(chain.height(), 0);
+        assert_eq!(chain.tip().hash, params.genesis.hash());
+    }
+
+    #[test]
+    fn when_new_regtest_then_genesis_tip() {
+        let chain = regtest();
+        assert_eq!(chain.height(), 0);
+        assert_eq!(chain.tip().hash.to_string(), "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206");
+        assert_eq!(chain.tip().chainwork, Uint256::from_u64(2));
+        assert_eq!(chain.locator(), vec![chain.tip().hash]);
+    }
+
+    #[test]
+    fn when_mainnet_block_1_then_extended() {
+        let mut chain = HeaderChain::new(genesis(1231006505, 0x1d00ffff, 2083236893), ConsensusParams::mainnet());
+        assert_eq!(chain.tip().hash.to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+
+        let block1 = BlockHeader {
+            version: 1,
+            previous: chain.tip().hash.hash,
+            merkleroot: Sha256::from_str("0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098").unwrap().hash,
+            time: 1231469665,
+            bits: 0x1d00ffff,
+            nonce: 2573394689,
+        };
+        assert_eq!(chain.accept(block1), Ok(Accepted::Extended));
+        assert_eq!(chain.tip().hash.to_string(), "00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048");
+        assert_eq!(chain.tip().chainwork, Uint256::from_u64(0x200020002));
+    }
+
+    #[test]
+    fn when_testnet_block_1_then_extended() {
+        let mut chain = HeaderChain::new(genesis(1296688602, 0x1d00ffff, 414098458), ConsensusParams::testnet());
+        assert_eq!(chain.tip().hash.to_string(), "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943");
+
+        let block1 = BlockHeader {
+            version: 1,
+            previous: chain.tip().hash.hash,
+            merkleroot: Sha256::from_str("f0315ffc38709d70ad5647e22048358dd3745f3ce3874223c80a7c92fab0c8ba").unwrap().hash,
+            time: 1296688928,
+            bits: 0x1d00ffff,
+            nonce: 1924588547,
+        };
+        assert_eq!(chain.accept(block1), Ok(Accepted::Extended));
+        assert_eq!(chain.tip().hash.to_string(), "00000000b873e79784647a6c82962c70d228557d24a747ea4d1b8bbe878e1206");
+    }
+
+    #[test]
+    fn when_extend_then_tip_and_locator() {
+        let mut chain = regtest();
+        let genesis = chain.tip().hash;
+        let hashes = extend(&mut chain, &genesis, 20, 1);
+
+        assert_eq!(chain.height(), 20);
+        assert_eq!(chain.tip().hash, hashes[19]);
+        assert_eq!(chain.tip().chainwork, Uint256::from_u64(2 * 21));
+        assert_eq!(chain.at_height(7).unwrap().hash, hashes[6]);
+        assert_eq!(chain.ancestor(&hashes[19], 3).unwrap().hash, hashes[2]);
+
+        // steps of 1 for the first 11 hashes, then doubling: 20 .. 10, 9, 7, 3, 0
+        let heights : Vec<u32> = chain.locator().iter()
+            .map(|hash| chain.get(hash).unwrap().height)
+            .collect();
+        assert_eq!(heights, vec![20, 19, 18, 17, 16, 15, 14, 13, 12, 11, 10, 9, 7, 3, 0]);
+
+        let request = chain.get_headers(70015);
+        assert_eq!(request.locators, chain.locator());
+        assert_eq!(request.stop, Sha256::default());
+    }
+
+    #[test]
+    fn when_same_header_twice_then_duplicate() {
+        let mut chain = regtest();
+        let header = mine(chain.tip(), 1296689202, 0x207fffff, 1);
+        assert_eq!(chain.accept(header.clone()), Ok(Accepted::Extended));
+        assert_eq!(chain.accept(header), Ok(Accepted::Duplicate));
+        assert_eq!(chain.height(), 1);
+    }
+
+    #[test]
+    fn when_previous_unknown_then_error() {
+        let mut chain = regtest();
+        let mut orphan = chain.tip().clone();
+        orphan.hash = Sha256::new([1; 32]);
+        let header = mine(&orphan, 1296689202, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainPreviousUnknown));
+    }
+
+    #[test]
+    fn when_hash_above_target_then_error() {
+        let mut chain = regtest();
+        let mut header = mine(chain.tip(), 1296689202, 0x207fffff, 1);
+        while header.check_pow(&chain.params().pow_limit).is_ok() {
+            header.nonce += 1;
+        }
+        assert_eq!(chain.accept(header), Err(Error::PowHashAboveTarget));
+    }
+
+    #[test]
+    fn when_bits_not_required_then_error() {
+        let mut chain = regtest();
+        let header = mine(chain.tip(), 1296689202, 0x207ffffe, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainBadDifficulty));
+    }
+
+    #[test]
+    fn when_time_not_after_median_then_error() {
+        let mut chain = regtest();
+        let genesis = chain.tip().hash;
+        extend(&mut chain, &genesis, 10, 1);
+
+        // times are genesis + 600 * height, the median of heights 0 to 10 is height 5
+        let median = chain.median_time_past(&chain.tip().hash);
+        assert_eq!(median, 1296688602 + 600 * 5);
+
+        let header = mine(chain.tip(), median, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainTimeTooOld));
+
+        let header = mine(chain.tip(), median + 1, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+    }
+
+    #[test]
+    fn when_branch_gets_more_work_then_reorganize() {
+        let mut chain = regtest();
+        let genesis = chain.tip().hash;
+        let main = extend(&mut chain, &genesis, 5, 1);
+
+        // same work as the best chain, the first seen tip stays
+        let mut branch = Vec::new();
+        let mut previous = chain.at_height(2).unwrap().clone();
+        for _ in 0..3 {
+            let header = mine(&previous, previous.header.time + 300, 0x207fffff, 2);
+            assert_eq!(chain.accept(header.clone()), Ok(Accepted::Fork));
+            previous = chain.get(&header.hash()).unwrap().clone();
+            branch.push(previous.hash);
+        }
+        assert_eq!(chain.tip().hash, main[4]);
+        assert!(!chain.is_active(chain.get(&branch[2]).unwrap()));
+        assert_eq!(chain.ancestor(&branch[2], 3).unwrap().hash, branch[0]);
+        assert_eq!(chain.ancestor(&branch[2], 1).unwrap().hash, main[0]);
+
+        let header = mine(&previous, previous.header.time + 300, 0x207fffff, 2);
+        let result = chain.accept(header.clone());
+        branch.push(header.hash());
+
+        assert_eq!(result, Ok(Accepted::Reorganized {
+            disconnected: vec![main[4], main[3], main[2]],
+            connected: branch.clone(),
+        }));
+        assert_eq!(chain.height(), 6);
+        assert_eq!(chain.tip().hash, branch[3]);
+        assert_eq!(chain.at_height(3).unwrap().hash, branch[0]);
+        assert_eq!(chain.at_height(2).unwrap().hash, main[1]);
+
+        // the former best chain can come back
+        let back = extend(&mut chain, &main[4], 2, 1);
+        assert_eq!(chain.tip().hash, back[1]);
+        assert_eq!(chain.at_height(5).unwrap().hash, main[4]);
+    }
+
+    /// Regtest pow limit, retargeting every 4 blocks of 1 second. The genesis target is low
+    /// enough that the retarget product fits in 256 bits, which it does not at the regtest limit.
+    fn fast_retarget(allow_min_difficulty: bool) -> HeaderChain {
+        let params = ConsensusParams {
+            pow_target_timespan: 4,
+            pow_target_spacing: 1,
+            pow_allow_min_difficulty_blocks: allow_min_difficulty,
+            pow_no_retargeting: false,
+            ..ConsensusParams::regtest()
+        };
+        let mut chain = HeaderChain::new(genesis(1296688602, 0x200fffff, 0), params);
+        for _ in 0..3 {
+            let header = mine(chain.tip(), chain.tip().header.time + 1, 0x200fffff, 1);
+            assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+        }
+        chain
+    }
+
+    #[test]
+    fn when_retarget_height_then_bits_adjusted() {
+        let mut chain = fast_retarget(false);
+
+        let time = chain.tip().header.time + 1;
+        let header = mine(chain.tip(), time, 0x200fffff, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainBadDifficulty));
+
+        // the period lasted 3 seconds instead of 4, the target is multiplied by 3 / 4
+        let bits = chain.next_work_required(chain.tip(), &mine(chain.tip(), time, 0x200fffff, 1));
+        assert_eq!(bits, 0x200bffff);
+        let header = mine(chain.tip(), time, bits, 1);
+        assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+
+        // no exception for late blocks
+        let header = mine(chain.tip(), chain.tip().header.time + 3, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainBadDifficulty));
+    }
+
+    #[test]
+    fn when_min_difficulty_allowed_then_late_block_at_pow_limit() {
+        let mut chain = fast_retarget(true);
+        let header = mine(chain.tip(), chain.tip().header.time + 1, 0x200bffff, 1);
+        assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+
+        // more than twice the spacing late, the pow limit is allowed
+        let header = mine(chain.tip(), chain.tip().header.time + 3, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+
+        // back on time, the bits are the last ones that were not at the pow limit
+        let header = mine(chain.tip(), chain.tip().header.time + 1, 0x207fffff, 1);
+        assert_eq!(chain.accept(header), Err(Error::ChainBadDifficulty));
+        let header = mine(chain.tip(), chain.tip().header.time + 1, 0x200bffff, 1);
+        assert_eq!(chain.accept(header), Ok(Accepted::Extended));
+    }
+
+    #[test]
+    fn when_parent_time_near_max_then_no_min_difficulty() {
+        let chain = fast_retarget(true);
+
+        // the parent time plus twice the spacing does not fit in 32 bits
+        let mut parent = chain.at_height(1).unwrap().clone();
+        parent.header.time = u32::MAX - 1;
+        let header = mine(&parent, u32::MAX, 0x207fffff, 1);
+        assert_eq!(chain.next_work_required(&parent, &header), 0x200fffff);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::encode::error::Error;
+use crate::block::outpoint::OutPoint;
+use crate::chain::utxo::{Coin, BlockUndo, UtxoBatch, UtxoStore};
+use crate::utils::sha256::Sha256;
+
+use std::collections::HashMap;
+
+/// A UTXO store held in memory, lost when dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    coins: HashMap<OutPoint, Coin>,
+    undo: HashMap<Sha256, BlockUndo>,
+    tip: Option<Sha256>,
+}
+
+impl MemoryStore {
+
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    /// Number of unspent outputs.
+    pub fn len(&self) -> usize {
+        self.coins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coins.is_empty()
+    }
+}
+
+impl UtxoStore for MemoryStore {
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<Coin>, Error> {
+        Ok(self.coins.get(outpoint).cloned())
+    }
+
+    fn tip(&self) -> Result<Option<Sha256>, Error> {
+        Ok(self.tip)
+    }
+
+    fn undo(&self, hash: &Sha256) -> Result<Option<BlockUndo>, Error> {
+        Ok(self.undo.get(hash).cloned())
+    }
+
+    fn write(&mut self, batch: UtxoBatch) -> Result<(), Error> {
+
+        trace!("write");
+
+        for (outpoint, coin) in batch.coins {
+            match coin {
+                Some(coin) => self.coins.insert(outpoint, coin),
+                None => self.coins.remove(&outpoint),
+            };
+        }
+        if let Some((hash, undo)) = batch.undo {
+            self.undo.insert(hash, undo);
+        }
+        if let Some(hash) = batch.remove_undo {
+            self.undo.remove(&hash);
+        }
+        self.tip = Some(batch.tip);
+        Ok(())
+    }
+}
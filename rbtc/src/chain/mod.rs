@@ -0,0 +1,9 @@
+pub mod params;
+pub mod chainparams;
+pub mod headerchain;
+pub mod address;
+pub mod utxo;
+pub mod memorystore;
+pub mod sqlitestore;
+pub mod blockstore;
+pub mod blockindex;
@@ -0,0 +1,179 @@
+use crate::block::check::COIN;
+use crate::utils::uint256::Uint256;
+
+use std::str::FromStr;
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/consensus/params.h
+///
+/// # Consensus parameters
+///
+/// The rules a header chain is checked against, which differ between networks.
+///
+/// ```
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | Parameter                       | main        | testnet3    | signet      | regtest     |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | pow_limit                       | 0x1d00ffff  | 0x1d00ffff  | 0x1e0377ae  | 0x207fffff  |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | pow_target_timespan             | 2 weeks     | 2 weeks     | 2 weeks     | 2 weeks     |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | pow_target_spacing              | 10 minutes  | 10 minutes  | 10 minutes  | 10 minutes  |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | pow_allow_min_difficulty_blocks | no          | yes         | no          | yes         |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | pow_no_retargeting              | no          | no          | no          | yes         |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// | subsidy_halving_interval        | 210000      | 210000      | 210000      | 150         |
+/// +---------------------------------+-------------+-------------+-------------+-------------+
+/// ```
+///
+/// Soft fork activation heights:
+/// ```
+/// +--------------+--------------+--------------+--------+---------+
+/// | Soft fork    | main         | testnet3     | signet | regtest |
+/// +--------------+--------------+--------------+--------+---------+
+/// | BIP34        | 227931       | 21111        | 1      | 1       |
+/// +--------------+--------------+--------------+--------+---------+
+/// | BIP65        | 388381       | 581885       | 1      | 1       |
+/// +--------------+--------------+--------------+--------+---------+
+/// | BIP66        | 363725       | 330776       | 1      | 1       |
+/// +--------------+--------------+--------------+--------+---------+
+/// | CSV          | 419328       | 770112       | 1      | 1       |
+/// +--------------+--------------+--------------+--------+---------+
+/// | Segwit       | 481824       | 834624       | 1      | 0       |
+/// +--------------+--------------+--------------+--------+---------+
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusParams {
+    /// Highest target a header may use
+    pub pow_limit: Uint256,
+    /// Expected duration of a difficulty period, in seconds
+    pub pow_target_timespan: u32,
+    /// Expected duration between two blocks, in seconds
+    pub pow_target_spacing: u32,
+    /// A block more than twice the spacing after its parent may use the pow limit
+    pub pow_allow_min_difficulty_blocks: bool,
+    /// The target never changes
+    pub pow_no_retargeting: bool,
+    /// Number of blocks after which the block subsidy is halved
+    pub subsidy_halving_interval: u32,
+    /// Height from which the coinbase starts with the block height
+    pub bip34_height: u32,
+    /// Height from which OP_CHECKLOCKTIMEVERIFY is enforced
+    pub bip65_height: u32,
+    /// Height from which strict DER signatures are enforced
+    pub bip66_height: u32,
+    /// Height from which BIP68, BIP112 and BIP113 (OP_CHECKSEQUENCEVERIFY) are enforced
+    pub csv_height: u32,
+    /// Height from which segregated witness (BIP141, BIP143, BIP147) is enforced
+    pub segwit_height: u32,
+}
+
+impl ConsensusParams {
+
+    pub fn mainnet() -> ConsensusParams {
+        ConsensusParams {
+            pow_limit: Uint256::from_str("00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap(),
+            pow_target_timespan: 14 * 24 * 60 * 60,
+            pow_target_spacing: 10 * 60,
+            pow_allow_min_difficulty_blocks: false,
+            pow_no_retargeting: false,
+            subsidy_halving_interval: 210000,
+            bip34_height: 227931,
+            bip65_height: 388381,
+            bip66_height: 363725,
+            csv_height: 419328,
+            segwit_height: 481824,
+        }
+    }
+
+    pub fn testnet() -> ConsensusParams {
+        ConsensusParams {
+            pow_allow_min_difficulty_blocks: true,
+            bip34_height: 21111,
+            bip65_height: 581885,
+            bip66_height: 330776,
+            csv_height: 770112,
+            segwit_height: 834624,
+            ..ConsensusParams::mainnet()
+        }
+    }
+
+    pub fn signet() -> ConsensusParams {
+        ConsensusParams {
+            pow_limit: Uint256::from_str("00000377ae000000000000000000000000000000000000000000000000000000").unwrap(),
+            bip34_height: 1,
+            bip65_height: 1,
+            bip66_height: 1,
+            csv_height: 1,
+            segwit_height: 1,
+            ..ConsensusParams::mainnet()
+        }
+    }
+
+    pub fn regtest() -> ConsensusParams {
+        ConsensusParams {
+            pow_limit: Uint256::from_str("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap(),
+            pow_allow_min_difficulty_blocks: true,
+            pow_no_retargeting: true,
+            subsidy_halving_interval: 150,
+            bip34_height: 1,
+            bip65_height: 1,
+            bip66_height: 1,
+            csv_height: 1,
+            segwit_height: 0,
+            ..ConsensusParams::mainnet()
+        }
+    }
+
+    /// Number of blocks between two retargets, 2016 on every network.
+    pub fn difficulty_adjustment_interval(&self) -> u32 {
+        self.pow_target_timespan / self.pow_target_spacing
+    }
+
+    /// The amount of new coins a coinbase at `height` may claim on top of the fees,
+    /// 50 BTC halved every `subsidy_halving_interval` blocks, down to nothing.
+    ///
+    // https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp GetBlockSubsidy
+    pub fn block_subsidy(&self, height: u32) -> u64 {
+        let halvings = height / self.subsidy_halving_interval;
+        if halvings >= 64 {
+            return 0;
+        }
+        (50 * COIN) >> halvings
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::chain::params::ConsensusParams;
+
+    #[test]
+    fn when_params_then_2016_blocks_interval() {
+        assert_eq!(ConsensusParams::mainnet().difficulty_adjustment_interval(), 2016);
+        assert_eq!(ConsensusParams::testnet().difficulty_adjustment_interval(), 2016);
+        assert_eq!(ConsensusParams::signet().difficulty_adjustment_interval(), 2016);
+        assert_eq!(ConsensusParams::regtest().difficulty_adjustment_interval(), 2016);
+    }
+
+    #[test]
+    fn when_pow_limit_then_compact() {
+        assert_eq!(ConsensusParams::mainnet().pow_limit.to_compact(false), 0x1d00ffff);
+        assert_eq!(ConsensusParams::signet().pow_limit.to_compact(false), 0x1e0377ae);
+        assert_eq!(ConsensusParams::regtest().pow_limit.to_compact(false), 0x207fffff);
+    }
+
+    #[test]
+    fn when_height_then_block_subsidy_halved() {
+        let params = ConsensusParams::mainnet();
+        assert_eq!(params.block_subsidy(0), 5_000_000_000);
+        assert_eq!(params.block_subsidy(209999), 5_000_000_000);
+        assert_eq!(params.block_subsidy(210000), 2_500_000_000);
+        assert_eq!(params.block_subsidy(840000), 312_500_000);
+        assert_eq!(params.block_subsidy(210000 * 33), 0);
+        assert_eq!(params.block_subsidy(210000 * 64), 0);
+        assert_eq!(ConsensusParams::regtest().block_subsidy(150), 2_500_000_000);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::block::outpoint::OutPoint;
+use crate::chain::utxo::{Coin, BlockUndo, UtxoBatch, UtxoStore};
+use crate::utils::sha256::Sha256;
+
+use std::io::Cursor;
+use std::path::Path;
+
+use rusqlite::types::ToSql;
+use rusqlite::{Connection, OptionalExtension, NO_PARAMS};
+
+/// A UTXO store kept in a sqlite database, the coins and undo data serialized as blobs:
+///
+/// ```
+/// utxo (txid BLOB, vout INTEGER, coin BLOB)   primary key (txid, vout)
+/// undo (hash BLOB, data BLOB)                 primary key (hash)
+/// tip  (id INTEGER, hash BLOB)                a single row, id 0
+/// ```
+///
+/// A batch is written in a single sqlite transaction.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+
+    /// Opens the database at `path`, creating it and its tables when missing.
+    pub fn open(path: &Path) -> Result<SqliteStore, Error> {
+
+        trace!("open");
+
+        let conn = Connection::open(path).map_err(|e| Error::UtxoStore.caused_by(e))?;
+        let store = SqliteStore {
+            conn: conn,
+        };
+        store.init()?;
+        Ok(store)
+    }
+
+    /// A database in memory, lost when dropped.
+    pub fn memory() -> Result<SqliteStore, Error> {
+
+        trace!("memory");
+
+        let conn = Connection::open_in_memory().map_err(|e| Error::UtxoStore.caused_by(e))?;
+        let store = SqliteStore {
+            conn: conn,
+        };
+        store.init()?;
+        Ok(store)
+    }
+
+    fn init(&self) -> Result<(), Error> {
+
+        trace!("init");
+
+        self.conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS utxo (
+                txid BLOB NOT NULL,
+                vout INTEGER NOT NULL,
+                coin BLOB NOT NULL,
+                PRIMARY KEY (txid, vout)
+            );
+            CREATE TABLE IF NOT EXISTS undo (
+                hash BLOB PRIMARY KEY,
+                data BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS tip (
+                id INTEGER PRIMARY KEY,
+                hash BLOB NOT NULL
+            );
+        ").map_err(|e| Error::UtxoStore.caused_by(e))
+    }
+
+    fn blob(&self, sql: &str, params: &[&dyn ToSql]) -> Result<Option<Vec<u8>>, Error> {
+        self.conn.query_row(sql, params, |row| row.get_checked(0))
+            .optional()
+            .map_err(|e| Error::UtxoStore.caused_by(e))?
+            .transpose()
+            .map_err(|e| Error::UtxoStore.caused_by(e))
+    }
+}
+
+impl UtxoStore for SqliteStore {
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<Coin>, Error> {
+
+        trace!("get");
+
+        let txid = outpoint.transaction_hash.to_vec();
+        let blob = self.blob("SELECT coin FROM utxo WHERE txid = ?1 AND vout = ?2", &[&txid as &dyn ToSql, &outpoint.index])?;
+        match blob {
+            Some(blob) => Ok(Some(Coin::decode(&mut Cursor::new(&blob))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn tip(&self) -> Result<Option<Sha256>, Error> {
+
+        trace!("tip");
+
+        let blob = self.blob("SELECT hash FROM tip WHERE id = 0", NO_PARAMS)?;
+        match blob {
+            Some(blob) => Ok(Some(Sha256::decode(&mut Cursor::new(&blob)).map_err(|e| Error::UtxoStore.caused_by(e))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn undo(&self, hash: &Sha256) -> Result<Option<BlockUndo>, Error> {
+
+        trace!("undo");
+
+        let hash = hash.hash.to_vec();
+        let blob = self.blob("SELECT data FROM undo WHERE hash = ?1", &[&hash as &dyn ToSql])?;
+        match blob {
+            Some(blob) => Ok(Some(BlockUndo::decode(&mut Cursor::new(&blob))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn write(&mut self, batch: UtxoBatch) -> Result<(), Error> {
+
+        trace!("write");
+
+        let tx = self.conn.transaction().map_err(|e| Error::UtxoStore.caused_by(e))?;
+        for (outpoint, coin) in batch.coins {
+            let txid = outpoint.transaction_hash.to_vec();
+            match coin {
+                Some(coin) => {
+                    let mut data = vec![];
+                    coin.encode(&mut data)?;
+                    tx.execute("INSERT OR REPLACE INTO utxo (txid, vout, coin) VALUES (?1, ?2, ?3)",
+                        &[&txid as &dyn ToSql, &outpoint.index, &data])
+                },
+                None => tx.execute("DELETE FROM utxo WHERE txid = ?1 AND vout = ?2",
+                    &[&txid as &dyn ToSql, &outpoint.index]),
+            }.map_err(|e| Error::UtxoStore.caused_by(e))?;
+        }
+        if let Some((hash, undo)) = batch.undo {
+            let mut data = vec![];
+            undo.encode(&mut data)?;
+            tx.execute("INSERT OR REPLACE INTO undo (hash, data) VALUES (?1, ?2)", &[&hash.hash.to_vec() as &dyn ToSql, &data])
+                .map_err(|e| Error::UtxoStore.caused_by(e))?;
+        }
+        if let Some(hash) = batch.remove_undo {
+            tx.execute("DELETE FROM undo WHERE hash = ?1", &[&hash.hash.to_vec() as &dyn ToSql])
+                .map_err(|e| Error::UtxoStore.caused_by(e))?;
+        }
+        tx.execute("INSERT OR REPLACE INTO tip (id, hash) VALUES (0, ?1)", &[&batch.tip.hash.to_vec() as &dyn ToSql])
+            .map_err(|e| Error::UtxoStore.caused_by(e))?;
+        tx.commit().map_err(|e| Error::UtxoStore.caused_by(e))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::block::check::COIN;
+    use crate::block::outpoint::OutPoint;
+    use crate::chain::sqlitestore::SqliteStore;
+    use crate::chain::utxo::{Coin, UtxoStore};
+    use crate::chain::utxo::test::{chain, output, outpoint};
+
+    use std::fs;
+
+    #[test]
+    fn when_reopened_then_same_set() {
+
+        let path = std::env::temp_dir().join(format!("rbtc-utxo-{}.sqlite", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let (set, blocks) = chain(SqliteStore::open(&path).unwrap(), 3);
+        let tip = blocks[3].hash();
+        let coinbase_2 = outpoint(blocks[2].transactions.get(0).unwrap(), 0);
+        assert_eq!(set.tip(), Ok(Some(tip)));
+        drop(set);
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.tip(), Ok(Some(tip)));
+        assert_eq!(store.get(&coinbase_2), Ok(Some(Coin { output: output(50 * COIN), height: 2, coinbase: true })));
+        assert_eq!(store.get(&OutPoint { transaction_hash: [1; 32], index: 0 }), Ok(None));
+        assert!(store.undo(&tip).unwrap().unwrap().spent.is_empty());
+        assert_eq!(store.undo(&blocks[0].hash()), Ok(None));
+
+        drop(store);
+        fs::remove_file(&path).unwrap();
+    }
+}
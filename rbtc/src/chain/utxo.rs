@@ -0,0 +1,621 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::encode::varint::VarInt;
+use crate::block::block::{Block, MAX_BLOCK_SIGOPS_COST};
+use crate::block::check::MAX_MONEY;
+use crate::block::outpoint::OutPoint;
+use crate::block::txout::TxOut;
+use crate::chain::params::ConsensusParams;
+use crate::utils::sha256::Sha256;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Blocks a coinbase output waits before it can be spent
+pub const COINBASE_MATURITY : u32 = 100;
+
+/// The two mainnet blocks whose coinbase overwrote an unspent coinbase of the same
+/// txid before BIP30, height and hash.
+const BIP30_EXCEPTIONS : [(u32, &str); 2] = [
+    (91842, "00000000000a4d0a398161ffc163c503763b1f4360639393e0e4c8e300e0caec"),
+    (91880, "00000000000743f190a18c5577a3c2d2a1f610ae9601ac046a38084ccb7cd721"),
+];
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/coins.h
+///
+/// # Coin
+///
+/// An unspent output, with the height of the block that created it and whether it
+/// was created by a coinbase, which decides when it matures.
+///
+/// ```
+/// +----------+----------+----------------------------+
+/// | Size     | Name     | Type                       |
+/// +----------+----------+----------------------------+
+/// | 4        | height   | u32                        |
+/// +----------+----------+----------------------------+
+/// | 1        | coinbase | bool                       |
+/// +----------+----------+----------------------------+
+/// | 9+       | output   | TxOut                      |
+/// +----------+----------+----------------------------+
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coin {
+    pub output: TxOut,
+    pub height: u32,
+    pub coinbase: bool,
+}
+
+impl Encodable for Coin {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        self.height.encode(w).map_err(|e| Error::CoinHeight.caused_by(e))?;
+        self.coinbase.encode(w).map_err(|e| Error::CoinCoinbase.caused_by(e))?;
+        self.output.encode(w).map_err(|e| Error::CoinOutput.caused_by(e))?;
+        Ok(())
+    }
+}
+
+impl Decodable for Coin {
+
+    fn decode<R: Read>(r: &mut R) -> Result<Coin, Error> {
+
+        trace!("decode");
+
+        let height = u32::decode(r).map_err(|e| Error::CoinHeight.caused_by(e))?;
+        let coinbase = bool::decode(r).map_err(|e| Error::CoinCoinbase.caused_by(e))?;
+        let output = TxOut::decode(r).map_err(|e| Error::CoinOutput.caused_by(e))?;
+
+        let result = Coin {
+            output: output,
+            height: height,
+            coinbase: coinbase,
+        };
+        Ok(result)
+    }
+}
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/undo.h
+///
+/// # Block undo
+///
+/// The coins spent by a block, kept to put them back when the block is disconnected:
+/// one list per transaction but the coinbase, in the order of the inputs.
+///
+/// ```
+/// +----------+----------+----------------------------+
+/// | Size     | Name     | Type                       |
+/// +----------+----------+----------------------------+
+/// | 1+       | count    | var_int                    |
+/// +----------+----------+----------------------------+
+/// | ?        | spent    | (var_int, Coin[])[]        |
+/// +----------+----------+----------------------------+
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BlockUndo {
+    pub spent: Vec<Vec<Coin>>,
+}
+
+impl Encodable for BlockUndo {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+
+        VarInt::new(self.spent.len() as u64).encode(w).map_err(|e| Error::BlockUndoCount.caused_by(e))?;
+        for coins in &self.spent {
+            VarInt::new(coins.len() as u64).encode(w).map_err(|e| Error::BlockUndoCount.caused_by(e))?;
+            for coin in coins {
+                coin.encode(w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decodable for BlockUndo {
+
+    fn decode<R: Read>(r: &mut R) -> Result<BlockUndo, Error> {
+
+        trace!("decode");
+
+        let count = VarInt::decode(r).map_err(|e| Error::BlockUndoCount.caused_by(e))?;
+        let mut spent = vec![];
+        for _ in 0..count.0 {
+            let len = VarInt::decode(r).map_err(|e| Error::BlockUndoCount.caused_by(e))?;
+            let mut coins = vec![];
+            for _ in 0..len.0 {
+                coins.push(Coin::decode(r)?);
+            }
+            spent.push(coins);
+        }
+        Ok(BlockUndo { spent: spent })
+    }
+}
+
+/// The changes of a block connected or disconnected, written at once by the store.
+#[derive(Debug, Default)]
+pub struct UtxoBatch {
+    /// Block the set is at after the batch
+    pub tip: Sha256,
+    /// Coins added or replaced when Some, removed when None
+    pub coins: Vec<(OutPoint, Option<Coin>)>,
+    /// Undo data of the block connected
+    pub undo: Option<(Sha256, BlockUndo)>,
+    /// Block disconnected, whose undo data is dropped
+    pub remove_undo: Option<Sha256>,
+}
+
+/// Where the unspent outputs, the tip and the undo data of the blocks are kept. A batch
+/// is written entirely or not at all, the set never being half way through a block.
+pub trait UtxoStore {
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<Coin>, Error>;
+
+    /// Last block connected, None before the genesis.
+    fn tip(&self) -> Result<Option<Sha256>, Error>;
+
+    fn undo(&self, hash: &Sha256) -> Result<Option<BlockUndo>, Error>;
+
+    fn write(&mut self, batch: UtxoBatch) -> Result<(), Error>;
+}
+
+/// https://en.bitcoin.it/wiki/Protocol_rules#.22block.22_messages
+///
+/// # UTXO set
+///
+/// The outputs not spent yet by the blocks of the chain up to the tip. Blocks are
+/// connected on top of the tip and disconnected from it, in the order of the chain.
+///
+/// Connecting a block checks what needs the spent outputs, after `check_block` and
+/// `check_block_height`:
+///
+/// ```
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | Rule                                | Core reject reason                   | Error              |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | inputs spend unspent outputs        | bad-txns-inputs-missingorspent       | UtxoMissing        |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | coinbase outputs spent after 100    | bad-txns-premature-spend-of-coinbase | UtxoPrematureSpend |
+/// | blocks                              |                                      |                    |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | inputs total <= 21 million          | bad-txns-inputvalues-outofrange      | UtxoInputValues    |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | inputs total >= outputs total       | bad-txns-in-belowout                 | UtxoInBelowOut     |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | sigops cost <= 80 000, P2SH and     | bad-blk-sigops                       | BlockSigOps        |
+/// | witness ones included               |                                      |                    |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | no unspent output overwritten, but  | bad-txns-BIP30                       | UtxoOverwrite      |
+/// | at mainnet 91842 and 91880          |                                      |                    |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// | coinbase outputs <= subsidy + fees  | bad-cb-amount                        | UtxoCoinbaseAmount |
+/// +-------------------------------------+--------------------------------------+--------------------+
+/// ```
+///
+/// The scripts of the inputs are not verified, see `verify_transaction`. The outputs of
+/// the genesis block and the unspendable ones never enter the set.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/validation.cpp ConnectBlock, DisconnectBlock
+pub struct UtxoSet<S: UtxoStore> {
+    store: S,
+    params: ConsensusParams,
+}
+
+impl<S: UtxoStore> UtxoSet<S> {
+
+    pub fn new(store: S, params: ConsensusParams) -> UtxoSet<S> {
+        UtxoSet {
+            store: store,
+            params: params,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn tip(&self) -> Result<Option<Sha256>, Error> {
+        self.store.tip()
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Result<Option<Coin>, Error> {
+        self.store.get(outpoint)
+    }
+
+    /// Spends the inputs of the block at `height` and adds its outputs, keeping the
+    /// coins spent as undo data. Returns the fees of the block. Nothing is written when
+    /// the block breaks a rule.
+    pub fn connect_block(&mut self, block: &Block, height: u32) -> Result<u64, Error> {
+
+        trace!("connect_block");
+
+        let hash = block.hash();
+        match self.store.tip()? {
+            Some(tip) if tip.hash == block.header.previous => {},
+            None if block.header.previous == [0; 32] => {
+                let batch = UtxoBatch { tip: hash, ..UtxoBatch::default() };
+                return self.store.write(batch).map(|_| 0);
+            },
+            _ => return Err(Error::UtxoBlockNotTip),
+        }
+
+        let coinbase = block.transactions.get(0).ok_or(Error::BlockCoinbaseMissing)?;
+        let overwrite = BIP30_EXCEPTIONS.iter().any(|(h, exception)| *h == height && hash.to_string() == *exception);
+
+        let mut view : HashMap<OutPoint, Option<Coin>> = HashMap::new();
+        let mut undo = BlockUndo::default();
+        let mut fees : u64 = 0;
+        let mut sigop_cost = 0;
+        for (index, transaction) in block.transactions.iter().enumerate() {
+
+            if index == 0 {
+                sigop_cost += transaction.sigop_cost(&[])?;
+            } else {
+                let mut spent = Vec::with_capacity(transaction.inputs.len());
+                let mut value_in : u64 = 0;
+                for input in &transaction.inputs {
+                    let coin = self.lookup(&view, &input.previous)?.ok_or(Error::UtxoMissing)?;
+                    if coin.coinbase && height.saturating_sub(coin.height) < COINBASE_MATURITY {
+                        return Err(Error::UtxoPrematureSpend);
+                    }
+                    value_in = value_in.saturating_add(coin.output.amount);
+                    if coin.output.amount > MAX_MONEY || value_in > MAX_MONEY {
+                        return Err(Error::UtxoInputValues);
+                    }
+                    view.insert(input.previous.clone(), None);
+                    spent.push(coin);
+                }
+                let value_out = transaction.outputs.iter().fold(0u64, |total, output| total.saturating_add(output.amount));
+                if value_in < value_out {
+                    return Err(Error::UtxoInBelowOut);
+                }
+                fees += value_in - value_out;
+
+                let spent_outputs : Vec<TxOut> = spent.iter().map(|coin| coin.output.clone()).collect();
+                sigop_cost += transaction.sigop_cost(&spent_outputs)?;
+                undo.spent.push(spent);
+            }
+            if sigop_cost > MAX_BLOCK_SIGOPS_COST {
+                return Err(Error::BlockSigOps);
+            }
+
+            let txid = transaction.txid();
+            for (vout, output) in transaction.outputs.iter().enumerate() {
+                if output.script_pubkey.is_unspendable() {
+                    continue;
+                }
+                let outpoint = OutPoint { transaction_hash: txid.hash, index: vout as u32 };
+                if !overwrite && self.lookup(&view, &outpoint)?.is_some() {
+                    return Err(Error::UtxoOverwrite);
+                }
+                let coin = Coin { output: output.clone(), height: height, coinbase: index == 0 };
+                view.insert(outpoint, Some(coin));
+            }
+        }
+
+        let reward = coinbase.outputs.iter().fold(0u64, |total, output| total.saturating_add(output.amount));
+        if reward > self.params.block_subsidy(height) + fees {
+            return Err(Error::UtxoCoinbaseAmount);
+        }
+
+        let batch = UtxoBatch {
+            tip: hash,
+            coins: view.into_iter().collect(),
+            undo: Some((hash, undo)),
+            remove_undo: None,
+        };
+        self.store.write(batch)?;
+        Ok(fees)
+    }
+
+    /// Removes the outputs of the tip block and puts back the coins it spent, from the
+    /// undo data kept when it was connected. The genesis block cannot be disconnected.
+    pub fn disconnect_block(&mut self, block: &Block) -> Result<(), Error> {
+
+        trace!("disconnect_block");
+
+        let hash = block.hash();
+        if self.store.tip()? != Some(hash) {
+            return Err(Error::UtxoBlockNotTip);
+        }
+        let undo = self.store.undo(&hash)?.ok_or(Error::UtxoUndoMissing)?;
+        if undo.spent.len() + 1 != block.transactions.len() {
+            return Err(Error::UtxoUndoMismatch);
+        }
+
+        let mut view : HashMap<OutPoint, Option<Coin>> = HashMap::new();
+        for (index, transaction) in block.transactions.iter().enumerate().rev() {
+
+            let txid = transaction.txid();
+            for (vout, output) in transaction.outputs.iter().enumerate() {
+                if output.script_pubkey.is_unspendable() {
+                    continue;
+                }
+                let outpoint = OutPoint { transaction_hash: txid.hash, index: vout as u32 };
+                if self.lookup(&view, &outpoint)?.is_none() {
+                    return Err(Error::UtxoUndoMismatch);
+                }
+                view.insert(outpoint, None);
+            }
+
+            if index > 0 {
+                let coins = &undo.spent[index - 1];
+                if coins.len() != transaction.inputs.len() {
+                    return Err(Error::UtxoUndoMismatch);
+                }
+                for (input, coin) in transaction.inputs.iter().zip(coins) {
+                    view.insert(input.previous.clone(), Some(coin.clone()));
+                }
+            }
+        }
+
+        let batch = UtxoBatch {
+            tip: Sha256::new(block.header.previous),
+            coins: view.into_iter().collect(),
+            undo: None,
+            remove_undo: Some(hash),
+        };
+        self.store.write(batch)
+    }
+
+    /// The coin as changed by the block being connected, else as in the store.
+    fn lookup(&self, view: &HashMap<OutPoint, Option<Coin>>, outpoint: &OutPoint) -> Result<Option<Coin>, Error> {
+        match view.get(outpoint) {
+            Some(coin) => Ok(coin.clone()),
+            None => self.store.get(outpoint),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::block::block::Block;
+    use crate::block::check::COIN;
+    use crate::block::header::BlockHeader;
+    use crate::block::merkle::MerkleRoot;
+    use crate::block::outpoint::OutPoint;
+    use crate::block::script::Script;
+    use crate::block::transaction::{Transaction, Transactions};
+    use crate::block::txin::TxIn;
+    use crate::block::txout::TxOut;
+    use crate::chain::memorystore::MemoryStore;
+    use crate::chain::params::ConsensusParams;
+    use crate::chain::sqlitestore::SqliteStore;
+    use crate::chain::utxo::{Coin, BlockUndo, UtxoSet, UtxoStore};
+    use crate::utils::sha256::Sha256;
+
+    use std::io::Cursor;
+
+    fn transaction(inputs: Vec<OutPoint>, outputs: Vec<TxOut>) -> Transaction {
+        Transaction {
+            version: 1,
+            flag: None,
+            inputs: inputs.into_iter().map(|previous| TxIn {
+                previous: previous,
+                signature: Script::default(),
+                sequence: 0xffff_ffff,
+                witness: vec![],
+            }).collect(),
+            outputs: outputs,
+            locktime: 0,
+        }
+    }
+
+    /// An OP_TRUE output of `amount`.
+    pub fn output(amount: u64) -> TxOut {
+        TxOut { amount: amount, script_pubkey: Script::new(vec![0x51]) }
+    }
+
+    pub fn outpoint(transaction: &Transaction, index: u32) -> OutPoint {
+        OutPoint { transaction_hash: transaction.txid().hash, index: index }
+    }
+
+    /// A coinbase with the BIP34 `height`, paying `amount`.
+    pub fn coinbase(height: u32, amount: u64) -> Transaction {
+        let mut script_sig = Script::default();
+        script_sig.push_int(height as i64);
+        script_sig.push_int(0);
+        let mut result = transaction(vec![OutPoint { transaction_hash: [0; 32], index: 0xffff_ffff }], vec![output(amount)]);
+        result.inputs[0].signature = script_sig;
+        result
+    }
+
+    /// A regtest block on top of `previous`, mined.
+    pub fn block(previous: Sha256, transactions: Vec<Transaction>) -> Block {
+
+        let mut block = Block {
+            header: BlockHeader { version: 4, previous: previous.hash, merkleroot: [0; 32], time: 1296688602, bits: 0x207fffff, nonce: 0 },
+            transactions: Transactions::new(transactions),
+        };
+        block.header.merkleroot = MerkleRoot::block(&block).root.hash;
+        let limit = ConsensusParams::regtest().pow_limit;
+        while block.check_pow(&limit).is_err() {
+            block.header.nonce += 1;
+        }
+        block
+    }
+
+    /// A regtest chain of the genesis and `height` blocks paying 50 bitcoins to their
+    /// coinbase, the coinbases of the blocks 1 to `height` - 100 being mature.
+    pub fn chain<S: UtxoStore>(store: S, height: u32) -> (UtxoSet<S>, Vec<Block>) {
+
+        let mut set = UtxoSet::new(store, ConsensusParams::regtest());
+        let mut blocks = vec![block(Sha256::default(), vec![coinbase(0, 50 * COIN)])];
+        assert_eq!(set.connect_block(&blocks[0], 0), Ok(0));
+        for h in 1..=height {
+            let next = block(blocks.last().unwrap().hash(), vec![coinbase(h, 50 * COIN)]);
+            assert_eq!(set.connect_block(&next, h), Ok(0));
+            blocks.push(next);
+        }
+        (set, blocks)
+    }
+
+    fn connect_and_disconnect<S: UtxoStore>(store: S) {
+
+        let (mut set, blocks) = chain(store, 101);
+        let coinbase_1 = outpoint(blocks[1].transactions.get(0).unwrap(), 0);
+        let genesis = outpoint(blocks[0].transactions.get(0).unwrap(), 0);
+        assert_eq!(set.get(&genesis), Ok(None));
+        assert_eq!(set.get(&coinbase_1), Ok(Some(Coin { output: output(50 * COIN), height: 1, coinbase: true })));
+
+        // spends the coinbase of block 1, then one of its outputs in the same block
+        let spend = transaction(vec![coinbase_1.clone()], vec![output(30 * COIN), output(19 * COIN)]);
+        let chained = transaction(vec![outpoint(&spend, 1)], vec![output(18 * COIN)]);
+        let (spend_0, spend_1, chained_0) = (outpoint(&spend, 0), outpoint(&spend, 1), outpoint(&chained, 0));
+        let block_102 = block(blocks[101].hash(), vec![coinbase(102, 52 * COIN), spend, chained]);
+        assert_eq!(set.connect_block(&block_102, 102), Ok(2 * COIN));
+        assert_eq!(set.tip(), Ok(Some(block_102.hash())));
+        assert_eq!(set.get(&coinbase_1), Ok(None));
+        assert_eq!(set.get(&spend_0), Ok(Some(Coin { output: output(30 * COIN), height: 102, coinbase: false })));
+        assert_eq!(set.get(&spend_1), Ok(None));
+        assert_eq!(set.get(&chained_0).unwrap().unwrap().output, output(18 * COIN));
+
+        // spent twice
+        let again = block(block_102.hash(), vec![coinbase(103, 50 * COIN), transaction(vec![coinbase_1.clone()], vec![output(COIN)])]);
+        assert_eq!(set.connect_block(&again, 103), Err(Error::UtxoMissing));
+        assert_eq!(set.tip(), Ok(Some(block_102.hash())));
+
+        assert_eq!(set.disconnect_block(&blocks[101]), Err(Error::UtxoBlockNotTip));
+        assert_eq!(set.disconnect_block(&block_102), Ok(()));
+        assert_eq!(set.tip(), Ok(Some(blocks[101].hash())));
+        assert_eq!(set.get(&coinbase_1), Ok(Some(Coin { output: output(50 * COIN), height: 1, coinbase: true })));
+        assert_eq!(set.get(&spend_0), Ok(None));
+        assert_eq!(set.get(&chained_0), Ok(None));
+        assert_eq!(set.get(&outpoint(block_102.transactions.get(0).unwrap(), 0)), Ok(None));
+        assert_eq!(set.store().undo(&block_102.hash()), Ok(None));
+
+        // and connected again
+        assert_eq!(set.connect_block(&block_102, 102), Ok(2 * COIN));
+    }
+
+    #[test]
+    fn when_memory_chain_then_connect_and_disconnect() {
+        connect_and_disconnect(MemoryStore::new());
+    }
+
+    #[test]
+    fn when_sqlite_chain_then_connect_and_disconnect() {
+        connect_and_disconnect(SqliteStore::memory().unwrap());
+    }
+
+    #[test]
+    fn when_coin_and_undo_then_encode_decode() {
+
+        let coin = Coin { output: output(COIN), height: 7, coinbase: true };
+        let mut data = vec![];
+        coin.encode(&mut data).unwrap();
+        assert_eq!(hex::encode(&data), "070000000100e1f505000000000151");
+        assert_eq!(Coin::decode(&mut Cursor::new(&data)), Ok(coin.clone()));
+
+        let undo = BlockUndo { spent: vec![vec![coin.clone(), coin.clone()], vec![coin]] };
+        let mut data = vec![];
+        undo.encode(&mut data).unwrap();
+        assert_eq!(BlockUndo::decode(&mut Cursor::new(&data)), Ok(undo));
+    }
+
+    #[test]
+    fn when_coinbase_not_100_blocks_deep_then_premature_spend() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 100);
+        let spend = transaction(vec![outpoint(blocks[1].transactions.get(0).unwrap(), 0)], vec![output(COIN)]);
+        let block_101 = block(blocks[100].hash(), vec![coinbase(101, 50 * COIN), spend]);
+        assert_eq!(set.connect_block(&block_101, 100), Err(Error::UtxoPrematureSpend));
+        assert_eq!(set.connect_block(&block_101, 101), Ok(49 * COIN));
+    }
+
+    #[test]
+    fn when_outputs_above_inputs_then_in_belowout() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 101);
+        let spend = transaction(vec![outpoint(blocks[1].transactions.get(0).unwrap(), 0)], vec![output(50 * COIN + 1)]);
+        let block_102 = block(blocks[101].hash(), vec![coinbase(102, 50 * COIN), spend]);
+        assert_eq!(set.connect_block(&block_102, 102), Err(Error::UtxoInBelowOut));
+    }
+
+    #[test]
+    fn when_coinbase_above_subsidy_and_fees_then_cb_amount() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 101);
+        let spend = || transaction(vec![outpoint(blocks[1].transactions.get(0).unwrap(), 0)], vec![output(49 * COIN)]);
+        let block_102 = block(blocks[101].hash(), vec![coinbase(102, 51 * COIN + 1), spend()]);
+        assert_eq!(set.connect_block(&block_102, 102), Err(Error::UtxoCoinbaseAmount));
+
+        // regtest halves every 150 blocks
+        let block_150 = block(blocks[101].hash(), vec![coinbase(150, 50 * COIN), spend()]);
+        assert_eq!(set.connect_block(&block_150, 150), Err(Error::UtxoCoinbaseAmount));
+    }
+
+    #[test]
+    fn when_not_on_tip_then_not_connected() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 2);
+        let fork = block(blocks[1].hash(), vec![coinbase(2, 25 * COIN)]);
+        assert_eq!(set.connect_block(&fork, 2), Err(Error::UtxoBlockNotTip));
+        assert_eq!(set.disconnect_block(&fork), Err(Error::UtxoBlockNotTip));
+    }
+
+    #[test]
+    fn when_genesis_then_no_undo() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 0);
+        assert_eq!(set.disconnect_block(&blocks[0]), Err(Error::UtxoUndoMissing));
+    }
+
+    #[test]
+    fn when_unspent_txid_repeated_then_bip30() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 1);
+        let duplicate = block(blocks[1].hash(), vec![coinbase(1, 50 * COIN)]);
+        assert_eq!(set.connect_block(&duplicate, 2), Err(Error::UtxoOverwrite));
+    }
+
+    #[test]
+    fn when_op_return_then_not_in_set() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 101);
+        let mut spend = transaction(vec![outpoint(blocks[1].transactions.get(0).unwrap(), 0)], vec![output(COIN), output(COIN)]);
+        spend.outputs[1].script_pubkey = Script::new(vec![0x6a, 0x01, 0x01]);
+        let (spend_0, spend_1) = (outpoint(&spend, 0), outpoint(&spend, 1));
+        let block_102 = block(blocks[101].hash(), vec![coinbase(102, 50 * COIN), spend]);
+        assert_eq!(set.connect_block(&block_102, 102), Ok(48 * COIN));
+        assert!(set.get(&spend_0).unwrap().is_some());
+        assert_eq!(set.get(&spend_1), Ok(None));
+        assert_eq!(set.disconnect_block(&block_102), Ok(()));
+    }
+
+    #[test]
+    fn when_p2sh_sigops_over_limit_then_bad_blk_sigops() {
+
+        let (mut set, blocks) = chain(MemoryStore::new(), 102);
+
+        // 260 OP_16 OP_CHECKMULTISIG, 4160 sigops for each P2SH input, costing 16 640
+        let redeem_script = [0x60, 0xae].repeat(260);
+        let mut p2sh = Script::new(vec![0xa9, 0x14]);
+        p2sh.content.extend_from_slice(&[0x01; 20]);
+        p2sh.content.push(0x87);
+
+        let fund = transaction(vec![outpoint(blocks[1].transactions.get(0).unwrap(), 0)], (0..5).map(|_| TxOut { amount: COIN, script_pubkey: p2sh.clone() }).collect());
+        let funded : Vec<OutPoint> = (0..5).map(|index| outpoint(&fund, index)).collect();
+        let block_103 = block(blocks[102].hash(), vec![coinbase(103, 50 * COIN), fund]);
+        assert_eq!(set.connect_block(&block_103, 103), Ok(45 * COIN));
+
+        let spend = |count: usize| {
+            let mut result = transaction(funded[..count].to_vec(), vec![output(COIN)]);
+            for input in result.inputs.iter_mut() {
+                input.signature.push_data(&redeem_script);
+            }
+            block(block_103.hash(), vec![coinbase(104, 50 * COIN), result])
+        };
+        assert_eq!(set.connect_block(&spend(5), 104), Err(Error::BlockSigOps));
+        assert_eq!(set.connect_block(&spend(4), 104), Ok(3 * COIN));
+    }
+}
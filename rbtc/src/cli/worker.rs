@@ -1,5 +1,4 @@
 
-use tokio::codec::length_delimited;
 use tokio::codec::Framed;
 use tokio::io::{AsyncWrite, AsyncRead};
 use tokio::net::{TcpStream, tcp::ConnectFuture};
@@ -13,6 +12,8 @@ use futures::sync::mpsc::{channel, Receiver, Sender};
 use crate::cli::*;
 use crate::cli::result::*;
 use crate::cli::rbtc::*;
+use crate::network::codec::MessageCodec;
+use crate::network::message::Magic;
 
 use std::net::{SocketAddr, AddrParseError};
 use std::io::prelude::*;
@@ -60,7 +61,7 @@ pub struct Worker {
     addr: Option<SocketAddr>,
 
     connect: Option<ConnectFuture>, 
-    framed: Option<Framed<TcpStream, length_delimited::LengthDelimitedCodec>>, 
+    framed: Option<Framed<TcpStream, MessageCodec>>, 
 
 }
 
@@ -192,14 +193,8 @@ impl Worker {
                     Ok(Async::Ready(stream)) => {
                         println!("connecting [Ready]");
 
-                        let ld = length_delimited::Builder::new()
-                            .length_field_offset(16) // bitcoin header + message
-                            .length_field_length(2)
-                            .length_adjustment(-16)   // default value
-                            .num_skip(2) // Do not strip frame header
-                        ;
-                        // let framed = Framed::new(stream, ld);
-                        //self.framed = Some(framed);
+                        let framed = Framed::new(stream, MessageCodec::new(Magic::MainNet));
+                        self.framed = Some(framed);
                         Ok(())
                     },
                     Ok(Async::NotReady) => {
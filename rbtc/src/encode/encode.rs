@@ -1,183 +1,186 @@
 use crate::encode::error::Error;
 use crate::encode::varint::VarInt;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
+/// Largest size of a vector or a message, MAX_SIZE of Core, 32 MiB
+pub const MAX_SIZE : u64 = 0x0200_0000;
+
 pub trait Encodable {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error>;
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error>;
 }
 
 pub trait NetworkEncodable {
-    fn encode_network_byte_order(&self, w: &mut Vec<u8>) -> Result<(), Error>;
+    fn encode_network_byte_order<W: Write>(&self, w: &mut W) -> Result<(), Error>;
 }
 
 pub trait Decodable : Sized {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Self, Error>;
+    fn decode<R: Read>(r: &mut R) -> Result<Self, Error>;
 }
 
 pub trait NetworkDecodable : Sized {
-    fn decode_network_byte_order(r: &mut Cursor<&Vec<u8>>) -> Result<Self, Error>;
+    fn decode_network_byte_order<R: Read>(r: &mut R) -> Result<Self, Error>;
 }
 
 impl Encodable for i64 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_i64::<LittleEndian>(*self).map_err(|_| Error::WriteI64)?;
+        w.write_i64::<LittleEndian>(*self).map_err(|e| Error::WriteI64.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for i64 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<i64, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<i64, Error> {
         trace!("decode");
-        let result = r.read_i64::<LittleEndian>().map_err(|_| Error::ReadI64)?;
+        let result = r.read_i64::<LittleEndian>().map_err(|e| Error::ReadI64.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for i32 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_i32::<LittleEndian>(*self).map_err(|_| Error::WriteI32)?;
+        w.write_i32::<LittleEndian>(*self).map_err(|e| Error::WriteI32.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for i32 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<i32, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<i32, Error> {
         trace!("decode");
-        let result = r.read_i32::<LittleEndian>().map_err(|_| Error::ReadI32)?;
+        let result = r.read_i32::<LittleEndian>().map_err(|e| Error::ReadI32.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for i16 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_i16::<LittleEndian>(*self).map_err(|_| Error::WriteI16)?;
+        w.write_i16::<LittleEndian>(*self).map_err(|e| Error::WriteI16.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for i16 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<i16, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<i16, Error> {
         trace!("decode");
-        let result = r.read_i16::<LittleEndian>().map_err(|_| Error::ReadI16)?;
+        let result = r.read_i16::<LittleEndian>().map_err(|e| Error::ReadI16.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for i8 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_i8(*self).map_err(|_| Error::WriteI8)?;
+        w.write_i8(*self).map_err(|e| Error::WriteI8.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for i8 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<i8, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<i8, Error> {
         trace!("decode");
-        let result = r.read_i8().map_err(|_| Error::ReadI8)?;
+        let result = r.read_i8().map_err(|e| Error::ReadI8.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for u64 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_u64::<LittleEndian>(*self).map_err(|_| Error::WriteU64)?;
+        w.write_u64::<LittleEndian>(*self).map_err(|e| Error::WriteU64.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for u64 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<u64, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<u64, Error> {
         trace!("decode");
-        let result = r.read_u64::<LittleEndian>().map_err(|_| Error::ReadU64)?;
+        let result = r.read_u64::<LittleEndian>().map_err(|e| Error::ReadU64.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for u32 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_u32::<LittleEndian>(*self).map_err(|_| Error::WriteU32)?;
+        w.write_u32::<LittleEndian>(*self).map_err(|e| Error::WriteU32.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for u32 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<u32, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<u32, Error> {
         trace!("decode");
-        let result = r.read_u32::<LittleEndian>().map_err(|_| Error::ReadU32)?;
+        let result = r.read_u32::<LittleEndian>().map_err(|e| Error::ReadU32.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for u16 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_u16::<LittleEndian>(*self).map_err(|_| Error::WriteU16)?;
+        w.write_u16::<LittleEndian>(*self).map_err(|e| Error::WriteU16.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for u16 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<u16, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<u16, Error> {
         trace!("decode");
-        let result = r.read_u16::<LittleEndian>().map_err(|_| Error::ReadU16)?;
+        let result = r.read_u16::<LittleEndian>().map_err(|e| Error::ReadU16.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl NetworkEncodable for u16 {
-    fn encode_network_byte_order(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode_network_byte_order<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_u16::<BigEndian>(*self).map_err(|_| Error::WriteU16)?;
+        w.write_u16::<BigEndian>(*self).map_err(|e| Error::WriteU16.caused_by(e))?;
         Ok(())
     }
 }
 
 impl NetworkDecodable for u16 {
 
-    fn decode_network_byte_order(r: &mut Cursor<&Vec<u8>>) -> Result<u16, Error> {
+    fn decode_network_byte_order<R: Read>(r: &mut R) -> Result<u16, Error> {
         trace!("decode");
-        let result = r.read_u16::<BigEndian>().map_err(|_| Error::ReadU16)?;
+        let result = r.read_u16::<BigEndian>().map_err(|e| Error::ReadU16.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for u8 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_u8(*self).map_err(|_| Error::WriteU8)?;
+        w.write_u8(*self).map_err(|e| Error::WriteU8.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for u8 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<u8, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<u8, Error> {
         trace!("decode");
-        let result = r.read_u8().map_err(|_| Error::ReadU8)?;
+        let result = r.read_u8().map_err(|e| Error::ReadU8.caused_by(e))?;
         Ok(result)
     }
 }
 
 impl Encodable for bool {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
         let value : u8 = if *self { 1 } else { 0 };
-        value.encode(w).map_err(|_| Error::WriteBool)?;
+        value.encode(w).map_err(|e| Error::WriteBool.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for bool {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<bool, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<bool, Error> {
         trace!("decode");
-        let b = u8::decode(r).map_err(|_| Error::ReadBool)?;
+        let b = u8::decode(r).map_err(|e| Error::ReadBool.caused_by(e))?;
         let result = match b {
             0 => false,
             _ => true
@@ -190,17 +193,17 @@ macro_rules! impl_array {
     ( $size:expr ) => (
 
         impl Encodable for [u8; $size] {
-            fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+            fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
                 trace!("encode");
-                w.write_all(self).map_err(|_| Error::WriteAll)?;
+                w.write_all(self).map_err(|e| Error::WriteAll.caused_by(e))?;
                 Ok(())
             }
         }
         impl Decodable for [u8; $size]  {
-            fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<[u8; $size], Error> {
+            fn decode<R: Read>(r: &mut R) -> Result<[u8; $size], Error> {
                 trace!("decode");
                 let mut result = [0u8; $size];
-                r.read_exact(&mut result).map_err(|_| Error::ReadExact)?;
+                r.read_exact(&mut result).map_err(|e| Error::ReadExact.caused_by(e))?;
                 Ok(result)
             }
         }
@@ -215,22 +218,74 @@ impl_array!(16);
 impl_array!(32);
 
 impl Encodable for Vec<u8> {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        w.write_all(&self).map_err(|_| Error::VecContent)?;
+        w.write_all(&self).map_err(|e| Error::VecContent.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Vec<u8> {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<u8>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<u8>, Error> {
 
         trace!("decode");
-        let varint = VarInt::decode(r).map_err(|_| Error::VecLen)?;
-        let mut content = vec![0u8; varint.0 as usize];
-        let mut content_ref = content.as_mut_slice();
-        r.read_exact(&mut content_ref).map_err(|_| Error::VecContent)?;
+        let varint = VarInt::decode(r).map_err(|e| Error::VecLen.caused_by(e))?;
+        if varint.0 > MAX_SIZE {
+            return Err(Error::VecTooLarge);
+        }
+        let content = read_bytes(r, varint.0).map_err(|e| Error::VecContent.caused_by(e))?;
 
         Ok(content)
     }
 }
+
+/// Reads exactly `len` bytes. The buffer grows with the bytes read: a length sent by
+/// a peer allocates no more than the bytes that actually arrive.
+pub fn read_bytes<R: Read>(r: &mut R, len: u64) -> Result<Vec<u8>, Error> {
+
+    let mut result = vec![];
+    r.take(len).read_to_end(&mut result).map_err(|e| Error::ReadExact.caused_by(e))?;
+    if (result.len() as u64) < len {
+        return Err(Error::ReadExact);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Decodable, MAX_SIZE};
+
+    /// `len` bytes of a xorshift generator, the same for a seed.
+    pub fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..len).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect()
+    }
+
+    /// Decodes `prefix` followed by up to 1000 bytes of noise, for 256 seeds, each
+    /// decoding failing with `expected` without allocating what the prefix announces.
+    pub fn fuzz<T: Decodable + std::fmt::Debug>(prefix: &[u8], expected: Error) {
+        for seed in 0..256 {
+            let mut data = prefix.to_vec();
+            data.extend(noise(seed, (seed as usize * 37) % 1000));
+            let result = T::decode(&mut &data[..]);
+            assert_eq!(result.as_ref().err(), Some(&expected), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn when_vec_len_over_max_size_then_error() {
+        // 0x02000001 then 2^64 - 1
+        fuzz::<Vec<u8>>(&[0xfe, 0x01, 0x00, 0x00, 0x02], Error::VecTooLarge);
+        fuzz::<Vec<u8>>(&[0xff; 9], Error::VecTooLarge);
+        // under the maximum, but missing bytes
+        fuzz::<Vec<u8>>(&[0xfe, 0x00, 0x00, 0x00, 0x02], Error::VecContent);
+        assert_eq!(MAX_SIZE, 32 * 1024 * 1024);
+    }
+}
@@ -1,5 +1,14 @@
 
-#[derive(PartialEq, Debug)]
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+/// The kind of an error, compared by kind only: an error with a context equals the
+/// unit variant of its kind, `Error::TxInSequence` at any offset is `Error::TxInSequence`.
+///
+/// The decoders wrap their errors in `Error::Context`, so a bare variant pattern such as
+/// `Err(Error::PayloadLen) =>` no longer matches them: match on `error.kind()` instead.
+#[derive(Debug)]
 pub enum Error {
 
     ReadI64,
@@ -30,9 +39,12 @@ pub enum Error {
 
     VecContent,
     VecLen,
+    VecTooLarge,
 
 
     Sha256Count,
+    Sha256Parse,
+    Uint256Parse,
     ReadSha256,
     WriteSha256,
     
@@ -48,6 +60,7 @@ pub enum Error {
 
     MessageMagic,
     MessageMagicReverse,
+    MessageIo,
 
     PingNonce,
     PongNonce,
@@ -59,6 +72,7 @@ pub enum Error {
     PayloadChecksum,
     PayloadChecksumData,
     PayloadChecksumInvalid,
+    PayloadTooLarge,
     PayloadUnknown,
     PayloadCommandString,
 
@@ -68,6 +82,14 @@ pub enum Error {
     GetHeadersLocator,
     GetHeadersStop,
 
+    GetBlocksVersion,
+    GetBlocksLocators,
+    GetBlocksStop,
+
+    HeadersCount,
+    HeadersTransactionCount,
+    HeadersTooMany,
+
     VersionVersion,
     VersionServices,
     VersionTimestamp,
@@ -112,16 +134,149 @@ pub enum Error {
     BlockTime,
     BlockNonce,
     BlockBits,
+    BlockTooLarge,
+    BlockMerkleRootMismatch,
+    BlockMerkleRootMutated,
+    BlockWitnessCommitment,
+    BlockWitnessReservedValue,
+    BlockWitnessUnexpected,
+    BlockWeight,
+    BlockSigOps,
+    BlockLength,
+    BlockCoinbaseMissing,
+    BlockCoinbaseMultiple,
+    BlockCoinbaseHeight,
+
+    PowTargetNegative,
+    PowTargetOverflow,
+    PowTargetZero,
+    PowTargetAboveLimit,
+    PowHashAboveTarget,
+
+    ChainPreviousUnknown,
+    ChainBadDifficulty,
+    ChainTimeTooOld,
+
+    UtxoMissing,
+    UtxoPrematureSpend,
+    UtxoInputValues,
+    UtxoInBelowOut,
+    UtxoCoinbaseAmount,
+    UtxoOverwrite,
+    UtxoBlockNotTip,
+    UtxoUndoMissing,
+    UtxoUndoMismatch,
+    UtxoStore,
+    CoinHeight,
+    CoinCoinbase,
+    CoinOutput,
+    BlockUndoCount,
+
+    BlockFileOpen,
+    BlockFileRead,
+    BlockFileWrite,
+    BlockFileMagic,
+    BlockFileSize,
+    BlockFileXorKey,
+    BlockPosFile,
+    BlockPosOffset,
+    BlockIndexCount,
     
     TransactionsCount,
+    TransactionsTooMany,
     TransactionFlag,
+    TransactionWitnessEmpty,
     TransactionVersion,
     TransactionLockTime,
+    TransactionInputsEmpty,
+    TransactionOutputsEmpty,
+    TransactionOversize,
+    TransactionOutputValue,
+    TransactionOutputTotal,
+    TransactionInputsDuplicate,
+    TransactionCoinbaseLength,
+    TransactionPrevoutNull,
 
     Script,
+    ScriptPushTruncated,
+    ScriptAsm,
     Signature,
     ScriptPubKey,
 
+    ScriptEvalFalse,
+    ScriptOpReturn,
+    ScriptSize,
+    ScriptPushSize,
+    ScriptOpCount,
+    ScriptStackSize,
+    ScriptSigCount,
+    ScriptPubKeyCount,
+    ScriptVerify,
+    ScriptEqualVerify,
+    ScriptCheckMultiSigVerify,
+    ScriptCheckSigVerify,
+    ScriptNumEqualVerify,
+    ScriptBadOpcode,
+    ScriptDisabledOpcode,
+    ScriptInvalidStackOperation,
+    ScriptInvalidAltStackOperation,
+    ScriptUnbalancedConditional,
+    ScriptNegativeLockTime,
+    ScriptUnsatisfiedLockTime,
+    ScriptSigHashType,
+    ScriptSigHashInputIndex,
+    ScriptSigDer,
+    ScriptMinimalData,
+    ScriptSigPushOnly,
+    ScriptSigHighS,
+    ScriptSigNullDummy,
+    ScriptPubKeyType,
+    ScriptCleanStack,
+    ScriptMinimalIf,
+    ScriptNullFail,
+    ScriptNum,
+    ScriptDiscourageUpgradableNops,
+    ScriptDiscourageUpgradableWitnessProgram,
+    ScriptWitnessProgramWrongLength,
+    ScriptWitnessProgramWitnessEmpty,
+    ScriptWitnessProgramMismatch,
+    ScriptWitnessMalleated,
+    ScriptWitnessMalleatedP2sh,
+    ScriptWitnessUnexpected,
+    ScriptWitnessPubKeyType,
+    ScriptOpCodeSeparator,
+    ScriptSigFindAndDelete,
+    ScriptSchnorrSigSize,
+    ScriptSchnorrSigHashType,
+    ScriptSchnorrSig,
+    ScriptTaprootWrongControlSize,
+    ScriptTapscriptValidationWeight,
+    ScriptTapscriptCheckMultiSig,
+    ScriptTapscriptMinimalIf,
+    ScriptTapscriptEmptyPubKey,
+    ScriptDiscourageOpSuccess,
+    ScriptDiscourageUpgradableTaprootVersion,
+    ScriptDiscourageUpgradablePubKeyType,
+    TaprootInternalKey,
+    TaprootTweak,
+    SpentOutputsCount,
+
+    Base58Char,
+    Base58Checksum,
+
+    Bech32Char,
+    Bech32Length,
+    Bech32Separator,
+    Bech32Data,
+    Bech32Checksum,
+    Bech32Padding,
+    Bech32Hrp,
+    Bech32WitnessVersion,
+    Bech32WitnessProgram,
+
+    AddressLength,
+    AddressPrefix,
+
     OutputsCount,
     TxOutAmount,
 
@@ -134,6 +289,7 @@ pub enum Error {
     WitnessesCount,
     WitnessLen,
     WitnessData,
+    WitnessTooLarge,
 
     VarInt,
     VarIntFD,
@@ -145,9 +301,225 @@ pub enum Error {
     
     AlertLen,
     AlertMessage,
+    AlertTooLarge,
 
-    InvLen,
-    InvMessage,
+    InvCount,
+    InvTooMany,
+    InvVectType,
+    InvVectHash,
 
     AddrCount,
-}
\ No newline at end of file
+    AddrTooMany,
+
+    /// An error of another kind with where it happened, see `Error::within`
+    Context(Box<Context>),
+}
+
+/// Where an error happened: the offset of the byte being read in the outermost
+/// structure, the structures being decoded, outermost first, and the error it was
+/// mapped from.
+///
+/// ```
+/// TxInSequence at byte 1043 in block -> tx #312 -> input #2
+///  caused by ReadU32
+///  caused by failed to fill whole buffer
+/// ```
+///
+#[derive(Debug)]
+pub struct Context {
+    pub kind: Error,
+    pub offset: Option<u64>,
+    pub path: Vec<Frame>,
+    pub source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+/// A structure in the path of an error, with its index when in a list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub name: &'static str,
+    pub index: Option<usize>,
+}
+
+impl Frame {
+
+    pub fn new(name: &'static str) -> Frame {
+        Frame {
+            name: name,
+            index: None,
+        }
+    }
+
+    /// The item `index` of a list, `tx #312`.
+    pub fn item(name: &'static str, index: usize) -> Frame {
+        Frame {
+            name: name,
+            index: Some(index),
+        }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{} #{}", self.name, index),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl Error {
+
+    /// The kind of the error, never a `Context`.
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Context(context) => &context.kind,
+            _ => self,
+        }
+    }
+
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Context(context) => context.offset,
+            _ => None,
+        }
+    }
+
+    /// The structures being decoded, outermost first.
+    pub fn path(&self) -> &[Frame] {
+        match self {
+            Error::Context(context) => &context.path,
+            _ => &[],
+        }
+    }
+
+    fn context(self) -> Box<Context> {
+        match self {
+            Error::Context(context) => context,
+            kind => Box::new(Context {
+                kind: kind,
+                offset: None,
+                path: vec![],
+                source: None,
+            }),
+        }
+    }
+
+    /// The error happened while decoding `frame`, the frames being added from the
+    /// innermost structure out.
+    pub fn within(self, frame: Frame) -> Error {
+        let mut context = self.context();
+        context.path.insert(0, frame);
+        Error::Context(context)
+    }
+
+    /// The error happened reading the byte at `offset`, kept when already known.
+    pub fn at(self, offset: u64) -> Error {
+        let mut context = self.context();
+        context.offset = context.offset.or(Some(offset));
+        Error::Context(context)
+    }
+
+    /// This kind of error, mapped from `source`. The offset and the path of a source
+    /// of this crate move to the new error, as the place it happened.
+    pub fn caused_by<E: Into<Box<dyn StdError + Send + Sync>>>(self, source: E) -> Error {
+        let mut context = self.context();
+        let source = match source.into().downcast::<Error>() {
+            Ok(error) => {
+                let mut inner = error.context();
+                context.offset = context.offset.or(inner.offset.take());
+                context.path.append(&mut inner.path);
+                Box::new(Error::Context(inner)) as Box<dyn StdError + Send + Sync>
+            },
+            Err(source) => source,
+        };
+        context.source = Some(source);
+        Error::Context(context)
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        std::mem::discriminant(self.kind()) == std::mem::discriminant(other.kind())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Context(context) => {
+                write!(f, "{:?}", context.kind)?;
+                if let Some(offset) = context.offset {
+                    write!(f, " at byte {}", offset)?;
+                }
+                if !context.path.is_empty() {
+                    let path : Vec<String> = context.path.iter().map(|frame| frame.to_string()).collect();
+                    write!(f, " in {}", path.join(" -> "))?;
+                }
+                Ok(())
+            },
+            kind => write!(f, "{:?}", kind),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Context(context) => context.source.as_ref().map(|source| source.as_ref() as &(dyn StdError + 'static)),
+            _ => None,
+        }
+    }
+}
+
+/// Required by `tokio::codec::{Decoder, Encoder}` for the errors of the stream, the
+/// decoders map their io errors explicitly with `caused_by`.
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::MessageIo.caused_by(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::{Error, Frame};
+
+    use std::error::Error as StdError;
+    use std::io;
+
+    #[test]
+    fn when_context_then_equal_to_kind() {
+
+        let error = Error::TxInSequence.within(Frame::item("input", 2)).at(12);
+        assert_eq!(error, Error::TxInSequence);
+        assert_ne!(error, Error::TxInIndex);
+        assert_eq!(error.kind(), &Error::TxInSequence);
+        assert_eq!(Error::TxInSequence.offset(), None);
+        assert!(Error::TxInSequence.path().is_empty());
+    }
+
+    #[test]
+    fn when_mapped_then_path_and_offset_kept_and_source_chained() {
+
+        let read = Error::ReadU32.caused_by(io::Error::new(io::ErrorKind::UnexpectedEof, "eof"));
+        let error = Error::TxInSequence.caused_by(read)
+            .within(Frame::item("input", 2))
+            .at(1043)
+            .within(Frame::item("tx", 312))
+            .within(Frame::new("block"))
+            .at(1);
+
+        assert_eq!(error.offset(), Some(1043));
+        assert_eq!(error.to_string(), "TxInSequence at byte 1043 in block -> tx #312 -> input #2");
+
+        let source = error.source().unwrap();
+        assert_eq!(source.to_string(), "ReadU32");
+        assert_eq!(source.source().unwrap().to_string(), "eof");
+
+        let inner = Error::Script.within(Frame::new("script")).at(7);
+        let error = Error::Signature.caused_by(inner).within(Frame::item("input", 0));
+        assert_eq!(error.path(), &[Frame::item("input", 0), Frame::new("script")]);
+        assert_eq!(error.offset(), Some(7));
+        assert_eq!(error.source().unwrap().to_string(), "Script");
+    }
+}
@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::Cursor;
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 
@@ -43,21 +43,21 @@ impl VarInt {
 
 impl Decodable for VarInt {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<VarInt, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<VarInt, Error> {
 
         trace!("decode");
-        let varlen = r.read_u8().map_err(|_| Error::VarInt)?;
+        let varlen = r.read_u8().map_err(|e| Error::VarInt.caused_by(e))?;
         match varlen {
-            0xFD => u16::decode(r).map(|v| VarInt::new(v as u64)).map_err(|_| Error::VarIntFD),
-            0xFE => u32::decode(r).map(|v| VarInt::new(v as u64)).map_err(|_| Error::VarIntFE),
-            0xFF => u64::decode(r).map(|v| VarInt::new(v as u64)).map_err(|_| Error::VarIntFF),
+            0xFD => u16::decode(r).map(|v| VarInt::new(v as u64)).map_err(|e| Error::VarIntFD.caused_by(e)),
+            0xFE => u32::decode(r).map(|v| VarInt::new(v as u64)).map_err(|e| Error::VarIntFE.caused_by(e)),
+            0xFF => u64::decode(r).map(|v| VarInt::new(v as u64)).map_err(|e| Error::VarIntFF.caused_by(e)),
             _ => Ok(VarInt::new(varlen as u64))
         }
     }
 }
 impl Encodable for VarInt {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
         
@@ -68,20 +68,20 @@ impl Encodable for VarInt {
             _ => 0xFF,
         };
 
-        size_enc.encode(w).map_err(|_| Error::VarInt)?;
+        size_enc.encode(w).map_err(|e| Error::VarInt.caused_by(e))?;
 
         match self.0 {
             0...0xFC => {},
             0xFD...0xFFFF => {
                 let s = self.0 as u16;
-                s.encode(w).map_err(|_| Error::VarIntFD)?;
+                s.encode(w).map_err(|e| Error::VarIntFD.caused_by(e))?;
             },
             0x10000...0xFFFFFFFF => {
                 let s = self.0 as u32;
-                s.encode(w).map_err(|_| Error::VarIntFE)?;
+                s.encode(w).map_err(|e| Error::VarIntFE.caused_by(e))?;
             },
             _ => {
-                self.0.encode(w).map_err(|_| Error::VarIntFF)?;
+                self.0.encode(w).map_err(|e| Error::VarIntFF.caused_by(e))?;
             },
         };
 
@@ -103,7 +103,7 @@ mod test {
     fn when_decode_varint_0x00_then_1_byte() {
 
         let data : Vec<u8> = vec![0x00, 0x00, 0x00, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 1);
@@ -116,7 +116,7 @@ mod test {
     fn when_decode_varint_0xfc_then_1_byte() {
 
         let data : Vec<u8> = vec![0xfc, 0x00, 0x00, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 1);
@@ -129,7 +129,7 @@ mod test {
     fn when_decode_varint_0xfd_then_3_byte() {
 
         let data : Vec<u8> = vec![0xfd, 0xfe, 0x00, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 3);
@@ -142,7 +142,7 @@ mod test {
     fn when_decode_varint_0xfd_fe_01_then_3_byte() {
 
         let data : Vec<u8> = vec![0xfd, 0xfe, 0x01, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 3);
@@ -156,7 +156,7 @@ mod test {
     fn when_decode_varint_0xfe_then_5_byte() {
 
         let data : Vec<u8> = vec![0xfe, 0x03, 0x02, 0x01, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 5);
@@ -168,7 +168,7 @@ mod test {
     fn when_decode_varint_0xff_then_9_byte() {
 
         let data : Vec<u8> = vec![0xff, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_ok());
         assert_eq!(c.position(), 9);
@@ -182,7 +182,7 @@ mod test {
     fn when_decode_varint_0xff_too_small_then_fail_parseerror_varint_ff() {
 
         let data : Vec<u8> = vec![0xff, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_err());
         assert_eq!(c.position(), 1);
@@ -199,7 +199,7 @@ mod test {
     fn when_decode_varint_0xfe_too_small_then_fail_parseerror_varint_fe() {
 
         let data : Vec<u8> = vec![0xfe, 0x07, 0x06, 0x05 ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_err());
         assert_eq!(c.position(), 1);
@@ -215,7 +215,7 @@ mod test {
     fn when_decode_varint_0xfd_too_small_then_fail_parseerror_varint_fd() {
 
         let data : Vec<u8> = vec![0xfd, 0x07 ];
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let varint = VarInt::decode(&mut c);
         assert!(varint.is_err());
         assert_eq!(c.position(), 1);
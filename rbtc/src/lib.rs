@@ -17,9 +17,11 @@ extern crate microstate;
 extern crate futures;
 
 extern crate tokio;
+extern crate rusqlite;
 
 pub mod network;
 pub mod block;
+pub mod chain;
 pub mod utils;
 pub mod encode;
 pub mod cli;
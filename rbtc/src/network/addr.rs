@@ -3,7 +3,7 @@ use crate::encode::varint::VarInt;
 use crate::encode::encode::{Encodable, Decodable};
 use crate::network::networkaddr::TimedNetworkAddr;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#addr
@@ -62,7 +62,7 @@ pub struct Addr {
 
 impl Encodable for Addr {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
         self.addrs.encode(w)?;
         Ok(())
@@ -71,7 +71,7 @@ impl Encodable for Addr {
 
 impl Decodable for Addr {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Addr, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Addr, Error> {
         trace!("decode");
         let addrs = <Vec<TimedNetworkAddr>>::decode(r)?;
         let result= Addr {
@@ -1,9 +1,9 @@
 use crate::encode::error::Error;
-use crate::encode::encode::{Encodable, Decodable};
+use crate::encode::encode::{Encodable, Decodable, MAX_SIZE, read_bytes};
 use crate::encode::varint::VarInt;
 
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#alert
@@ -133,7 +133,7 @@ pub struct Alert {
 
 impl Encodable for Alert {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
         let varint = VarInt::new(self.data.len() as u64);
@@ -145,13 +145,14 @@ impl Encodable for Alert {
 
 impl Decodable for Alert {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Alert, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Alert, Error> {
 
         trace!("decode");
-        let varlen = VarInt::decode(r).map_err(|_| Error::AlertLen)?;
-        let mut data = vec![0u8; varlen.0 as usize];
-        let mut data_ref = data.as_mut_slice();
-        r.read_exact(&mut data_ref).map_err(|_| Error::AlertMessage)?;
+        let varlen = VarInt::decode(r).map_err(|e| Error::AlertLen.caused_by(e))?;
+        if varlen.0 > MAX_SIZE {
+            return Err(Error::AlertTooLarge);
+        }
+        let data = read_bytes(r, varlen.0).map_err(|e| Error::AlertMessage.caused_by(e))?;
 
         let result = Alert {
             data: data
@@ -165,6 +166,8 @@ mod test {
 
     use crate::encode::encode::{Encodable, Decodable};
     use crate::network::alert::Alert;
+    use crate::encode::error::Error;
+    use crate::encode::encode::test::fuzz;
 
     use std::io::Cursor;
 
@@ -196,4 +199,10 @@ mod test {
         assert_eq!(expected, result.unwrap());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn when_decode_alert_over_max_size_then_error() {
+        // 0x02000001 then 2^64 - 1 bytes
+        fuzz::<Alert>(&[0xfe, 0x01, 0x00, 0x00, 0x02], Error::AlertTooLarge);
+        fuzz::<Alert>(&[0xff; 9], Error::AlertTooLarge);
+    }
+}
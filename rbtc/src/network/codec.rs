@@ -0,0 +1,304 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::network::message::{Magic, Message};
+
+use std::io::Cursor;
+use bytes::BytesMut;
+use byteorder::{LittleEndian, ByteOrder};
+use tokio::codec::{Decoder, Encoder};
+
+/// Largest payload accepted by default, MAX_PROTOCOL_MESSAGE_LENGTH of Core
+pub const MAX_PAYLOAD_SIZE : usize = 4_000_000;
+/// magic, command, length and checksum
+const HEADER_SIZE : usize = 24;
+/// Offset of the length in the header
+const LENGTH_OFFSET : usize = 16;
+
+/// https://github.com/bitcoin/bitcoin/blob/master/src/net.cpp V1Transport
+///
+/// # Message codec
+///
+/// Frames the messages of a stream of a network, for `tokio::codec::Framed`:
+///
+/// ```
+/// garbage | magic command length checksum | payload | magic command ...
+///         | <---------- 24 bytes -------> | length  |
+/// ```
+///
+/// The header is read first, then the codec waits for exactly `length` bytes of
+/// payload before decoding the message, one message at a time. The bytes before the
+/// magic of the network are dropped, which resyncs the stream after garbage. A
+/// payload over the maximum size fails the stream, a message with an invalid checksum
+/// or an unknown command is skipped.
+///
+#[derive(Debug, Clone)]
+pub struct MessageCodec {
+    magic: Magic,
+    max_payload_size: usize,
+}
+
+impl MessageCodec {
+
+    pub fn new(magic: Magic) -> MessageCodec {
+        MessageCodec {
+            magic: magic,
+            max_payload_size: MAX_PAYLOAD_SIZE,
+        }
+    }
+
+    /// Payloads up to `size` bytes instead of 4 000 000.
+    pub fn with_max_payload_size(mut self, size: usize) -> MessageCodec {
+        self.max_payload_size = size;
+        self
+    }
+
+    fn magic_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        self.magic.encode(&mut result).unwrap_or_default();
+        result
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Message;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>, Error> {
+
+        trace!("decode");
+
+        let magic = self.magic_bytes();
+        loop {
+            match src.windows(magic.len()).position(|window| window == &magic[..]) {
+                Some(0) => {},
+                Some(start) => {
+                    debug!("decode [skipped: {}]", start);
+                    src.advance(start);
+                },
+                None => {
+                    // the end may be the beginning of a magic
+                    let keep = src.len().min(magic.len() - 1);
+                    let skip = src.len() - keep;
+                    src.advance(skip);
+                    return Ok(None);
+                },
+            }
+
+            if src.len() < HEADER_SIZE {
+                return Ok(None);
+            }
+            let length = LittleEndian::read_u32(&src[LENGTH_OFFSET..LENGTH_OFFSET + 4]) as usize;
+            if length > self.max_payload_size {
+                return Err(Error::PayloadTooLarge);
+            }
+            if src.len() < HEADER_SIZE + length {
+                src.reserve(HEADER_SIZE + length - src.len());
+                return Ok(None);
+            }
+
+            let frame = src.split_to(HEADER_SIZE + length).to_vec();
+            let mut r = Cursor::new(&frame);
+            match Message::decode(&mut r).map_err(|e| e.at(r.position())) {
+                Ok(message) => return Ok(Some(message)),
+                Err(ref error) if matches!(error.kind(), Error::PayloadChecksumInvalid | Error::CommandFromStr) => {
+                    debug!("decode [skipped: {}]", hex::encode(&frame[4..LENGTH_OFFSET]));
+                },
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Message>, Error> {
+
+        trace!("decode_eof");
+
+        let result = self.decode(src)?;
+        if result.is_none() {
+            src.clear();
+        }
+        Ok(result)
+    }
+}
+
+impl Encoder for MessageCodec {
+    type Item = Message;
+    type Error = Error;
+
+    fn encode(&mut self, message: Message, dst: &mut BytesMut) -> Result<(), Error> {
+
+        trace!("encode");
+
+        let mut buffer = vec![];
+        message.encode(&mut buffer)?;
+        if buffer.len() > HEADER_SIZE + self.max_payload_size {
+            return Err(Error::PayloadTooLarge);
+        }
+        dst.extend_from_slice(&buffer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::Encodable;
+    use crate::network::codec::MessageCodec;
+    use crate::network::command::Command;
+    use crate::network::getaddr::GetAddr;
+    use crate::network::message::{Magic, Message, Payload};
+    use crate::network::verack::VerAck;
+
+    use bytes::BytesMut;
+    use futures::{Async, Future, Poll, Stream, Sink};
+    use std::io::{self, Read, Write, Cursor};
+    use tokio::codec::{Decoder, FramedRead, FramedWrite};
+    use tokio::io::{AsyncRead, AsyncWrite};
+
+    // ping of nonce 0x0102030405060708 on mainnet
+    const PING : &str = "f9beb4d970696e670000000000000000080000003b5a75130807060504030201";
+
+    fn encode(payload: Payload) -> Vec<u8> {
+        let mut result = vec![];
+        Message { magic: Magic::MainNet, payload: payload }.encode(&mut result).unwrap();
+        result
+    }
+
+    /// getaddr, ping and verack messages, one after the other.
+    fn stream() -> Vec<u8> {
+        let mut result = encode(Payload::GetAddr(GetAddr {}));
+        result.extend(hex::decode(PING).unwrap());
+        result.extend(encode(Payload::VerAck(VerAck {})));
+        result
+    }
+
+    fn commands(messages: &[Message]) -> Vec<Command> {
+        messages.iter().map(|message| message.payload.to_command()).collect()
+    }
+
+    /// A reader returning `size` bytes at most on each read.
+    struct Fragments {
+        data: Cursor<Vec<u8>>,
+        size: usize,
+    }
+
+    impl Read for Fragments {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.size);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    impl AsyncRead for Fragments {}
+
+    /// A writer keeping what is written.
+    struct Written(Vec<u8>);
+
+    impl Write for Written {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl AsyncWrite for Written {
+        fn shutdown(&mut self) -> Poll<(), io::Error> {
+            Ok(Async::Ready(()))
+        }
+    }
+
+    fn read(data: Vec<u8>, size: usize) -> Result<Vec<Message>, Error> {
+        let fragments = Fragments { data: Cursor::new(data), size: size };
+        FramedRead::new(fragments, MessageCodec::new(Magic::MainNet)).collect().wait()
+    }
+
+    #[test]
+    fn when_coalesced_then_one_message_at_a_time() {
+
+        let mut codec = MessageCodec::new(Magic::MainNet);
+        let mut src = BytesMut::from(stream());
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap().payload.to_command(), Command::GetAddr);
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap().payload.to_command(), Command::Ping);
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap().payload.to_command(), Command::VerAck);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn when_fragmented_then_wait_for_whole_message() {
+
+        let data = stream();
+        let mut codec = MessageCodec::new(Magic::MainNet);
+        let mut src = BytesMut::new();
+        let mut messages = vec![];
+        for byte in data {
+            src.extend_from_slice(&[byte]);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                messages.push(message);
+            }
+        }
+        assert_eq!(commands(&messages), vec![Command::GetAddr, Command::Ping, Command::VerAck]);
+
+        for size in &[1, 3, 7, 24, 25, 1000] {
+            assert_eq!(commands(&read(stream(), *size).unwrap()), vec![Command::GetAddr, Command::Ping, Command::VerAck]);
+        }
+    }
+
+    #[test]
+    fn when_garbage_then_resync_on_magic() {
+
+        let mut data = vec![0x00, 0xf9, 0xbe, 0x13, 0x37];
+        data.extend(stream());
+        // a magic of another network, the beginning of a magic at the end
+        data.extend(hex::decode("fabfb5da0000f9be").unwrap());
+        for size in &[1, 5, 1000] {
+            assert_eq!(commands(&read(data.clone(), *size).unwrap()), vec![Command::GetAddr, Command::Ping, Command::VerAck]);
+        }
+
+        let mut codec = MessageCodec::new(Magic::MainNet);
+        let mut src = BytesMut::from(hex::decode("0102030405f9beb4").unwrap());
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert_eq!(&src[..], &[0xf9, 0xbe, 0xb4]);
+    }
+
+    #[test]
+    fn when_payload_over_max_then_error() {
+
+        let mut ping = hex::decode(PING).unwrap();
+        let mut codec = MessageCodec::new(Magic::MainNet).with_max_payload_size(7);
+        assert_eq!(codec.decode(&mut BytesMut::from(ping.clone())).err(), Some(Error::PayloadTooLarge));
+
+        // the length is checked before the payload arrives
+        ping[16..20].copy_from_slice(&0xffff_ffffu32.to_le_bytes());
+        ping.truncate(24);
+        let mut codec = MessageCodec::new(Magic::MainNet);
+        assert_eq!(codec.decode(&mut BytesMut::from(ping)).err(), Some(Error::PayloadTooLarge));
+    }
+
+    #[test]
+    fn when_checksum_invalid_or_command_unknown_then_skipped() {
+
+        let mut data = hex::decode(PING).unwrap();
+        data[20] ^= 0x01;
+        // a "sendcmpct" message
+        data.extend(hex::decode("f9beb4d973656e64636d70637400000009000000e92f5ef8000200000000000000").unwrap());
+        data.extend(stream());
+        assert_eq!(commands(&read(data, 1000).unwrap()), vec![Command::GetAddr, Command::Ping, Command::VerAck]);
+    }
+
+    #[test]
+    fn when_messages_sent_then_stream_bytes() {
+
+        let messages = vec![
+            Message { magic: Magic::MainNet, payload: Payload::GetAddr(GetAddr {}) },
+            Message { magic: Magic::MainNet, payload: Payload::VerAck(VerAck {}) },
+        ];
+        let sink = FramedWrite::new(Written(vec![]), MessageCodec::new(Magic::MainNet));
+        let sink = sink.send_all(futures::stream::iter_ok::<_, Error>(messages)).wait().unwrap().0;
+        let mut expected = encode(Payload::GetAddr(GetAddr {}));
+        expected.extend(encode(Payload::VerAck(VerAck {})));
+        assert_eq!(sink.into_inner().0, expected);
+    }
+}
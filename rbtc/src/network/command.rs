@@ -4,7 +4,7 @@ use crate::encode::encode::{Encodable, Decodable};
 use std::fmt;
 use std::str::FromStr;
 use std::string::ToString;
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 #[derive(PartialEq, Debug)]
@@ -15,12 +15,18 @@ pub enum Command {
     Version,
     VerAck,
     GetHeaders,
+    Headers,
     GetAddr,
     Alert,
     Addr,
     Ping,
     Pong,
     Inv,
+    GetData,
+    NotFound,
+    GetBlocks,
+    Block,
+    Tx,
 }
 
 impl FromStr for Command {
@@ -30,6 +36,7 @@ impl FromStr for Command {
          match s {
             "version" => Ok(Command::Version),
             "getheaders" => Ok(Command::GetHeaders),
+            "headers" => Ok(Command::Headers),
             "getaddr" => Ok(Command::GetAddr),
             "verack" => Ok(Command::VerAck),
             "alert" => Ok(Command::Alert),
@@ -37,6 +44,11 @@ impl FromStr for Command {
             "ping" => Ok(Command::Ping),
             "pong" => Ok(Command::Pong),
             "inv" => Ok(Command::Inv),
+            "getdata" => Ok(Command::GetData),
+            "notfound" => Ok(Command::NotFound),
+            "getblocks" => Ok(Command::GetBlocks),
+            "block" => Ok(Command::Block),
+            "tx" => Ok(Command::Tx),
             _ => Err(())
         }
     }
@@ -48,6 +60,7 @@ impl ToString for Command {
         match self {
             Command::Version => "version",
             Command::GetHeaders => "getheaders",
+            Command::Headers => "headers",
             Command::GetAddr => "getaddr",
             Command::VerAck => "verack",
             Command::Alert => "alert",
@@ -55,6 +68,11 @@ impl ToString for Command {
             Command::Ping => "ping",
             Command::Pong => "pong",
             Command::Inv => "inv",
+            Command::GetData => "getdata",
+            Command::NotFound => "notfound",
+            Command::GetBlocks => "getblocks",
+            Command::Block => "block",
+            Command::Tx => "tx",
         }.to_owned()
     }
 }
@@ -68,13 +86,13 @@ impl CommandString {
 
 impl Encodable for CommandString {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
         let mut command = format!("{:}\0\0\0\0\0\0\0\0\0\0\0\0", self.0)
             .to_lowercase();
         command.truncate(12);
-        w.write_all(command.as_bytes()).map_err(|_| Error::Command)?;
+        w.write_all(command.as_bytes()).map_err(|e| Error::Command.caused_by(e))?;
 
         Ok(())
     }
@@ -82,11 +100,11 @@ impl Encodable for CommandString {
 
 impl Decodable for CommandString {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<CommandString, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<CommandString, Error> {
         
         trace!("decode");
-        let buffer = <[u8; 12]>::decode(r).map_err(|_| Error::Command)?;
-        let mut s = String::from_utf8(buffer.to_vec()).map_err(|_| Error::CommandDecode)?;
+        let buffer = <[u8; 12]>::decode(r).map_err(|e| Error::Command.caused_by(e))?;
+        let mut s = String::from_utf8(buffer.to_vec()).map_err(|e| Error::CommandDecode.caused_by(e))?;
         s.retain(|c| c != (0 as char));
 
         let result = CommandString(s);
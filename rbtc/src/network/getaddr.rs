@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#getaddr
@@ -24,7 +24,7 @@ pub struct GetAddr {
 
 impl Encodable for GetAddr {
 
-    fn encode(&self, _: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, _: &mut W) -> Result<(), Error> {
         trace!("encode");
         Ok(())
     }
@@ -32,7 +32,7 @@ impl Encodable for GetAddr {
 
 impl Decodable for GetAddr {
 
-    fn decode(_: &mut Cursor<&Vec<u8>>) -> Result<GetAddr, Error> {
+    fn decode<R: Read>(_: &mut R) -> Result<GetAddr, Error> {
         trace!("decode");
         Ok(GetAddr {})
     }
@@ -0,0 +1,127 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::utils::sha256::Sha256;
+
+use std::io::{Read, Write};
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#getblocks
+///
+/// # getblocks
+///
+/// Return an inv packet containing the list of blocks starting right after the last known hash
+/// in the block locator object, up to hash_stop or 500 blocks, whichever comes first.
+/// The blocks are then requested with a getdata.
+///
+/// Payload:
+///
+/// ```
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// | Field Size    | Description              | Data type   | Comments                                              |
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// |     4         | version                  | uint32_t    | the protocol version                                  |
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// |     1+        | hash count               | var_int     | number of block locator hash entries                  |
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// |     32+       | block locator hashes     | char[32]    | block locator object; newest back to genesis block    |
+/// |               |                          |             | (dense to start, but then sparse)                     |
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// |     32        | hash_stop                | char[32]    | hash of the last desired block;                       |
+/// |               |                          |             | set to zero to get as many blocks as possible (500)   |
+/// +---------------+--------------------------+-------------+-------------------------------------------------------+
+/// ```
+///
+#[derive(Debug, PartialEq)]
+pub struct GetBlocks {
+    /// The protocol version
+    pub version: u32,
+    /// Locator hashes, ordered newest to oldest
+    pub locators: Vec<Sha256>,
+    /// References the block to stop at, or zero to fetch the maximum 500 blocks
+    pub stop: Sha256
+}
+
+impl Encodable for GetBlocks {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        self.version.encode(w).map_err(|e| Error::GetBlocksVersion.caused_by(e))?;
+        self.locators.encode(w).map_err(|e| Error::GetBlocksLocators.caused_by(e))?;
+        self.stop.encode(w).map_err(|e| Error::GetBlocksStop.caused_by(e))?;
+
+        Ok(())
+    }
+}
+
+impl Decodable for GetBlocks {
+
+    fn decode<R: Read>(r: &mut R) -> Result<GetBlocks, Error> {
+
+        trace!("decode");
+        let version = u32::decode(r).map_err(|e| Error::GetBlocksVersion.caused_by(e))?;
+        let locators = <Vec<Sha256>>::decode(r).map_err(|e| Error::GetBlocksLocators.caused_by(e))?;
+        let stop = Sha256::decode(r).map_err(|e| Error::GetBlocksStop.caused_by(e))?;
+
+        let result = GetBlocks {
+            version: version,
+            locators: locators,
+            stop: stop
+        };
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::network::getblocks::GetBlocks;
+    use crate::network::message::{Message, Payload};
+    use crate::utils::hexdump;
+    use crate::utils::sha256::Sha256;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_decode_getblocks_message_then_same() {
+
+        let dump = "
+00000000   F9 BE B4 D9 67 65 74 62  6C 6F 63 6B 73 00 00 00   main.getblocks..
+00000010   45 00 00 00 6A 74 9B 6F  80 11 01 00 01 48 60 EB   len.chks.ver.c.l
+00000020   18 BF 1B 16 20 E3 7E 94  90 FC 8A 42 75 14 41 6F   ocator.locator.l
+00000030   D7 51 59 AB 86 68 8E 9A  83 00 00 00 00 00 00 00   ocator.stop.stop
+00000040   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   stop.stop.stop.s
+00000050   00 00 00 00 00 00 00 00  00 00 00 00 00            top.stop.stop...
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+
+        let expected = GetBlocks {
+            version: 70016,
+            locators: vec!["00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048".parse::<Sha256>().unwrap()],
+            stop: Sha256::default(),
+        };
+        match message.payload {
+            Payload::GetBlocks(ref getblocks) => assert_eq!(getblocks, &expected),
+            _ => panic!("should be getblocks"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+
+    #[test]
+    fn when_decode_getblocks_without_stop_then_error() {
+
+        let data : Vec<u8> = hex::decode("801101000100").unwrap();
+        assert_eq!(GetBlocks::decode(&mut Cursor::new(&data)).err(), Some(Error::GetBlocksLocators));
+        let data : Vec<u8> = hex::decode("8011010000").unwrap();
+        assert_eq!(GetBlocks::decode(&mut Cursor::new(&data)).err(), Some(Error::GetBlocksStop));
+    }
+}
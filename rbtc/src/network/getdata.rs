@@ -0,0 +1,93 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::network::inv::InvVect;
+
+use std::io::{Read, Write};
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#getdata
+///
+/// # getdata
+///
+/// getdata is used in response to inv, to retrieve the content of a specific object, and is
+/// usually sent after receiving an inv packet, after filtering known elements. It can be used
+/// to retrieve transactions, but only if they are in the memory pool or relay set.
+///
+/// Payload (maximum 50,000 entries, which is just over 1.8 megabytes):
+///
+/// ```
+/// +------+-------------+------------+-----------------------------+
+/// | Size | Description | Data type  | Comments                    |
+/// +------+-------------+------------+-----------------------------+
+/// |   1+ | count       | var_int    | Number of inventory entries |
+/// | 36x? | inventory   | inv_vect[] | Inventory vectors           |
+/// +------+-------------+------------+-----------------------------+
+/// ```
+///
+#[derive(Debug, PartialEq)]
+pub struct GetData {
+    pub inventory: Vec<InvVect>,
+}
+
+impl Encodable for GetData {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        self.inventory.encode(w)
+    }
+}
+
+impl Decodable for GetData {
+
+    fn decode<R: Read>(r: &mut R) -> Result<GetData, Error> {
+
+        trace!("decode");
+        let inventory = <Vec<InvVect>>::decode(r)?;
+
+        let result = GetData {
+            inventory: inventory
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::network::getdata::GetData;
+    use crate::network::inv::{InvVect, InvType};
+    use crate::network::message::{Message, Payload};
+    use crate::utils::hexdump;
+    use crate::utils::sha256::Sha256;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_decode_getdata_message_then_same() {
+
+        let dump = "
+00000000   F9 BE B4 D9 67 65 74 64  61 74 61 00 00 00 00 00   main.getdata....
+00000010   25 00 00 00 97 E8 F9 E9  01 02 00 00 40 6F E2 8C   len.chks.c.type.
+00000020   0A B6 F1 B3 72 C1 A6 A2  46 AE 63 F7 4F 93 1E 83   hash.hash.hash.h
+00000030   65 E1 5A 08 9C 68 D6 19  00 00 00 00 00            ash.hash.hash.ha
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+
+        let expected = GetData {
+            inventory: vec![InvVect::new(InvType::WitnessBlock, "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f".parse::<Sha256>().unwrap())]
+        };
+        match message.payload {
+            Payload::GetData(ref getdata) => assert_eq!(getdata, &expected),
+            _ => panic!("should be getdata"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+}
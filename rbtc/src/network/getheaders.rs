@@ -2,7 +2,7 @@ use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 use crate::utils::sha256::Sha256;
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 
@@ -49,24 +49,24 @@ pub struct GetHeaders {
 
 impl Encodable for GetHeaders {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
-        self.version.encode(w).map_err(|_| Error::GetHeadersVersion)?;
-        self.locators.encode(w).map_err(|_| Error::GetHeadersLocators)?;
-        self.stop.encode(w).map_err(|_| Error::GetHeadersStop)?;
+        self.version.encode(w).map_err(|e| Error::GetHeadersVersion.caused_by(e))?;
+        self.locators.encode(w).map_err(|e| Error::GetHeadersLocators.caused_by(e))?;
+        self.stop.encode(w).map_err(|e| Error::GetHeadersStop.caused_by(e))?;
 
         Ok(())
     }
 }
 impl Decodable for GetHeaders {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<GetHeaders, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<GetHeaders, Error> {
 
         trace!("decode");
-        let version = u32::decode(r).map_err(|_| Error::GetHeadersVersion)?;
-        let locators = <Vec<Sha256>>::decode(r).map_err(|_| Error::GetHeadersLocators)?;
-        let stop = Sha256::decode(r).map_err(|_| Error::GetHeadersStop)?;
+        let version = u32::decode(r).map_err(|e| Error::GetHeadersVersion.caused_by(e))?;
+        let locators = <Vec<Sha256>>::decode(r).map_err(|e| Error::GetHeadersLocators.caused_by(e))?;
+        let stop = Sha256::decode(r).map_err(|e| Error::GetHeadersStop.caused_by(e))?;
 
         let result = GetHeaders {
             version: version,
@@ -99,7 +99,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = GetHeaders::decode(&mut c);
         assert!(result.is_err());
         assert_eq!(c.position(), 0);
@@ -119,7 +119,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = GetHeaders::decode(&mut c);
         assert!(result.is_err());
         assert_eq!(c.position(), 0);
@@ -139,7 +139,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         let result = GetHeaders::decode(&mut c);
         assert!(result.is_err());
         assert_eq!(c.position(), 4);
@@ -166,7 +166,7 @@ mod test {
 ";
 
         let data : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(data.as_ref());
+        let mut c = Cursor::new(&data);
         c.set_position(24); // move beyond network protocol headers
 
         let result = GetHeaders::decode(&mut c);
@@ -210,7 +210,7 @@ mod test {
 ";
 
         let original : Vec<u8> = hexdump::decode(dump);
-        let mut c = Cursor::new(original.as_ref());
+        let mut c = Cursor::new(&original);
 
         let decoded = GetHeaders::decode(&mut c);
         assert!(decoded.is_ok());
@@ -0,0 +1,172 @@
+use crate::encode::error::{Error, Frame};
+use crate::encode::varint::VarInt;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::block::header::BlockHeader;
+
+use std::io::{Read, Write};
+
+/// Maximum headers of a headers message, MAX_HEADERS_RESULTS of Core
+pub const MAX_HEADERS_RESULTS : u64 = 2000;
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#headers
+///
+/// # headers
+/// The headers packet returns block headers in response to a getheaders packet.
+///
+/// ## Payload:
+/// ```
+/// +------------+-------------+--------------+-------------------------------------------------+
+/// | Field Size | Description | Data type    | Comments                                        |
+/// +------------+-------------+--------------+-------------------------------------------------+
+/// |     1+     | count       | var_int      | Number of block headers (max: 2000)             |
+/// +------------+-------------+--------------+-------------------------------------------------+
+/// |    81x?    | headers     | block_header | Block headers, each followed by a transaction   |
+/// |            |             |              | count var_int that is always 0                  |
+/// +------------+-------------+--------------+-------------------------------------------------+
+/// ```
+///
+#[derive(Debug, PartialEq)]
+pub struct Headers {
+    pub headers: Vec<BlockHeader>,
+}
+
+impl Encodable for Headers {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        let varint = VarInt::new(self.headers.len() as u64);
+        varint.encode(w).map_err(|e| Error::HeadersCount.caused_by(e))?;
+        for header in self.headers.iter() {
+            header.encode(w)?;
+            VarInt::new(0).encode(w).map_err(|e| Error::HeadersTransactionCount.caused_by(e))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decodable for Headers {
+
+    fn decode<R: Read>(r: &mut R) -> Result<Headers, Error> {
+
+        trace!("decode");
+        let varint = VarInt::decode(r).map_err(|e| Error::HeadersCount.caused_by(e))?;
+        if varint.0 > MAX_HEADERS_RESULTS {
+            return Err(Error::HeadersTooMany);
+        }
+        let mut headers : Vec<BlockHeader> = Vec::new();
+        for i in 0..varint.0 {
+            let header = BlockHeader::decode(r).map_err(|e| e.within(Frame::item("header", i as usize)))?;
+            let count = VarInt::decode(r).map_err(|e| Error::HeadersTransactionCount.caused_by(e))?;
+            if count.0 != 0 {
+                return Err(Error::HeadersTransactionCount);
+            }
+            headers.push(header);
+        }
+
+        let result = Headers {
+            headers: headers
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::network::headers::Headers;
+    use crate::network::message::{Message, Magic, Payload};
+    use crate::encode::error::Error;
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::encode::encode::test::fuzz;
+    use crate::utils::hexdump;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_decode_with_empty_vec_then_fail_parse_error_headerscount() {
+
+        let data : Vec<u8> = Vec::new();
+        let mut c = Cursor::new(&data);
+        let result = Headers::decode(&mut c);
+
+        assert_eq!(result, Err(Error::HeadersCount));
+    }
+
+    #[test]
+    fn when_decode_header_with_transactions_then_fail_parse_error_headerstransactioncount() {
+
+        let dump = "
+00000000   01 01 00 00 00 00 00 00  00 00 00 00 00 00 00 00   c.ver.previous.p
+00000010   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   revious.previous
+00000020   00 00 00 00 00 3B A3 ED  FD 7A 7B 12 B2 7A C7 2C   .prev.merkleroot
+00000030   3E 67 76 8F 61 7F C8 1B  C3 88 8A 51 32 3A 9F B8   .merkleroot.merk
+00000040   AA 4B 1E 5E 4A 29 AB 5F  49 FF FF 00 1D 1D AC 2B   leroo.time.bits.
+00000050   7C 01                                              no..............
+";
+
+        let data : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&data);
+        let result = Headers::decode(&mut c);
+
+        assert_eq!(result, Err(Error::HeadersTransactionCount));
+    }
+
+    #[test]
+    fn when_decode_headers_message_then_same() {
+
+        let dump = "
+00000000   F9 BE B4 D9 68 65 61 64  65 72 73 00 00 00 00 00   main.headers....
+00000010   52 00 00 00 0B 0E 13 EB  01 01 00 00 00 00 00 00   len.chks.c.ver.p
+00000020   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   revious.previous
+00000030   00 00 00 00 00 00 00 00  00 00 00 00 00 3B A3 ED   .previo.merkler.
+00000040   FD 7A 7B 12 B2 7A C7 2C  3E 67 76 8F 61 7F C8 1B   merkleroot.merkl
+00000050   C3 88 8A 51 32 3A 9F B8  AA 4B 1E 5E 4A 29 AB 5F   eroot.merkl.time
+00000060   49 FF FF 00 1D 1D AC 2B  7C 00                     .bits.nonc.c....
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let result = Message::decode(&mut c);
+        assert!(result.is_ok());
+        assert_eq!(c.position() as usize, original.len());
+
+        let message = result.unwrap();
+        if let Payload::Headers(ref headers) = message.payload {
+            assert_eq!(headers.headers.len(), 1, "headers.len");
+            assert_eq!(
+                headers.headers[0].hash().to_string(),
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+                "headers[0].hash"
+            );
+        } else {
+            panic!("should be headers");
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        assert!(message.encode(&mut encoded).is_ok());
+        assert_eq!(original, encoded);
+    }
+
+    #[test]
+    fn when_encode_empty_headers_then_single_count() {
+
+        let message = Message {
+            magic: Magic::MainNet,
+            payload: Payload::Headers(Headers { headers: Vec::new() })
+        };
+
+        let mut data : Vec<u8> = Vec::new();
+        assert!(message.encode(&mut data).is_ok());
+        assert_eq!(data.len(), 25);
+        assert_eq!(data[24], 0x00);
+    }
+
+    #[test]
+    fn when_decode_over_2000_headers_then_error() {
+        // 2001 then 2^64 - 1 headers
+        fuzz::<Headers>(&[0xfd, 0xd1, 0x07], Error::HeadersTooMany);
+        fuzz::<Headers>(&[0xff; 9], Error::HeadersTooMany);
+    }
+}
@@ -1,10 +1,119 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 use crate::encode::varint::VarInt;
+use crate::utils::sha256::Sha256;
 
+use std::io::{Read, Write};
 
-use std::io::{Read, Write, Cursor};
-use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
+/// Maximum entries of an inv, MAX_INV_SZ of Core
+pub const MAX_INV_SIZE : u64 = 50_000;
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#Inventory_Vectors
+///
+/// # Object type
+///
+/// The kind of object an inventory vector refers to:
+///
+/// ```
+/// +------------+----------------+--------------------------------------------------+
+/// | Value      | Name           | Description                                      |
+/// +------------+----------------+--------------------------------------------------+
+/// | 0          | ERROR          | Any data of with this number may be ignored      |
+/// | 1          | MSG_TX         | Hash of a transaction                            |
+/// | 2          | MSG_BLOCK      | Hash of a block                                  |
+/// | 3          | MSG_FILTERED_  | Hash of a block, replied with a merkleblock,     |
+/// |            | BLOCK          | BIP37                                            |
+/// | 4          | MSG_CMPCT_     | Hash of a block, replied with a cmpctblock,      |
+/// |            | BLOCK          | BIP152                                           |
+/// | 5          | MSG_WTX        | Witness txid of a transaction, BIP339            |
+/// | 0x40000001 | MSG_WITNESS_TX | Hash of a transaction, with its witness, BIP144  |
+/// | 0x40000002 | MSG_WITNESS_   | Hash of a block, with its witnesses, BIP144      |
+/// |            | BLOCK          |                                                  |
+/// | 0x40000003 | MSG_FILTERED_  | Hash of a block, replied with a merkleblock,     |
+/// |            | WITNESS_BLOCK  | with witnesses, reserved                         |
+/// | other      |                | Unknown, kept so that the peer's inventory can   |
+/// |            |                | be ignored instead of failing the whole message  |
+/// +------------+----------------+--------------------------------------------------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/protocol.h GetDataMsg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvType {
+    Error,
+    Tx,
+    Block,
+    FilteredBlock,
+    CompactBlock,
+    WitnessTx,
+    WitnessBlock,
+    FilteredWitnessBlock,
+    WTx,
+    Unknown(u32),
+}
+
+impl InvType {
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            InvType::Error => 0,
+            InvType::Tx => 1,
+            InvType::Block => 2,
+            InvType::FilteredBlock => 3,
+            InvType::CompactBlock => 4,
+            InvType::WTx => 5,
+            InvType::WitnessTx => 0x4000_0001,
+            InvType::WitnessBlock => 0x4000_0002,
+            InvType::FilteredWitnessBlock => 0x4000_0003,
+            InvType::Unknown(value) => value,
+        }
+    }
+
+    pub fn from_u32(value: u32) -> InvType {
+        match value {
+            0 => InvType::Error,
+            1 => InvType::Tx,
+            2 => InvType::Block,
+            3 => InvType::FilteredBlock,
+            4 => InvType::CompactBlock,
+            5 => InvType::WTx,
+            0x4000_0001 => InvType::WitnessTx,
+            0x4000_0002 => InvType::WitnessBlock,
+            0x4000_0003 => InvType::FilteredWitnessBlock,
+            _ => InvType::Unknown(value),
+        }
+    }
+}
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#Inventory_Vectors
+///
+/// # Inventory vector
+///
+/// Refers to an object, a transaction or a block, in inv, getdata and notfound messages:
+///
+/// ```
+/// +------+-------------+-----------+-------------------------------+
+/// | Size | Description | Data type | Comments                      |
+/// +------+-------------+-----------+-------------------------------+
+/// |    4 | type        | uint32_t  | Type of object, see InvType   |
+/// |   32 | hash        | char[32]  | Hash of the object            |
+/// +------+-------------+-----------+-------------------------------+
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvVect {
+    pub inv_type: InvType,
+    pub hash: Sha256,
+}
+
+impl InvVect {
+
+    pub fn new(inv_type: InvType, hash: Sha256) -> InvVect {
+        InvVect {
+            inv_type: inv_type,
+            hash: hash,
+        }
+    }
+}
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#inv
 /// 
@@ -26,33 +135,80 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 /// 
 #[derive(Debug, PartialEq)]
 pub struct Inv {
-    data: Vec<u8>,
+    pub inventory: Vec<InvVect>,
 }
 
-impl Encodable for Inv {
+impl Encodable for InvVect {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        self.inv_type.to_u32().encode(w).map_err(|e| Error::InvVectType.caused_by(e))?;
+        self.hash.encode(w).map_err(|e| Error::InvVectHash.caused_by(e))?;
+        Ok(())
+    }
+}
+
+impl Decodable for InvVect {
+
+    fn decode<R: Read>(r: &mut R) -> Result<InvVect, Error> {
+
+        trace!("decode");
+        let value = u32::decode(r).map_err(|e| Error::InvVectType.caused_by(e))?;
+        let inv_type = InvType::from_u32(value);
+        let hash = Sha256::decode(r).map_err(|e| Error::InvVectHash.caused_by(e))?;
+        Ok(InvVect::new(inv_type, hash))
+    }
+}
+
+impl Encodable for Vec<InvVect> {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
-        let varint = VarInt::new(self.data.len() as u64);
-        varint.encode(w)?;
-        self.data.encode(w)?;
+        VarInt::new(self.len() as u64).encode(w).map_err(|e| Error::InvCount.caused_by(e))?;
+        for inv in self {
+            inv.encode(w)?;
+        }
         Ok(())
     }
 }
 
+impl Decodable for Vec<InvVect> {
+
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<InvVect>, Error> {
+
+        trace!("decode");
+        let count = VarInt::decode(r).map_err(|e| Error::InvCount.caused_by(e))?;
+        if count.0 > MAX_INV_SIZE {
+            return Err(Error::InvTooMany);
+        }
+        let mut result = vec![];
+        for _ in 0..count.0 {
+            result.push(InvVect::decode(r)?);
+        }
+        Ok(result)
+    }
+}
+
+impl Encodable for Inv {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        self.inventory.encode(w)
+    }
+}
+
 impl Decodable for Inv {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Inv, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Inv, Error> {
 
         trace!("decode");
-        let varlen = VarInt::decode(r).map_err(|_| Error::InvLen)?;
-        let mut data = vec![0u8; varlen.0 as usize];
-        let mut data_ref = data.as_mut_slice();
-        r.read_exact(&mut data_ref).map_err(|_| Error::InvMessage)?;
+        let inventory = <Vec<InvVect>>::decode(r)?;
 
         let result = Inv {
-            data: data
+            inventory: inventory
         };
         Ok(result)
     }
@@ -62,15 +218,23 @@ impl Decodable for Inv {
 mod test {
 
     use crate::encode::encode::{Encodable, Decodable};
-    use crate::network::inv::Inv;
+    use crate::encode::error::Error;
+    use crate::encode::encode::test::fuzz;
+    use crate::network::inv::{Inv, InvVect, InvType};
+    use crate::network::message::{Message, Payload};
+    use crate::utils::hexdump;
+    use crate::utils::sha256::Sha256;
 
     use std::io::Cursor;
 
+    const GENESIS : &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+    const GENESIS_COINBASE : &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
     #[test]
     fn when_encode_inv_then_nothing_to_encode() {
 
         let message = Inv {
-            data: Vec::new()
+            inventory: Vec::new()
         };
         let mut data : Vec<u8> = Vec::new();
 
@@ -87,11 +251,77 @@ mod test {
         let result = Inv::decode(&mut read);
 
         let expected = Inv {
-            data: Vec::new()
+            inventory: Vec::new()
         };
 
         assert!(result.is_ok());
         assert_eq!(expected, result.unwrap());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn when_decode_inv_message_then_same() {
+
+        let dump = "
+00000000   F9 BE B4 D9 69 6E 76 00  00 00 00 00 00 00 00 00   main.inv........
+00000010   49 00 00 00 FD 9F F5 28  02 01 00 00 00 3B A3 ED   len.chks.c.type.
+00000020   FD 7A 7B 12 B2 7A C7 2C  3E 67 76 8F 61 7F C8 1B   hash.hash.hash.h
+00000030   C3 88 8A 51 32 3A 9F B8  AA 4B 1E 5E 4A 02 00 00   ash.hash.hash.ty
+00000040   00 6F E2 8C 0A B6 F1 B3  72 C1 A6 A2 46 AE 63 F7   p.hash.hash.hash
+00000050   4F 93 1E 83 65 E1 5A 08  9C 68 D6 19 00 00 00 00   .hash.hash.hash.
+00000060   00                                                 h...............
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+
+        let expected = Inv {
+            inventory: vec![
+                InvVect::new(InvType::Tx, GENESIS_COINBASE.parse::<Sha256>().unwrap()),
+                InvVect::new(InvType::Block, GENESIS.parse::<Sha256>().unwrap()),
+            ]
+        };
+        match message.payload {
+            Payload::Inv(ref inv) => assert_eq!(inv, &expected),
+            _ => panic!("should be inv"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+
+    #[test]
+    fn when_inv_type_then_core_value() {
+
+        let types = [
+            (InvType::Error, 0), (InvType::Tx, 1), (InvType::Block, 2), (InvType::FilteredBlock, 3),
+            (InvType::CompactBlock, 4), (InvType::WTx, 5), (InvType::WitnessTx, 0x4000_0001), (InvType::WitnessBlock, 0x4000_0002),
+            (InvType::FilteredWitnessBlock, 0x4000_0003), (InvType::Unknown(6), 6), (InvType::Unknown(0x4000_0004), 0x4000_0004),
+        ];
+        for (inv_type, value) in types.iter() {
+            assert_eq!(inv_type.to_u32(), *value);
+            assert_eq!(InvType::from_u32(*value), *inv_type);
+        }
+
+        // an unknown type does not fail the message and is encoded back
+        let mut data : Vec<u8> = hex::decode("0204000040").unwrap();
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(&hex::decode("02000000").unwrap());
+        data.extend_from_slice(&[1; 32]);
+        let inv = Inv::decode(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(inv.inventory[0], InvVect::new(InvType::Unknown(0x4000_0004), Sha256::default()));
+        assert_eq!(inv.inventory[1].inv_type, InvType::Block);
+        let mut encoded : Vec<u8> = Vec::new();
+        inv.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn when_decode_inv_over_50000_entries_then_error() {
+        // 50 001 then 2^64 - 1 entries
+        fuzz::<Inv>(&[0xfe, 0x51, 0xc3, 0x00, 0x00], Error::InvTooMany);
+        fuzz::<Inv>(&[0xff; 9], Error::InvTooMany);
+    }
+}
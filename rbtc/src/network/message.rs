@@ -1,8 +1,9 @@
-use crate::encode::error::Error;
-use crate::encode::encode::{Encodable, Decodable};
+use crate::encode::error::{Error, Frame};
+use crate::encode::encode::{Encodable, Decodable, MAX_SIZE};
 use crate::network::command::{CommandString, Command};
 
 use crate::network::getheaders;
+use crate::network::headers;
 use crate::network::getaddr;
 use crate::network::version;
 use crate::network::verack;
@@ -11,8 +12,13 @@ use crate::network::addr;
 use crate::network::ping;
 use crate::network::pong;
 use crate::network::inv;
+use crate::network::getdata;
+use crate::network::notfound;
+use crate::network::getblocks;
+use crate::block::block::Block;
+use crate::block::transaction::Transaction;
 
-use sha2::{Sha256, Digest};
+use crate::utils::sha256::Sha256;
 
 use std::fmt;
 use std::str::FromStr;
@@ -29,18 +35,21 @@ use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 /// +-----------+-------------+-------------------+
 /// | main      | 0xD9B4BEF9  | F9 BE B4 D9       |
 /// +-----------+-------------+-------------------+
-/// | testnet   | 0xDAB5BFFA  | FA BF B5 DA       |
-/// +-----------+-------------+-------------------+
 /// | testnet3  | 0x0709110B  | 0B 11 09 07       |
 /// +-----------+-------------+-------------------+
+/// | signet    | 0x40CF030A  | 0A 03 CF 40       |
+/// +-----------+-------------+-------------------+
+/// | regtest   | 0xDAB5BFFA  | FA BF B5 DA       |
+/// +-----------+-------------+-------------------+
 /// | namecoin  | 0xFEB4BEF9  | F9 BE B4 FE       |
 /// +-----------+-------------+-------------------+
 /// ```
 /// 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Magic {
     MainNet,
     TestNet,
+    SigNet,
     RegTest,
 }
 
@@ -49,28 +58,30 @@ impl Magic {
         match *self {
             Magic::MainNet => &[ 0xD9, 0xB4, 0xBE, 0xF9 ],
             Magic::TestNet => &[ 0x07, 0x09, 0x11, 0x0B ],
+            Magic::SigNet => &[ 0x40, 0xCF, 0x03, 0x0A ],
             Magic::RegTest => &[ 0xDA, 0xB5, 0xBF, 0xFA ],
         }
     }
 }
 
 impl Encodable for Magic {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         let mut wire = self.value().clone();
         wire.reverse();
-        w.write_all(&wire).map_err(|_| Error::MessageMagic)?;
+        w.write_all(&wire).map_err(|e| Error::MessageMagic.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Magic {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Magic, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Magic, Error> {
         let mut buffer = [0u8; 4];
-        r.read_exact(&mut buffer).map_err(|_| Error::MessageMagic)?;
+        r.read_exact(&mut buffer).map_err(|e| Error::MessageMagic.caused_by(e))?;
         buffer.reverse();
         match buffer {
             [ 0xD9, 0xB4, 0xBE, 0xF9 ] => Ok(Magic::MainNet),
             [ 0x07, 0x09, 0x11, 0x0B ] => Ok(Magic::TestNet),
+            [ 0x40, 0xCF, 0x03, 0x0A ] => Ok(Magic::SigNet),
             [ 0xDA, 0xB5, 0xBF, 0xFA ] => Ok(Magic::RegTest),
             _ => Err(Error::Magic)
         }
@@ -114,13 +125,8 @@ impl Message {
 
     fn checksum(payload: &Vec<u8>) -> Result<[u8; 4], ()> {
 
-        let mut hasher = Sha256::default();
-        hasher.input(payload.as_slice());
-        let res1 = hasher.result_reset();
-        hasher.input(res1);
-
-        let res2 = hasher.result_reset();
-        let hash = [res2[0], res2[1], res2[2], res2[3]];
+        let res = Sha256::double(payload.as_slice());
+        let hash = [res.hash[0], res.hash[1], res.hash[2], res.hash[3]];
 
         Ok(hash)
     }
@@ -128,7 +134,7 @@ impl Message {
 
 impl Encodable for Message {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         self.magic.encode(w)?;
         self.payload.encode(w)?;
@@ -138,7 +144,7 @@ impl Encodable for Message {
 
 impl Decodable for Message {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Message, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Message, Error> {
 
         let magic = Magic::decode(r)?;
         let payload = Payload::decode(r)?;
@@ -152,20 +158,24 @@ impl Decodable for Message {
 
 impl Decodable for Vec<Message> {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<Message>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<Message>, Error> {
 
         trace!("decode");
-        let len = (*r.get_ref()).len();
+        let mut data = vec![];
+        r.read_to_end(&mut data).map_err(|e| Error::MessageEmpty.caused_by(e))?;
+        let len = data.len();
         if len == 0{
             return Err(Error::MessageEmpty);
         }
 
+        let r = &mut Cursor::new(&data);
+
         let mut result : Vec<Message> = Vec::new();
         loop {
             if r.position() as usize == len {
                 break;
             }
-            let decode = Message::decode(r)?;
+            let decode = Message::decode(r).map_err(|e| e.within(Frame::item("message", result.len())).at(r.position()))?;
             result.push(decode);
         }
 
@@ -182,7 +192,7 @@ impl Decodable for Vec<Message> {
 
 impl Encodable for Vec<Message> {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
         for message in self {
             message.encode(w)?;
@@ -196,6 +206,7 @@ impl Encodable for Vec<Message> {
 pub enum Payload {
     Version(version::Version),
     GetHeaders(getheaders::GetHeaders),
+    Headers(headers::Headers),
     GetAddr(getaddr::GetAddr),
     VerAck(verack::VerAck),
     Alert(alert::Alert),
@@ -203,6 +214,11 @@ pub enum Payload {
     Ping(ping::Ping),
     Pong(pong::Pong),
     Inv(inv::Inv),
+    GetData(getdata::GetData),
+    NotFound(notfound::NotFound),
+    GetBlocks(getblocks::GetBlocks),
+    Block(Block),
+    Tx(Transaction),
 }
 
 impl Payload {
@@ -216,6 +232,7 @@ impl Payload {
         match self {
             Payload::Version(_) => Command::Version,
             Payload::GetHeaders(_) => Command::GetHeaders,
+            Payload::Headers(_) => Command::Headers,
             Payload::GetAddr(_) => Command::GetAddr,
             Payload::VerAck(_) => Command::VerAck,
             Payload::Alert(_) => Command::Alert,
@@ -223,31 +240,36 @@ impl Payload {
             Payload::Ping(_) => Command::Ping,
             Payload::Pong(_) => Command::Pong,
             Payload::Inv(_) => Command::Inv,
+            Payload::GetData(_) => Command::GetData,
+            Payload::NotFound(_) => Command::NotFound,
+            Payload::GetBlocks(_) => Command::GetBlocks,
+            Payload::Block(_) => Command::Block,
+            Payload::Tx(_) => Command::Tx,
         }
     }
 
 }
 impl Decodable for Payload {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Payload, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Payload, Error> {
 
         trace!("decode");
-        let commandstring = CommandString::decode(r).map_err(|_| Error::PayloadCommandString)?;
+        let commandstring = CommandString::decode(r).map_err(|e| Error::PayloadCommandString.caused_by(e))?;
         debug!("decode [commandstring : {:?}]", commandstring);
-        let payload_len = u32::decode(r).map_err(|_| Error::PayloadLen)?;
-        let reader_len = r.get_ref().len();
+        let payload_len = u32::decode(r).map_err(|e| Error::PayloadLen.caused_by(e))?;
         debug!("decode [payload_len : {:?}]", payload_len);
-        debug!("decode [reraderlen : {}]", reader_len);
-
-        if reader_len < payload_len as usize {
-            return Err(Error::PayloadTooSmall);
+        if u64::from(payload_len) > MAX_SIZE {
+            return Err(Error::PayloadTooLarge);
         }
 
-        let checksum = <[u8; 4]>::decode(r).map_err(|_| Error::PayloadChecksum)?;
+        let checksum = <[u8; 4]>::decode(r).map_err(|e| Error::PayloadChecksum.caused_by(e))?;
 
-        let mut buffer : Vec<u8> = vec![0u8; payload_len as usize];
-        let slice = buffer.as_mut_slice();
-        r.read_exact(slice).map_err(|_| Error::PayloadData)?;
+        // the buffer grows with the bytes read, not with the announced length
+        let mut buffer : Vec<u8> = Vec::new();
+        r.take(u64::from(payload_len)).read_to_end(&mut buffer).map_err(|e| Error::PayloadData.caused_by(e))?;
+        if buffer.len() < payload_len as usize {
+            return Err(Error::PayloadTooSmall);
+        }
 
         let checksum2 : [u8; 4] = Message::checksum(&buffer).map_err(|_| Error::PayloadChecksumData)?;
         if checksum2 != checksum {
@@ -269,6 +291,10 @@ impl Decodable for Payload {
                 let message = getheaders::GetHeaders::decode(&mut c)?;
                 Payload::GetHeaders(message)
             },
+            Command::Headers => {
+                let message = headers::Headers::decode(&mut c)?;
+                Payload::Headers(message)
+            },
             Command::VerAck => {
                 let message = verack::VerAck::decode(&mut c)?;
                 Payload::VerAck(message)
@@ -293,6 +319,26 @@ impl Decodable for Payload {
                 let message = inv::Inv::decode(&mut c)?;
                 Payload::Inv(message)
             },
+            Command::GetData => {
+                let message = getdata::GetData::decode(&mut c)?;
+                Payload::GetData(message)
+            },
+            Command::NotFound => {
+                let message = notfound::NotFound::decode(&mut c)?;
+                Payload::NotFound(message)
+            },
+            Command::GetBlocks => {
+                let message = getblocks::GetBlocks::decode(&mut c)?;
+                Payload::GetBlocks(message)
+            },
+            Command::Block => {
+                let message = Block::decode(&mut c)?;
+                Payload::Block(message)
+            },
+            Command::Tx => {
+                let message = Transaction::decode(&mut c)?;
+                Payload::Tx(message)
+            },
         };
         Ok(payload)
     }
@@ -300,7 +346,7 @@ impl Decodable for Payload {
 
 impl Encodable for Payload {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         
         self.to_commandstring().encode(w)?;
         
@@ -308,6 +354,7 @@ impl Encodable for Payload {
         match self {
             Payload::Version(ref dat) => dat.encode(&mut buffer),
             Payload::GetHeaders(ref dat) => dat.encode(&mut buffer),
+            Payload::Headers(ref dat) => dat.encode(&mut buffer),
             Payload::GetAddr(ref dat) => dat.encode(&mut buffer),
             Payload::VerAck(ref dat) => dat.encode(&mut buffer),
             Payload::Alert(ref dat) => dat.encode(&mut buffer),
@@ -315,14 +362,19 @@ impl Encodable for Payload {
             Payload::Ping(ref dat) => dat.encode(&mut buffer),
             Payload::Pong(ref dat) => dat.encode(&mut buffer),
             Payload::Inv(ref dat) => dat.encode(&mut buffer),
+            Payload::GetData(ref dat) => dat.encode(&mut buffer),
+            Payload::NotFound(ref dat) => dat.encode(&mut buffer),
+            Payload::GetBlocks(ref dat) => dat.encode(&mut buffer),
+            Payload::Block(ref dat) => dat.encode(&mut buffer),
+            Payload::Tx(ref dat) => dat.encode(&mut buffer),
         }?;
         let payload_len = buffer.len() as u32;
         
-        payload_len.encode(w).map_err(|_| Error::PayloadLen)?;
+        payload_len.encode(w).map_err(|e| Error::PayloadLen.caused_by(e))?;
 
         let checksum = Message::checksum(&buffer).map_err(|_| Error::PayloadChecksum)?;
-        checksum.encode(w).map_err(|_| Error::PayloadChecksum)?;
-        buffer.encode(w).map_err(|_| Error::PayloadData)?;
+        checksum.encode(w).map_err(|e| Error::PayloadChecksum.caused_by(e))?;
+        buffer.encode(w).map_err(|e| Error::PayloadData.caused_by(e))?;
 
         Ok(())
     }
@@ -337,11 +389,15 @@ mod test {
     use crate::network::message::Error;
     use crate::network::message::Payload;
     use crate::encode::encode::{Encodable, Decodable};
+    use crate::encode::encode::test::fuzz;
     
     use crate::network::getaddr::GetAddr;
+    use crate::chain::chainparams::ChainParams;
 
     use crate::utils::hexdump;
 
+    use std::io::Cursor;
+
 
     #[test]
     fn when_encode_getaddr_message_then_same() {
@@ -364,4 +420,56 @@ mod test {
         assert!(encoded.is_ok());
         assert_eq!(original, result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn when_decode_block_message_then_genesis_block() {
+
+        let genesis = ChainParams::mainnet().genesis_block();
+        let mut original : Vec<u8> = hex::decode("f9beb4d9626c6f636b000000000000001d010000f71a2403").unwrap();
+        genesis.encode(&mut original).unwrap();
+
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+        match message.payload {
+            Payload::Block(ref block) => {
+                assert_eq!(block.hash(), genesis.hash());
+                assert_eq!(block.transactions.len(), 1);
+            },
+            _ => panic!("should be block"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+
+    #[test]
+    fn when_decode_tx_message_then_genesis_coinbase() {
+
+        let genesis = ChainParams::mainnet().genesis_block();
+        let coinbase = genesis.transactions.get(0).unwrap();
+        let mut original : Vec<u8> = hex::decode("f9beb4d9747800000000000000000000cc0000003ba3edfd").unwrap();
+        coinbase.encode(&mut original).unwrap();
+
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+        match message.payload {
+            Payload::Tx(ref tx) => assert_eq!(tx.txid(), coinbase.txid()),
+            _ => panic!("should be tx"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+
+    #[test]
+    fn when_decode_payload_over_32mb_then_error() {
+        // a ping of 0x02000001 bytes
+        let mut prefix = b"ping\0\0\0\0\0\0\0\0".to_vec();
+        prefix.extend_from_slice(&[0x01, 0x00, 0x00, 0x02]);
+        fuzz::<Payload>(&prefix, Error::PayloadTooLarge);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod getaddr;
 pub mod getheaders;
+pub mod getblocks;
+pub mod headers;
 pub mod message;
 pub mod version;
 pub mod verack;
@@ -10,3 +12,6 @@ pub mod addr;
 pub mod ping;
 pub mod pong;
 pub mod inv;
+pub mod getdata;
+pub mod notfound;
+pub mod codec;
@@ -4,9 +4,11 @@ use crate::encode::error::Error;
 use crate::encode::varint::VarInt;
 
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
+/// Maximum addresses of an addr message, MAX_ADDR_TO_SEND of Core
+pub const MAX_ADDR_TO_SEND : u64 = 1000;
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#Network_address
 /// 
@@ -63,21 +65,21 @@ pub struct TimedNetworkAddr {
 
 impl Encodable for NetworkAddr {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
-        self.services.encode(w).map_err(|_| Error::NetworkAddrServices)?;
-        self.ip.encode(w).map_err(|_| Error::NetworkAddrIp)?;
-        self.port.encode_network_byte_order(w).map_err(|_| Error::NetworkAddrPort)?;
+        self.services.encode(w).map_err(|e| Error::NetworkAddrServices.caused_by(e))?;
+        self.ip.encode(w).map_err(|e| Error::NetworkAddrIp.caused_by(e))?;
+        self.port.encode_network_byte_order(w).map_err(|e| Error::NetworkAddrPort.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Encodable for TimedNetworkAddr {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        self.time.encode(w).map_err(|_| Error::TimedNetworkAddrTime)?;
+        self.time.encode(w).map_err(|e| Error::TimedNetworkAddrTime.caused_by(e))?;
         self.addr.encode(w)?;
         Ok(())
     }
@@ -86,11 +88,11 @@ impl Encodable for TimedNetworkAddr {
 
 impl Encodable for Vec<TimedNetworkAddr> {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
         let varint = VarInt::new(self.len() as u64);
-        varint.encode(w).map_err(|_| Error::TimedNetworkCount)?;
+        varint.encode(w).map_err(|e| Error::TimedNetworkCount.caused_by(e))?;
         for addr in self {
             addr.encode(w)?;
         }
@@ -101,11 +103,14 @@ impl Encodable for Vec<TimedNetworkAddr> {
 
 impl Decodable for Vec<TimedNetworkAddr> {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<TimedNetworkAddr>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<TimedNetworkAddr>, Error> {
 
         trace!("decode");
         let mut result: Vec<TimedNetworkAddr> = Vec::new();
-        let varint = VarInt::decode(r).map_err(|_| Error::AddrCount)?;
+        let varint = VarInt::decode(r).map_err(|e| Error::AddrCount.caused_by(e))?;
+        if varint.0 > MAX_ADDR_TO_SEND {
+            return Err(Error::AddrTooMany);
+        }
         for _ in 0..varint.0 {
             let addr = TimedNetworkAddr::decode(r)?;
             result.push(addr);
@@ -117,12 +122,12 @@ impl Decodable for Vec<TimedNetworkAddr> {
 
 impl Decodable for NetworkAddr {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<NetworkAddr, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<NetworkAddr, Error> {
 
         trace!("decode");
-        let services = Service::decode(r).map_err(|_| Error::NetworkAddrServices)?;
-        let ip = IpAddr::decode(r).map_err(|_| Error::NetworkAddrIp)?;
-        let port = u16::decode_network_byte_order(r).map_err(|_| Error::NetworkAddrPort)?;
+        let services = Service::decode(r).map_err(|e| Error::NetworkAddrServices.caused_by(e))?;
+        let ip = IpAddr::decode(r).map_err(|e| Error::NetworkAddrIp.caused_by(e))?;
+        let port = u16::decode_network_byte_order(r).map_err(|e| Error::NetworkAddrPort.caused_by(e))?;
 
         let result = NetworkAddr {
             services: services,
@@ -137,10 +142,10 @@ impl Decodable for NetworkAddr {
 
 impl Decodable for TimedNetworkAddr {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<TimedNetworkAddr, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<TimedNetworkAddr, Error> {
 
         trace!("decode");
-        let time = u32::decode(r).map_err(|_| Error::TimedNetworkAddrTime)?;
+        let time = u32::decode(r).map_err(|e| Error::TimedNetworkAddrTime.caused_by(e))?;
         let addr = NetworkAddr::decode(r)?;
 
         let result = TimedNetworkAddr {
@@ -153,30 +158,30 @@ impl Decodable for TimedNetworkAddr {
 }
 
 impl Encodable for IpAddr {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
         let ipv6 :Ipv6Addr = match *self {
             IpAddr::V4(ip4) => ip4.to_ipv6_mapped(),
             IpAddr::V6(ip6) => ip6,
         };
-        w.write_all(&ipv6.octets()).map_err(|_| Error::IpAddr)?;
+        w.write_all(&ipv6.octets()).map_err(|e| Error::IpAddr.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for IpAddr {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<IpAddr, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<IpAddr, Error> {
 
         trace!("decode");
-        let b1 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB1)?;
-        let b2 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB2)?;
-        let b3 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB3)?;
-        let b4 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB4)?;
-        let b5 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB5)?;
-        let b6 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB6)?;
-        let b7 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB7)?;
-        let b8 = u16::decode_network_byte_order(r).map_err(|_| Error::IpAddrB8)?;
+        let b1 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB1.caused_by(e))?;
+        let b2 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB2.caused_by(e))?;
+        let b3 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB3.caused_by(e))?;
+        let b4 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB4.caused_by(e))?;
+        let b5 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB5.caused_by(e))?;
+        let b6 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB6.caused_by(e))?;
+        let b7 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB7.caused_by(e))?;
+        let b8 = u16::decode_network_byte_order(r).map_err(|e| Error::IpAddrB8.caused_by(e))?;
 
         let ipv6 = Ipv6Addr::new(b1, b2, b3, b4, b5, b6, b7, b8);
         let ipaddr = match ipv6.to_ipv4() {
@@ -196,6 +201,8 @@ mod test {
     use crate::network::message::Payload;
     use crate::encode::encode::{Encodable, Decodable};
     use crate::network::networkaddr::NetworkAddr;
+    use crate::network::networkaddr::TimedNetworkAddr;
+    use crate::encode::encode::test::fuzz;
     use crate::network::version::Service;
     use crate::utils::hexdump;
 
@@ -340,4 +347,11 @@ mod test {
         assert_eq!(expected, result.unwrap());
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn when_decode_over_1000_addrs_then_error() {
+        // 1001 then 2^64 - 1 addresses
+        fuzz::<Vec<TimedNetworkAddr>>(&[0xfd, 0xe9, 0x03], Error::AddrTooMany);
+        fuzz::<Vec<TimedNetworkAddr>>(&[0xff; 9], Error::AddrTooMany);
+    }
+}
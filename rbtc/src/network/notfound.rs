@@ -0,0 +1,92 @@
+use crate::encode::error::Error;
+use crate::encode::encode::{Encodable, Decodable};
+use crate::network::inv::InvVect;
+
+use std::io::{Read, Write};
+
+/// https://en.bitcoin.it/wiki/Protocol_documentation#notfound
+///
+/// # notfound
+///
+/// notfound is a response to a getdata, sent if any requested data items could not be relayed,
+/// for example, because the requested transaction was not in the memory pool or relay set.
+///
+/// Payload (maximum 50,000 entries, which is just over 1.8 megabytes):
+///
+/// ```
+/// +------+-------------+------------+-----------------------------+
+/// | Size | Description | Data type  | Comments                    |
+/// +------+-------------+------------+-----------------------------+
+/// |   1+ | count       | var_int    | Number of inventory entries |
+/// | 36x? | inventory   | inv_vect[] | Inventory vectors           |
+/// +------+-------------+------------+-----------------------------+
+/// ```
+///
+#[derive(Debug, PartialEq)]
+pub struct NotFound {
+    pub inventory: Vec<InvVect>,
+}
+
+impl Encodable for NotFound {
+
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+
+        trace!("encode");
+        self.inventory.encode(w)
+    }
+}
+
+impl Decodable for NotFound {
+
+    fn decode<R: Read>(r: &mut R) -> Result<NotFound, Error> {
+
+        trace!("decode");
+        let inventory = <Vec<InvVect>>::decode(r)?;
+
+        let result = NotFound {
+            inventory: inventory
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::encode::{Encodable, Decodable};
+    use crate::network::notfound::NotFound;
+    use crate::network::inv::{InvVect, InvType};
+    use crate::network::message::{Message, Payload};
+    use crate::utils::hexdump;
+    use crate::utils::sha256::Sha256;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn when_decode_notfound_message_then_same() {
+
+        let dump = "
+00000000   F9 BE B4 D9 6E 6F 74 66  6F 75 6E 64 00 00 00 00   main.notfound...
+00000010   25 00 00 00 CB A4 3B F7  01 05 00 00 00 3B A3 ED   len.chks.c.type.
+00000020   FD 7A 7B 12 B2 7A C7 2C  3E 67 76 8F 61 7F C8 1B   hash.hash.hash.h
+00000030   C3 88 8A 51 32 3A 9F B8  AA 4B 1E 5E 4A            ash.hash.hash.ha
+";
+
+        let original : Vec<u8> = hexdump::decode(dump);
+        let mut c = Cursor::new(&original);
+        let message = Message::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, original.len());
+
+        let expected = NotFound {
+            inventory: vec![InvVect::new(InvType::WTx, "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b".parse::<Sha256>().unwrap())]
+        };
+        match message.payload {
+            Payload::NotFound(ref notfound) => assert_eq!(notfound, &expected),
+            _ => panic!("should be notfound"),
+        }
+
+        let mut encoded : Vec<u8> = Vec::new();
+        message.encode(&mut encoded).unwrap();
+        assert_eq!(original, encoded);
+    }
+}
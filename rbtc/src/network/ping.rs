@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#ping
@@ -27,18 +27,18 @@ pub struct Ping {
 
 impl Encodable for Ping {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        self.nonce.encode(w).map_err(|_| Error::PingNonce)?;
+        self.nonce.encode(w).map_err(|e| Error::PingNonce.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Ping {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Ping, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Ping, Error> {
         trace!("decode");
-        let nonce = u64::decode(r).map_err(|_| Error::PingNonce)?;
+        let nonce = u64::decode(r).map_err(|e| Error::PingNonce.caused_by(e))?;
         let result = Ping {
             nonce: nonce
         };
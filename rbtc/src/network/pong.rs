@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#Pong
@@ -27,18 +27,18 @@ pub struct Pong {
 
 impl Encodable for Pong {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        self.nonce.encode(w).map_err(|_| Error::PongNonce)?;
+        self.nonce.encode(w).map_err(|e| Error::PongNonce.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Pong {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Pong, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Pong, Error> {
         trace!("decode");
-        let nonce = u64::decode(r).map_err(|_| Error::PongNonce)?;
+        let nonce = u64::decode(r).map_err(|e| Error::PongNonce.caused_by(e))?;
         let result = Pong {
             nonce: nonce
         };
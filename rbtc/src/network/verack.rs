@@ -1,7 +1,7 @@
 use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 
-use std::io::{Read, Write, Cursor};
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
 /// https://en.bitcoin.it/wiki/Protocol_documentation#verack
@@ -32,7 +32,7 @@ pub struct VerAck {
 
 impl Encodable for VerAck {
 
-    fn encode(&self, _: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, _: &mut W) -> Result<(), Error> {
         trace!("encode");
         Ok(())
     }
@@ -40,7 +40,7 @@ impl Encodable for VerAck {
 
 impl Decodable for VerAck {
 
-    fn decode(_: &mut Cursor<&Vec<u8>>) -> Result<VerAck, Error> {
+    fn decode<R: Read>(_: &mut R) -> Result<VerAck, Error> {
         trace!("decode");
         Ok(VerAck {})
     }
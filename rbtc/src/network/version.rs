@@ -3,7 +3,7 @@ use crate::encode::encode::{Encodable, Decodable};
 use crate::network::networkaddr::NetworkAddr;
 use crate::encode::error::Error;
 
-use std::io::{Write, Read, Cursor};
+use std::io::{Write, Read};
 use byteorder::{LittleEndian, WriteBytesExt, ReadBytesExt};
 
 /// The `version` message
@@ -90,17 +90,17 @@ bitflags! {
 // }
 
 impl Encodable for Service {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         trace!("encode");
-        self.bits().encode(w).map_err(|_| Error::Service)?;
+        self.bits().encode(w).map_err(|e| Error::Service.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Service {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Service, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Service, Error> {
         trace!("decode");
-        let value = u64::decode(r).map_err(|_| Error::Service)?;
+        let value = u64::decode(r).map_err(|e| Error::Service.caused_by(e))?;
         let flag = Service::from_bits(value);
         match flag {
             Some(strict_result) => Ok(strict_result),
@@ -117,25 +117,25 @@ impl Decodable for Service {
 
 impl Encodable for Version {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
 
         trace!("encode");
-        self.version.encode(w).map_err(|_| Error::VersionVersion)?;
-        self.services.encode(w).map_err(|_| Error::VersionServices)?;
-        self.timestamp.encode(w).map_err(|_| Error::VersionTimestamp)?;
-        self.receiver.encode(w).map_err(|_| Error::VersionReceiver)?;
-        self.sender.encode(w).map_err(|_| Error::VersionSender)?;
-        self.nonce.encode(w).map_err(|_| Error::VersionNonce)?;
+        self.version.encode(w).map_err(|e| Error::VersionVersion.caused_by(e))?;
+        self.services.encode(w).map_err(|e| Error::VersionServices.caused_by(e))?;
+        self.timestamp.encode(w).map_err(|e| Error::VersionTimestamp.caused_by(e))?;
+        self.receiver.encode(w).map_err(|e| Error::VersionReceiver.caused_by(e))?;
+        self.sender.encode(w).map_err(|e| Error::VersionSender.caused_by(e))?;
+        self.nonce.encode(w).map_err(|e| Error::VersionNonce.caused_by(e))?;
 
         let user_agent_bytes = self.user_agent.as_bytes();
         let user_agent_len = user_agent_bytes.len() as u8;
 
-        user_agent_len.encode(w).map_err(|_| Error::VersionUserAgentLen)?;
+        user_agent_len.encode(w).map_err(|e| Error::VersionUserAgentLen.caused_by(e))?;
 
-        w.write_all(user_agent_bytes).map_err(|_| Error::VersionUserAgent)?;
+        w.write_all(user_agent_bytes).map_err(|e| Error::VersionUserAgent.caused_by(e))?;
 
-        self.start_height.encode(w).map_err(|_| Error::VersionStartHeight)?;
-        self.relay.encode(w).map_err(|_| Error::VersionRelay)?;
+        self.start_height.encode(w).map_err(|e| Error::VersionStartHeight.caused_by(e))?;
+        self.relay.encode(w).map_err(|e| Error::VersionRelay.caused_by(e))?;
 
         Ok(())
     }
@@ -143,24 +143,24 @@ impl Encodable for Version {
 
 impl Decodable for Version {
 
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Version, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Version, Error> {
 
         trace!("decode");
-        let version = i32::decode(r).map_err(|_| Error::VersionVersion)?;
-        let services = Service::decode(r).map_err(|_| Error::VersionServices)?;
-        let timestamp = i64::decode(r).map_err(|_| Error::VersionTimestamp)?;
-        let receiver = NetworkAddr::decode(r).map_err(|_| Error::VersionReceiver)?;
-        let sender = NetworkAddr::decode(r).map_err(|_| Error::VersionSender)?;
-        let nonce = u64::decode(r).map_err(|_| Error::VersionNonce)?;
-
-        let user_agent_len = u8::decode(r).map_err(|_| Error::VersionUserAgentLen)?;
+        let version = i32::decode(r).map_err(|e| Error::VersionVersion.caused_by(e))?;
+        let services = Service::decode(r).map_err(|e| Error::VersionServices.caused_by(e))?;
+        let timestamp = i64::decode(r).map_err(|e| Error::VersionTimestamp.caused_by(e))?;
+        let receiver = NetworkAddr::decode(r).map_err(|e| Error::VersionReceiver.caused_by(e))?;
+        let sender = NetworkAddr::decode(r).map_err(|e| Error::VersionSender.caused_by(e))?;
+        let nonce = u64::decode(r).map_err(|e| Error::VersionNonce.caused_by(e))?;
+
+        let user_agent_len = u8::decode(r).map_err(|e| Error::VersionUserAgentLen.caused_by(e))?;
         let mut user_agent_vec = vec![0u8; user_agent_len as usize];
         let user_agent_bytes = user_agent_vec.as_mut_slice();
 
-        r.read_exact(user_agent_bytes).map_err(|_| Error::VersionUserAgent)?;
-        let user_agent = String::from_utf8(user_agent_bytes.to_owned()).map_err(|_| Error::VersionUserAgentDecode)?;
-        let start_height = i32::decode(r).map_err(|_| Error::VersionStartHeight)?;
-        let relay = bool::decode(r).map_err(|_| Error::VersionRelay)?;
+        r.read_exact(user_agent_bytes).map_err(|e| Error::VersionUserAgent.caused_by(e))?;
+        let user_agent = String::from_utf8(user_agent_bytes.to_owned()).map_err(|e| Error::VersionUserAgentDecode.caused_by(e))?;
+        let start_height = i32::decode(r).map_err(|e| Error::VersionStartHeight.caused_by(e))?;
+        let relay = bool::decode(r).map_err(|e| Error::VersionRelay.caused_by(e))?;
 
         let result = Version {
             version: version,
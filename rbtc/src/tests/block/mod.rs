@@ -4,4 +4,14 @@ mod when_null_block_then_parse_ok;
 mod when_invalid_data_then_block_parse_fail_with_invalidlength;
 mod when_some_block_then_parse_ok;
 mod when_some_block_cutoff_then_parse_fail;
-mod when_segwit_block_then_parse_ok;
\ No newline at end of file
+mod when_segwit_block_then_parse_ok;
+mod when_genesis_block_then_encode_same;
+mod when_some_block_then_encode_same;
+mod when_segwit_block_then_encode_same;
+mod when_multi_input_segwit_transaction_then_parse_ok;
+mod when_segwit_flag_without_witness_then_parse_fail;
+mod when_segwit_block_mutated_then_validate_merkle_fail;
+mod when_segwit_block_then_classify_outputs;
+mod when_genesis_block_then_weight_and_sigops;
+mod when_segwit_transaction_then_weight_and_sigops;
+mod when_block_over_limits_then_check_limits_fail;
@@ -0,0 +1,54 @@
+use crate::encode::error::Error;
+use crate::encode::encode::Encodable;
+use crate::encode::varint::VarInt;
+use crate::block::block::Block;
+use crate::block::outpoint::OutPoint;
+use crate::block::script::Script;
+use crate::block::transaction::Transaction;
+use crate::block::txin::TxIn;
+use crate::block::txout::TxOut;
+use crate::chain::chainparams::ChainParams;
+
+/// The genesis header followed by a coinbase paying to `script_pubkey`.
+fn block(script_pubkey: Script) -> Block {
+
+    let coinbase = Transaction {
+        version: 1,
+        flag: None,
+        inputs: vec![TxIn {
+            previous: OutPoint { transaction_hash: [0; 32], index: 0xffff_ffff },
+            signature: Script::new(vec![0x51, 0x51]),
+            sequence: 0xffff_ffff,
+            witness: vec![],
+        }],
+        outputs: vec![TxOut { amount: 0, script_pubkey: script_pubkey }],
+        locktime: 0,
+    };
+
+    let mut data : Vec<u8> = Vec::new();
+    ChainParams::mainnet().genesis.encode(&mut data).unwrap();
+    VarInt::new(1).encode(&mut data).unwrap();
+    coinbase.encode(&mut data).unwrap();
+    Block::parse(&data).unwrap()
+}
+
+#[test]
+fn test() {
+
+    // 20 000 OP_CHECKSIG are 80 000 sigops cost, one more is too many
+    let block_at_limit = block(Script::new(vec![0xac; 20_000]));
+    assert_eq!(block_at_limit.legacy_sigop_cost(), 80_000);
+    assert_eq!(block_at_limit.check_limits(), Ok(()));
+    let over_sigops = block(Script::new(vec![0xac; 20_001]));
+    assert_eq!(over_sigops.check_limits(), Err(Error::BlockSigOps));
+
+    // 1001 OP_CHECKMULTISIG are counted 20 each, 20 020 sigops or 80 080 cost, over the 80 000
+    let multisig = block(Script::new(vec![0xae; 1_001]));
+    assert_eq!(multisig.legacy_sigop_cost(), 80_080);
+    assert_eq!(multisig.check_limits(), Err(Error::BlockSigOps));
+
+    // 1 000 000 bytes of OP_NOP, each weighing 4
+    let over_weight = block(Script::new(vec![0x61; 1_000_000]));
+    assert!(over_weight.weight() > 4_000_000);
+    assert_eq!(over_weight.check_limits(), Err(Error::BlockWeight));
+}
@@ -5,6 +5,9 @@ use crate::block::txout::TxOut;
 
 use crate::block::block::Block;
 use crate::utils::hexdump;
+use crate::utils::uint256::Uint256;
+
+use std::str::FromStr;
 
 #[test]
 fn test() {
@@ -39,17 +42,17 @@ fn test() {
 
     let b = result.ok().unwrap();
 
-    assert_eq!(b.version, 0x00000001, "b.version");
-    assert_eq!(b.previous, [0; 32], "b.previous");
-    assert_eq!(b.merkleroot, [
+    assert_eq!(b.header.version, 0x00000001, "b.header.version");
+    assert_eq!(b.header.previous, [0; 32], "b.header.previous");
+    assert_eq!(b.header.merkleroot, [
         0x3B, 0xA3, 0xED, 0xFD, 0x7A, 0x7B, 0x12, 0xB2, 
         0x7A, 0xC7, 0x2C, 0x3E, 0x67, 0x76, 0x8F, 0x61, 
         0x7F, 0xC8, 0x1B, 0xC3, 0x88, 0x8A, 0x51, 0x32, 
         0x3A, 0x9F, 0xB8, 0xAA, 0x4B, 0x1E, 0x5E, 0x4A
-    ], "b.merkleroot");
-    assert_eq!(b.time, 1231006505, "b.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
-    assert_eq!(b.bits, 0x1D00FFFF, "b.bits");
-    assert_eq!(b.nonce, 0x7C2BAC1D, "b.nonce");
+    ], "b.header.merkleroot");
+    assert_eq!(b.header.time, 1231006505, "b.header.time"); // Unix Epoch	1231006505 - Time (UTC)   Sat Jan 03 18:15:05 2009 UTC
+    assert_eq!(b.header.bits, 0x1D00FFFF, "b.header.bits");
+    assert_eq!(b.header.nonce, 0x7C2BAC1D, "b.header.nonce");
     assert_eq!(b.transactions.len(), 1, "b.transactions.len");
 
     let t : &Transaction = b.transactions.get(0).unwrap();
@@ -98,7 +101,13 @@ fn test() {
         0x1D, 0x5F, 0xAC
     ], "o.script_pubkey.content");
 
-    assert!(t.witness.is_none(), "t.witness");
+    assert!(!t.has_witness(), "t.witness");
 
+    assert_eq!(t.txid().to_string(), "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b", "t.txid");
+    assert_eq!(t.wtxid(), t.txid(), "t.wtxid");
+    assert_eq!(b.hash().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f", "b.hash");
+    assert_eq!(b.validate_merkle(), Ok(()), "b.validate_merkle");
 
-}
\ No newline at end of file
+    let limit = Uint256::from_str("00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap();
+    assert_eq!(b.check_pow(&limit), Ok(()), "b.check_pow");
+}
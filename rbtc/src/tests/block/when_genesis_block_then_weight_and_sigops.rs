@@ -0,0 +1,28 @@
+use crate::block::block::{MAX_BLOCK_WEIGHT, MAX_BLOCK_SIGOPS_COST};
+use crate::chain::chainparams::ChainParams;
+
+#[test]
+fn test() {
+
+    let block = ChainParams::mainnet().genesis_block();
+
+    // no witness, every byte weighs 4
+    assert_eq!(block.stripped_size(), 285);
+    assert_eq!(block.total_size(), 285);
+    assert_eq!(block.weight(), 1140);
+
+    let coinbase = block.transactions.get(0).unwrap();
+    assert!(coinbase.is_coinbase());
+    assert_eq!(coinbase.stripped_size(), 204);
+    assert_eq!(coinbase.weight(), 816);
+    assert_eq!(coinbase.vsize(), 204);
+
+    // the pay to pubkey output
+    assert_eq!(coinbase.legacy_sigop_count(), 1);
+    assert_eq!(coinbase.sigop_cost(&[]), Ok(4));
+    assert_eq!(block.legacy_sigop_cost(), 4);
+
+    assert!(block.weight() <= MAX_BLOCK_WEIGHT);
+    assert!(block.legacy_sigop_cost() <= MAX_BLOCK_SIGOPS_COST);
+    assert_eq!(block.check_limits(), Ok(()));
+}
@@ -0,0 +1,144 @@
+use crate::encode::encode::{Encodable, Decodable};
+use crate::block::transaction::Transaction;
+use crate::block::txin::TxIn;
+use crate::utils::hexdump;
+
+use std::io::Cursor;
+
+// BIP143 example: P2WSH with OP_CODESEPARATOR and out-of-range SIGHASH_SINGLE.
+// The first input spends a legacy P2PK output and has an empty witness,
+// the second one spends a P2WSH output with a 3 items witness.
+#[test]
+fn when_first_input_without_witness_then_parse_ok() {
+
+    let dump = "
+00000000   01 00 00 00 00 01 02 fe  3d c9 20 80 94 f3 ff d1   ................
+00000010   26 45 47 7b 3d c5 6f 60  ec 4f a8 e6 f5 d6 7c 56   ................
+00000020   5d 1c 6b 92 16 b3 6e 00  00 00 00 48 47 30 44 02   ................
+00000030   20 0a f4 e4 7c 9b 96 29  db ec c2 1f 73 af 98 9b   ................
+00000040   da a9 11 f7 e6 f6 c2 e9  39 45 88 a3 aa 68 f8 1e   ................
+00000050   99 02 20 4f 3f cf 6a de  7e 5a bb 12 95 b6 77 4c   ................
+00000060   8e 0a bd 94 ae 62 21 73  67 09 6b c0 2e e5 e4 35   ................
+00000070   b6 7d a2 01 ff ff ff ff  08 15 cf 02 0f 01 3e d6   ................
+00000080   cf 91 d2 9f 42 02 e8 a5  87 26 b1 ac 6c 79 da 47   ................
+00000090   c2 3d 1b ee 0a 69 25 f8  00 00 00 00 00 ff ff ff   ................
+000000a0   ff 01 00 f2 05 2a 01 00  00 00 19 76 a9 14 a3 07   ................
+000000b0   41 f8 14 5e 5a ca df 23  f7 51 86 41 67 f3 2e 09   ................
+000000c0   63 f7 88 ac 00 03 47 30  44 02 20 0d e6 6a cf 45   ................
+000000d0   27 78 9b fd a5 5f c5 45  9e 21 4f a6 08 3f 93 6b   ................
+000000e0   43 0a 76 2c 62 96 56 21  68 05 ac 02 20 39 6f 55   ................
+000000f0   06 92 cd 34 71 71 cb c1  ef 1f 51 e1 52 82 e8 37   ................
+00000100   bb 2b 30 86 0d c7 7c 8f  78 bc 85 01 e5 03 47 30   ................
+00000110   44 02 20 27 dc 95 ad 6b  74 0f e5 12 9e 7e 62 a7   ................
+00000120   5d d0 0f 29 1a 2a eb 12  00 b8 4b 09 d9 e3 78 94   ................
+00000130   06 b6 c0 02 20 1a 9e cd  31 5d d6 a0 e6 32 ab 20   ................
+00000140   bb b9 89 48 bc 0c 6f b2  04 f2 c2 86 96 3b b4 85   ................
+00000150   17 a7 05 8e 27 03 47 21  02 6d cc c7 49 ad c2 a9   ................
+00000160   d0 d8 94 97 ac 51 1f 76  0f 45 c4 7d c5 ed 9c f3   ................
+00000170   52 a5 8a c7 06 45 38 80  ae ad ab 21 02 55 a9 62   ................
+00000180   6a eb f5 e2 9c 0e 65 38  42 8b a0 d1 dc f6 ca 98   ................
+00000190   ff df 08 6a a8 ce d5 e0  d0 21 5e a4 65 ac 00 00   ................
+000001a0   00 00                                              ..              
+";
+
+    let hex: Vec<u8> = hexdump::decode(dump);
+    assert_eq!(hex.len(), 418);
+
+    let mut c = Cursor::new(&hex);
+    let result = Transaction::decode(&mut c);
+    assert!(result.is_ok());
+    assert_eq!(c.position() as usize, hex.len());
+
+    let t = result.unwrap();
+
+    assert_eq!(t.flag, Some(0x0100), "t.flag");
+    assert!(t.has_witness(), "t.witness");
+    assert_eq!(t.inputs.len(), 2, "t.inputs.len");
+    assert_eq!(t.outputs.len(), 1, "t.outputs.len");
+
+    let i1: &TxIn = t.inputs.get(0).unwrap();
+    assert_eq!(i1.signature.content.len(), 0x48, "i1.signature.content.len");
+    assert_eq!(i1.witness.len(), 0, "i1.witness.len");
+
+    let i2: &TxIn = t.inputs.get(1).unwrap();
+    assert_eq!(i2.signature.content.len(), 0, "i2.signature.content.len");
+    assert_eq!(i2.witness.len(), 3, "i2.witness.len");
+    assert_eq!(i2.witness.get(0).unwrap().data.len(), 0x47, "i2.witness[0].data.len");
+    assert_eq!(i2.witness.get(1).unwrap().data.len(), 0x47, "i2.witness[1].data.len");
+    assert_eq!(i2.witness.get(2).unwrap().data.len(), 0x47, "i2.witness[2].data.len");
+    assert_eq!(i2.witness.get(2).unwrap().data[0..2], [0x21, 0x02], "i2.witness[2].data");
+
+    assert_eq!(t.locktime, 0, "t.locktime");
+
+    let mut encoded: Vec<u8> = Vec::new();
+    let result = t.encode(&mut encoded);
+    assert!(result.is_ok());
+    assert_eq!(hex, encoded);
+}
+
+// BIP143 example: P2WSH with unexecuted OP_CODESEPARATOR and SINGLE|ANYONECANPAY.
+// Both inputs spend P2WSH outputs, each with its own 2 items witness.
+#[test]
+fn when_each_input_with_witness_then_parse_ok() {
+
+    let dump = "
+00000000   01 00 00 00 00 01 02 e9  b5 42 c5 17 68 08 10 7f   ................
+00000010   f1 df 90 6f 46 bb 1f 25  83 b1 61 12 b9 5e e5 38   ................
+00000020   06 65 ba 7f cf c0 01 00  00 00 00 00 ff ff ff ff   ................
+00000030   80 e6 88 31 51 63 92 fc  d1 00 d1 86 b3 c2 c7 b9   ................
+00000040   5c 80 b5 3c 77 e7 7c 35  ba 03 a6 6b 42 9a 2a 1b   ................
+00000050   00 00 00 00 00 ff ff ff  ff 02 80 96 98 00 00 00   ................
+00000060   00 00 19 76 a9 14 de 4b  23 16 26 ef 50 8c 9a 74   ................
+00000070   a8 51 7e 67 83 c0 54 6d  6b 28 88 ac 80 96 98 00   ................
+00000080   00 00 00 00 19 76 a9 14  66 48 a8 cd 45 31 e1 ec   ................
+00000090   47 f3 59 16 de 8e 25 92  37 29 4d 1e 88 ac 02 48   ................
+000000a0   30 45 02 21 00 f6 a1 0b  86 04 e6 dc 91 01 94 b7   ................
+000000b0   9c cf c9 3e 1b c0 ec 7c  03 45 3c aa a8 98 7f 7d   ................
+000000c0   6c 34 13 56 60 02 20 62  16 22 9e de 9b 4d 6e c2   ................
+000000d0   d3 25 be 24 5c 5b 50 8f  f0 33 9b f1 79 40 78 e2   ................
+000000e0   0b fe 0b ab c7 ff e6 83  27 00 63 ab 68 21 03 92   ................
+000000f0   97 2e 2e b6 17 b2 38 87  71 ab e2 72 35 fd 5a c4   ................
+00000100   4a f8 e6 16 93 26 15 50  44 7a 4c 3e 39 da 98 ac   ................
+00000110   02 47 30 44 02 20 03 25  21 80 2a 76 ad 7b f7 4d   ................
+00000120   0e 2c 21 8b 72 cf 0c bc  86 70 66 e2 e5 3d b9 05   ................
+00000130   ba 37 f1 30 39 7e 02 20  77 09 e2 18 8e d7 f0 8f   ................
+00000140   4c 95 2d 9d 13 98 6d a5  04 50 2b 8c 3b e5 96 17   ................
+00000150   e0 43 55 2f 50 6c 46 ff  83 27 51 63 ab 68 21 03   ................
+00000160   92 97 2e 2e b6 17 b2 38  87 71 ab e2 72 35 fd 5a   ................
+00000170   c4 4a f8 e6 16 93 26 15  50 44 7a 4c 3e 39 da 98   ................
+00000180   ac 00 00 00 00                                     .....           
+";
+
+    let hex: Vec<u8> = hexdump::decode(dump);
+    assert_eq!(hex.len(), 389);
+
+    let mut c = Cursor::new(&hex);
+    let result = Transaction::decode(&mut c);
+    assert!(result.is_ok());
+    assert_eq!(c.position() as usize, hex.len());
+
+    let t = result.unwrap();
+
+    assert_eq!(t.inputs.len(), 2, "t.inputs.len");
+    assert_eq!(t.outputs.len(), 2, "t.outputs.len");
+
+    let i1: &TxIn = t.inputs.get(0).unwrap();
+    assert_eq!(i1.witness.len(), 2, "i1.witness.len");
+    assert_eq!(i1.witness.get(0).unwrap().data.len(), 0x48, "i1.witness[0].data.len");
+    assert_eq!(i1.witness.get(1).unwrap().data.len(), 0x27, "i1.witness[1].data.len");
+
+    let i2: &TxIn = t.inputs.get(1).unwrap();
+    assert_eq!(i2.witness.len(), 2, "i2.witness.len");
+    assert_eq!(i2.witness.get(0).unwrap().data.len(), 0x47, "i2.witness[0].data.len");
+    assert_eq!(i2.witness.get(1).unwrap().data.len(), 0x27, "i2.witness[1].data.len");
+
+    let mut encoded: Vec<u8> = Vec::new();
+    let result = t.encode(&mut encoded);
+    assert!(result.is_ok());
+    assert_eq!(hex, encoded);
+
+    let mut stripped: Vec<u8> = Vec::new();
+    let result = t.encode_without_witness(&mut stripped);
+    assert!(result.is_ok());
+    assert_eq!(stripped.len(), hex.len() - 2 - (1 + 1 + 0x48 + 1 + 0x27) - (1 + 1 + 0x47 + 1 + 0x27));
+}
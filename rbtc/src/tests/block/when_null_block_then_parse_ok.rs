@@ -24,17 +24,17 @@ fn test() {
 
     let b = result.ok().unwrap();
 
-    assert_eq!(b.version, 0x00000000, "b.version");
-    assert_eq!(b.previous, [0; 32], "b.previous");
-    assert_eq!(b.merkleroot, [
+    assert_eq!(b.header.version, 0x00000000, "b.header.version");
+    assert_eq!(b.header.previous, [0; 32], "b.header.previous");
+    assert_eq!(b.header.merkleroot, [
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 
-    ], "b.merkleroot");
-    assert_eq!(b.time, 0000000000, "b.time");
-    assert_eq!(b.bits, 0x00000000, "b.bits");
-    assert_eq!(b.nonce, 0x00000000, "b.nonce");
+    ], "b.header.merkleroot");
+    assert_eq!(b.header.time, 0000000000, "b.header.time");
+    assert_eq!(b.header.bits, 0x00000000, "b.header.bits");
+    assert_eq!(b.header.nonce, 0x00000000, "b.header.nonce");
     assert_eq!(b.transactions.len(), 0, "b.transactions.len");
 
 }
\ No newline at end of file
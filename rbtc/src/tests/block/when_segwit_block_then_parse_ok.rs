@@ -8,6 +8,9 @@ use crate::block::txout::TxOut;
 use crate::block::block;
 
 use crate::utils::hexdump;
+use crate::utils::uint256::Uint256;
+
+use std::str::FromStr;
 
 #[test]
 fn test() {
@@ -294,28 +297,28 @@ fn test() {
 
     let b : Block = result.ok().unwrap();
 
-    assert_eq!(b.version, 0x20000000, "b.version");
+    assert_eq!(b.header.version, 0x20000000, "b.header.version");
     assert_eq!(
-        b.previous,
+        b.header.previous,
         [
             0x2a, 0xa2, 0xf2, 0xca, 0x79, 0x4c, 0xcb, 0xd4, 0x0c, 0x16, 0xe2, 0xf3, 0x33, 0x3f, 0x6b, 0x8b, 
             0x68, 0x3f, 0x9e, 0x71, 0x79, 0xb2, 0xc4, 0xd7, 0x49, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00
         ],
-        "b.previous"
+        "b.header.previous"
     );
 
     assert_eq!(
-        b.merkleroot,
+        b.header.merkleroot,
         [
             0x10, 0xbc, 0x26, 0xe7, 0x0a, 0x2f, 0x67, 0x2a, 0xd4, 0x20, 0xa6, 0x15, 0x3d, 0xd0, 0xc2, 0x8b, 
             0x40, 0xa6, 0x00, 0x2c, 0x55, 0x53, 0x1b, 0xfc, 0x99, 0xbf, 0x89, 0x94, 0xa8, 0xe8, 0xf6, 0x7e
         ],
-        "b.merkleroot"
+        "b.header.merkleroot"
     );
 
-    assert_eq!(b.time, 1472004949, "b.time");
-    assert_eq!(b.bits, 436655184, "b.bits");
-    assert_eq!(b.nonce, 1879759182, "b.nonce");
+    assert_eq!(b.header.time, 1472004949, "b.header.time");
+    assert_eq!(b.header.bits, 436655184, "b.header.bits");
+    assert_eq!(b.header.nonce, 1879759182, "b.header.nonce");
     assert_eq!(b.transactions.len(), 15, "b.transactions.len");
 
     let t1: &Transaction = b.transactions.get(0).unwrap();
@@ -367,9 +370,9 @@ fn test() {
         "t1o.script_pubkey.content"
     );
 
-    assert!(t1.witness.is_some(), "t1.witness");
-    let t1ws = t1.witness.as_ref().unwrap();
-    let t1w : &Witness = t1ws.get(0).unwrap();
+    assert!(t1.has_witness(), "t1.witness");
+    assert_eq!(t1i.witness.len(), 1, "t1i.witness.len");
+    let t1w : &Witness = t1i.witness.get(0).unwrap();
 
     assert_eq!(t1w.data.len(), 0x20, "t1w.data.len");
     assert_eq!(
@@ -404,7 +407,7 @@ fn test() {
         ], "t2i1.signature.content"
     );
 
-    assert!(t2.witness.is_none(), "t2.witness");
+    assert!(!t2.has_witness(), "t2.witness");
 
     let t2i1p: &OutPoint = &t2i1.previous;
 
@@ -433,4 +436,13 @@ fn test() {
         "t2o.script_pubkey.content"
     );
 
+    assert_eq!(t1.txid().to_string(), "4be105f158ea44aec57bf12c5817d073a712ab131df6f37786872cfc70734188", "t1.txid");
+    assert_eq!(t1.wtxid().to_string(), "4dec22f59b8c704faf29fd575e6895c1f6f88f0d45e3cfe1995998be36950fe7", "t1.wtxid");
+    assert_eq!(t2.txid().to_string(), "22dc883714a4536a3360e5ae311fae9fd59b3fc01614fb9a13ecb0fc84b70da1", "t2.txid");
+    assert_eq!(t2.wtxid(), t2.txid(), "t2.wtxid");
+    assert_eq!(b.hash().to_string(), "000000000000045e0b1660b6445b5e5c5ab63c9a4f956be7e1e69be04fa4497b", "b.hash");
+    assert_eq!(b.validate_merkle(), Ok(()), "b.validate_merkle");
+
+    let limit = Uint256::from_str("00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap();
+    assert_eq!(b.check_pow(&limit), Ok(()), "b.check_pow");
 }
@@ -0,0 +1,54 @@
+use crate::encode::error::Error;
+use crate::encode::encode::Decodable;
+use crate::block::transaction::Transaction;
+use crate::utils::hexdump;
+
+use std::io::Cursor;
+
+#[test]
+fn when_flag_and_empty_witnesses_then_fail_witness_empty() {
+
+    let dump = "
+00000000   01 00 00 00 00 01 01 00  00 00 00 00 00 00 00 00   ................
+00000010   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   ................
+00000020   00 00 00 00 00 00 00 00  00 00 00 00 ff ff ff ff   ................
+00000030   01 00 e1 f5 05 00 00 00  00 00 00 00 00 00 00 00   ................
+";
+
+    let hex: Vec<u8> = hexdump::decode(dump);
+    assert_eq!(hex.len(), 64);
+
+    let mut c = Cursor::new(&hex);
+    let result = Transaction::decode(&mut c);
+    assert!(result.is_err());
+
+    if let Err(e) = result {
+        assert_eq!(e, Error::TransactionWitnessEmpty);
+    } else {
+        panic!("should have failed");
+    }
+}
+
+#[test]
+fn when_marker_with_unknown_flag_then_fail_flag() {
+
+    let dump = "
+00000000   01 00 00 00 00 02 01 00  00 00 00 00 00 00 00 00   ................
+00000010   00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00   ................
+00000020   00 00 00 00 00 00 00 00  00 00 00 00 ff ff ff ff   ................
+00000030   01 00 e1 f5 05 00 00 00  00 00 00 00 00 00         ..............  
+";
+
+    let hex: Vec<u8> = hexdump::decode(dump);
+    assert_eq!(hex.len(), 62);
+
+    let mut c = Cursor::new(&hex);
+    let result = Transaction::decode(&mut c);
+    assert!(result.is_err());
+
+    if let Err(e) = result {
+        assert_eq!(e, Error::TransactionFlag);
+    } else {
+        panic!("should have failed");
+    }
+}
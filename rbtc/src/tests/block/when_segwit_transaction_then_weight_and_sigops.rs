@@ -0,0 +1,80 @@
+use crate::encode::error::Error;
+use crate::encode::encode::Decodable;
+use crate::block::opcode::Opcode;
+use crate::block::script::Script;
+use crate::block::transaction::Transaction;
+use crate::block::txout::TxOut;
+use crate::block::witness::Witness;
+
+use std::io::Cursor;
+
+fn transaction(data: &str) -> Transaction {
+    let bytes = hex::decode(data).unwrap();
+    let mut r = Cursor::new(&bytes);
+    Transaction::decode(&mut r).unwrap()
+}
+
+fn output(script_pubkey: &str) -> TxOut {
+    TxOut { amount: 0, script_pubkey: Script::from_asm(script_pubkey).unwrap() }
+}
+
+// https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json keyPathSpending
+const SIGNED_TRANSACTION : &str = "020000000001097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd990000000000fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a41842000000006b4830450221008f3b8f8f0537c420654d2283673a761b7ee2ea3c130753103e08ce79201cf32a022079e7ab904a1980ef1c5890b648c8783f4d10103dd62f740d13daa79e298d50c201210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd050000000000000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af10100000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b0141ed7c1647cb97379e76892be0cacff57ec4a7102aa24296ca39af7541246d8ff14d38958d4cc1e2e478e4d4a764bbfd835b16d4e314b72937b29833060b87276c030141052aedffc554b41f52b521071793a6b88d6dbca9dba94cf34c83696de0c1ec35ca9c5ed4ab28059bd606a4f3a657eec0bb96661d42921b5f50a95ad33675b54f83000141ff45f742a876139946a149ab4d9185574b98dc919d2eb6754f8abaa59d18b025637a3aa043b91817739554f4ed2026cf8022dbd83e351ce1fabc272841d2510a010140b4010dd48a617db09926f729e79c33ae0b4e94b79f04a1ae93ede6315eb3669de185a17d2b0ac9ee09fd4c64b678a0b61a0a86fa888a273c8511be83bfd6810f0247304402202b795e4de72646d76eab3f0ab27dfa30b810e856ff3a46c9a702df53bb0d8cc302203ccc4d822edab5f35caddb10af1be93583526ccfbade4b4ead350781e2f8adcd012102f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f90141a3785919a2ce3c4ce26f298c3d51619bc474ae24014bcdd31328cd8cfbab2eff3395fa0a16fe5f486d12f22a9cedded5ae74feb4bbe5351346508c5405bcfee0020141ea0c6ba90763c2d3a296ad82ba45881abb4f426b3f87af162dd24d5109edc1cdd11915095ba47c3a9963dc1e6c432939872bc49212fe34c632cd3ab9fed429c4820141bbc9584a11074e83bc8c6759ec55401f0ae7b03ef290c3139814f545b58a9f8127258000874f44bc46db7646322107d4d86aec8e73b8719a61fff761d75b5dd9810065cd1d";
+
+/// The outputs spent by the inputs: 7 taproot, a P2PKH and a P2WPKH
+const SPENT_OUTPUTS : [(&str, u64); 9] = [
+    ("512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343", 420000000),
+    ("5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3", 462000000),
+    ("76a914751e76e8199196d454941c45d1b3a323f1433bd688ac", 294000000),
+    ("5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e", 504000000),
+    ("512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605", 630000000),
+    ("00147dd65592d0ab2fe0d0257d571abf032cd9db93dc", 378000000),
+    ("512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831", 672000000),
+    ("5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5", 546000000),
+    ("512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220", 588000000),
+];
+
+#[test]
+fn test() {
+
+    let mut tx = transaction(SIGNED_TRANSACTION);
+    let spent : Vec<TxOut> = SPENT_OUTPUTS.iter().map(|(script_pubkey, amount)| TxOut {
+        amount: *amount,
+        script_pubkey: Script::new(hex::decode(script_pubkey).unwrap()),
+    }).collect();
+
+    assert_eq!(tx.total_size(), 1139);
+    assert_eq!(tx.stripped_size(), 561);
+    assert_eq!(tx.weight(), 2822);
+    assert_eq!(tx.vsize(), 706);
+    assert!(!tx.is_coinbase());
+
+    // a P2PKH output and an output script starting with OP_CHECKSIG
+    assert_eq!(tx.legacy_sigop_count(), 2);
+    assert_eq!(tx.p2sh_sigop_count(&spent), Ok(0));
+    // the P2WPKH input, the taproot ones are not counted
+    assert_eq!(tx.witness_sigop_count(&spent), Ok(1));
+    assert_eq!(tx.sigop_cost(&spent), Ok(9));
+    assert_eq!(tx.sigop_cost(&spent[1..]), Err(Error::SpentOutputsCount));
+
+    // the input 2 spending a P2SH 1 of 2 multisig
+    let key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let redeem_script = Script::from_asm(&format!("1 {0} {0} 2 OP_CHECKMULTISIG", key)).unwrap();
+    let mut p2sh_spent = spent.clone();
+    p2sh_spent[2] = output("OP_HASH160 89abcdefabbaabbaabbaabbaabbaabbaabbaabba OP_EQUAL");
+    tx.inputs[2].signature = Script::default();
+    tx.inputs[2].signature.push_opcode(Opcode::Op0);
+    tx.inputs[2].signature.push_data(&redeem_script.content);
+    assert_eq!(tx.p2sh_sigop_count(&p2sh_spent), Ok(2));
+    assert_eq!(tx.sigop_cost(&p2sh_spent), Ok(17));
+
+    // then a P2SH-P2WSH 2 of 3 multisig, counted once as witness sigops
+    let witness_script = Script::from_asm(&format!("2 {0} {0} {0} 3 OP_CHECKMULTISIG", key)).unwrap();
+    let p2wsh = Script::from_asm("0 89abcdefabbaabbaabbaabbaabbaabbaabbaabbaabbaabbaabbaabbaabbaabba").unwrap();
+    tx.inputs[2].signature = Script::default();
+    tx.inputs[2].signature.push_data(&p2wsh.content);
+    tx.inputs[2].witness = vec![Witness { data: vec![] }, Witness { data: witness_script.content }];
+    assert_eq!(tx.p2sh_sigop_count(&p2sh_spent), Ok(0));
+    assert_eq!(tx.witness_sigop_count(&p2sh_spent), Ok(4));
+    assert_eq!(tx.sigop_cost(&p2sh_spent), Ok(12));
+}
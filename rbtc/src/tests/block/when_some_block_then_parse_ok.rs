@@ -4,6 +4,9 @@ use crate::block::txin::TxIn;
 use crate::block::txout::TxOut;
 use crate::block::block::Block;
 use crate::utils::hexdump;
+use crate::utils::uint256::Uint256;
+
+use std::str::FromStr;
 
 #[test]
 fn test() {
@@ -60,30 +63,30 @@ fn test() {
 
     let b = result.ok().unwrap();
 
-    assert_eq!(b.version, 0x00000001, "b.version");
+    assert_eq!(b.header.version, 0x00000001, "b.header.version");
     assert_eq!(
-        b.previous,
+        b.header.previous,
         [
             0x4d, 0xdc, 0xcd, 0x54, 0x9d, 0x28, 0xf3, 0x85, 0xab, 0x45, 0x7e, 0x98, 0xd1, 0xb1,
             0x1c, 0xe8, 0x0b, 0xfe, 0xa2, 0xc5, 0xab, 0x93, 0x01, 0x5a, 0xde, 0x49, 0x73, 0xe4,
             0x00, 0x00, 0x00, 0x00,
         ],
-        "b.previous"
+        "b.header.previous"
     );
 
     assert_eq!(
-        b.merkleroot,
+        b.header.merkleroot,
         [
             0xbf, 0x44, 0x73, 0xe5, 0x37, 0x94, 0xbe, 0xae, 0x34, 0xe6, 0x4f, 0xcc, 0xc4, 0x71,
             0xda, 0xce, 0x6a, 0xe5, 0x44, 0x18, 0x08, 0x16, 0xf8, 0x95, 0x91, 0x89, 0x4e, 0x0f,
             0x41, 0x7a, 0x91, 0x4c,
         ],
-        "b.merkleroot"
+        "b.header.merkleroot"
     );
 
-    assert_eq!(b.time, 1231965655, "b.time");
-    assert_eq!(b.bits, 486604799, "b.bits");
-    assert_eq!(b.nonce, 2067413810, "b.nonce");
+    assert_eq!(b.header.time, 1231965655, "b.header.time");
+    assert_eq!(b.header.bits, 486604799, "b.header.bits");
+    assert_eq!(b.header.nonce, 2067413810, "b.header.nonce");
     assert_eq!(b.transactions.len(), 2, "b.transactions.len");
 
     let t1: &Transaction = b.transactions.get(0).unwrap();
@@ -109,7 +112,7 @@ fn test() {
         "t1i.signature.content"
     );
 
-    assert!(t1.witness.is_none(), "t1.witness");
+    assert!(!t1.has_witness(), "t1.witness");
 
     let t1ip: &OutPoint = &t1i.previous;
 
@@ -157,7 +160,7 @@ fn test() {
         ], "t2i1.signature.content"
     );
 
-    assert!(t2.witness.is_none(), "t2.witness");
+    assert!(!t2.has_witness(), "t2.witness");
 
     let t2i1p: &OutPoint = &t2i1.previous;
 
@@ -233,4 +236,11 @@ fn test() {
         "t2o.script_pubkey.content"
     );
 
+    assert_eq!(t1.txid().to_string(), "77dfc2fe598419b00641c296181a96cf16943697f573480b023b77cce82ada21", "t1.txid");
+    assert_eq!(t2.txid().to_string(), "a3b0e9e7cddbbe78270fa4182a7675ff00b92872d8df7d14265a2b1e379a9d33", "t2.txid");
+    assert_eq!(b.hash().to_string(), "00000000b0c5a240b2a61d2e75692224efd4cbecdf6eaf4cc2cf477ca7c270e7", "b.hash");
+    assert_eq!(b.validate_merkle(), Ok(()), "b.validate_merkle");
+
+    let limit = Uint256::from_str("00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap();
+    assert_eq!(b.check_pow(&limit), Ok(()), "b.check_pow");
 }
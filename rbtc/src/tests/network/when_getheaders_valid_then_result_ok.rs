@@ -25,7 +25,7 @@ fn test() {
 
 
     let data : Vec<u8> = hexdump::decode(dump);
-    let mut c = Cursor::new(data.as_ref());
+    let mut c = Cursor::new(&data);
     c.set_position(24); // move beyond network protocol headers
     let result = GetHeaders::decode(&mut c);
 
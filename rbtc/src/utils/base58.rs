@@ -0,0 +1,136 @@
+use crate::encode::error::Error;
+use crate::utils::sha256::Sha256;
+
+/// https://en.bitcoin.it/wiki/Base58Check_encoding
+///
+/// # Base58
+///
+/// Base 58 leaves out the characters looking alike: 0 (zero), O (capital o),
+/// I (capital i) and l (lower case L). Each leading zero byte is encoded as a '1'.
+///
+/// Base58Check appends the first 4 bytes of the double sha256 of the data before
+/// encoding, the version byte being the first byte of the data:
+/// ```
+/// +---------+----------------------------+----------+
+/// | version | payload                    | checksum |
+/// +---------+----------------------------+----------+
+/// | 1 byte  | 20 bytes for an address    | 4 bytes  |
+/// +---------+----------------------------+----------+
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/base58.cpp
+const ALPHABET : &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub fn encode(data: &[u8]) -> String {
+
+    let zeroes = data.iter().take_while(|b| **b == 0).count();
+
+    // log(256) / log(58), rounded up
+    let mut digits : Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for byte in &data[zeroes..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut result = String::with_capacity(zeroes + digits.len());
+    for _ in 0..zeroes {
+        result.push('1');
+    }
+    for digit in digits.iter().rev() {
+        result.push(ALPHABET[*digit as usize] as char);
+    }
+    result
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, Error> {
+
+    let zeroes = data.bytes().take_while(|c| *c == b'1').count();
+
+    // log(58) / log(256), rounded up
+    let mut bytes : Vec<u8> = Vec::with_capacity(data.len() * 733 / 1000 + 1);
+    for c in data.bytes().skip(zeroes) {
+        let mut carry = ALPHABET.iter().position(|a| *a == c).ok_or(Error::Base58Char)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut result = vec![0u8; zeroes];
+    result.extend(bytes.iter().rev());
+    Ok(result)
+}
+
+pub fn encode_check(data: &[u8]) -> String {
+
+    let checksum = Sha256::double(data);
+    let mut payload = data.to_vec();
+    payload.extend_from_slice(&checksum.hash[0..4]);
+    encode(&payload)
+}
+
+pub fn decode_check(data: &str) -> Result<Vec<u8>, Error> {
+
+    let mut payload = decode(data)?;
+    if payload.len() < 4 {
+        return Err(Error::Base58Checksum);
+    }
+
+    let checksum = payload.split_off(payload.len() - 4);
+    if Sha256::double(&payload).hash[0..4] != checksum[..] {
+        return Err(Error::Base58Checksum);
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::utils::base58;
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/data/base58_encode_decode.json
+    #[test]
+    fn when_core_vectors_then_same() {
+
+        let vectors : Vec<(String, String)> = serde_json::from_str(include_str!("../tests/data/base58_encode_decode.json")).unwrap();
+        assert!(!vectors.is_empty());
+        for (data, encoded) in vectors {
+            let data = hex::decode(&data).unwrap();
+            assert_eq!(base58::encode(&data), encoded);
+            assert_eq!(base58::decode(&encoded), Ok(data));
+        }
+    }
+
+    #[test]
+    fn when_invalid_char_then_error() {
+        assert_eq!(base58::decode("invalid"), Err(Error::Base58Char));
+        assert_eq!(base58::decode("0OIl"), Err(Error::Base58Char));
+        assert_eq!(base58::decode("3SEo3LWLoPntC"), Ok(hex::decode("bf4f89001e670274dd").unwrap()));
+    }
+
+    #[test]
+    fn when_check_then_checksum_verified() {
+
+        let data = hex::decode("0065a16059864a2fdbc7c99a4723a8395bc6f188eb").unwrap();
+        let encoded = base58::encode_check(&data);
+        assert_eq!(encoded, "1AGNa15ZQXAZUgFiqJ2i7Z2DPU2J6hW62i");
+        assert_eq!(base58::decode_check(&encoded), Ok(data));
+
+        assert_eq!(base58::decode_check("1AGNa15ZQXAZUgFiqJ2i7Z2DPU2J6hW62j"), Err(Error::Base58Checksum));
+        assert_eq!(base58::decode_check("1111"), Err(Error::Base58Checksum));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::encode::error::Error;
+
+/// https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki
+/// https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki
+///
+/// # Bech32 and Bech32m
+///
+/// A human readable part, the separator '1', then the data and a 6 characters
+/// checksum, 5 bits per character. Bech32m only differs by the checksum constant,
+/// it is used from witness version 1, Bech32 for witness version 0.
+///
+/// ```
+/// bc 1 q w508d6qejxtdg4y5r3zarvary0c5xw7k v8f3t4
+/// |  | | |                                |
+/// |  | | program, 8 to 5 bits            checksum
+/// |  | witness version
+/// |  separator
+/// human readable part
+/// ```
+///
+/// A string is at most 90 characters, and is either all lower case or all upper case.
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/bech32.cpp
+const CHARSET : &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const CHECKSUM_SIZE : usize = 6;
+
+const MAX_LENGTH : usize = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+fn polymod(values: &[u8]) -> u32 {
+
+    const GENERATOR : [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    let mut chk : u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ (*value as u32);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+
+    let mut result : Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    result.push(0);
+    result.extend(hrp.bytes().map(|c| c & 0x1f));
+    result
+}
+
+fn create_checksum(hrp: &str, data: &[u8], variant: Variant) -> Vec<u8> {
+
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0u8; CHECKSUM_SIZE]);
+
+    let polymod = polymod(&values) ^ variant.constant();
+    (0..CHECKSUM_SIZE)
+        .map(|i| ((polymod >> (5 * (5 - i))) & 0x1f) as u8)
+        .collect()
+}
+
+/// Encodes the 5 bits values of `data`, the human readable part must be lower case.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> String {
+
+    let checksum = create_checksum(hrp, data, variant);
+
+    let mut result = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_SIZE);
+    result.push_str(hrp);
+    result.push('1');
+    for value in data.iter().chain(checksum.iter()) {
+        result.push(CHARSET[*value as usize] as char);
+    }
+    result
+}
+
+/// Decodes a string into its lower case human readable part, the 5 bits values of
+/// its data without the checksum, and the variant its checksum matches.
+pub fn decode(bech: &str) -> Result<(String, Vec<u8>, Variant), Error> {
+
+    let bytes = bech.as_bytes();
+    if bytes.iter().any(|c| *c < 33 || *c > 126) {
+        return Err(Error::Bech32Char);
+    }
+    let lower = bytes.iter().any(|c| c.is_ascii_lowercase());
+    let upper = bytes.iter().any(|c| c.is_ascii_uppercase());
+    if lower && upper {
+        return Err(Error::Bech32Char);
+    }
+    if bytes.len() > MAX_LENGTH {
+        return Err(Error::Bech32Length);
+    }
+
+    let bech = bech.to_ascii_lowercase();
+    let position = match bech.rfind('1') {
+        Some(position) if position > 0 && position + CHECKSUM_SIZE < bech.len() => position,
+        _ => return Err(Error::Bech32Separator),
+    };
+
+    let hrp = &bech[..position];
+    let mut values : Vec<u8> = Vec::with_capacity(bech.len() - position - 1);
+    for c in bech[position + 1..].bytes() {
+        let value = CHARSET.iter().position(|a| *a == c).ok_or(Error::Bech32Data)?;
+        values.push(value as u8);
+    }
+
+    let mut check = hrp_expand(hrp);
+    check.extend_from_slice(&values);
+    let variant = match polymod(&check) {
+        c if c == Variant::Bech32.constant() => Variant::Bech32,
+        c if c == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => return Err(Error::Bech32Checksum),
+    };
+
+    values.truncate(values.len() - CHECKSUM_SIZE);
+    Ok((hrp.to_string(), values, variant))
+}
+
+/// Regroups `from` bits values into `to` bits values. When not padding, the
+/// remaining bits must be fewer than `from` and all zero.
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, Error> {
+
+    let mut acc : u32 = 0;
+    let mut bits : u32 = 0;
+    let max = (1u32 << to) - 1;
+    let mut result = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for value in data {
+        acc = (acc << from) | (*value as u32);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        return Err(Error::Bech32Padding);
+    }
+    Ok(result)
+}
+
+/// Encodes a segwit address, Bech32 for version 0 and Bech32m for the next ones.
+pub fn encode_segwit(hrp: &str, version: u8, program: &[u8]) -> String {
+
+    let variant = if version == 0 { Variant::Bech32 } else { Variant::Bech32m };
+
+    let mut data = vec![version];
+    // padding 8 bits to 5 bits never fails
+    data.extend(convert_bits(program, 8, 5, true).unwrap());
+    encode(hrp, &data, variant)
+}
+
+/// Decodes a segwit address of the `hrp` network into its witness version and program.
+pub fn decode_segwit(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), Error> {
+
+    let (decoded, data, variant) = decode(address)?;
+    if decoded != hrp {
+        return Err(Error::Bech32Hrp);
+    }
+    if data.is_empty() || data[0] > 16 {
+        return Err(Error::Bech32WitnessVersion);
+    }
+
+    let version = data[0];
+    let program = convert_bits(&data[1..], 5, 8, false)?;
+    if program.len() < 2 || program.len() > 40 || (version == 0 && program.len() != 20 && program.len() != 32) {
+        return Err(Error::Bech32WitnessProgram);
+    }
+
+    let expected = if version == 0 { Variant::Bech32 } else { Variant::Bech32m };
+    if variant != expected {
+        return Err(Error::Bech32Checksum);
+    }
+    Ok((version, program))
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::encode::error::Error;
+    use crate::utils::bech32;
+    use crate::utils::bech32::Variant;
+
+    fn recode(bech: &str, variant: Variant) {
+        let (hrp, data, decoded) = bech32::decode(bech).unwrap();
+        assert_eq!(decoded, variant);
+        assert_eq!(bech32::encode(&hrp, &data, variant), bech.to_ascii_lowercase());
+    }
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/bech32_tests.cpp
+    #[test]
+    fn when_bip173_valid_then_recode() {
+        recode("A12UEL5L", Variant::Bech32);
+        recode("a12uel5l", Variant::Bech32);
+        recode("an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs", Variant::Bech32);
+        recode("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", Variant::Bech32);
+        recode("11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j", Variant::Bech32);
+        recode("split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w", Variant::Bech32);
+        recode("?1ezyfcl", Variant::Bech32);
+    }
+
+    #[test]
+    fn when_bip350_valid_then_recode() {
+        recode("A1LQFN3A", Variant::Bech32m);
+        recode("a1lqfn3a", Variant::Bech32m);
+        recode("an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6", Variant::Bech32m);
+        recode("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx", Variant::Bech32m);
+        recode("11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8", Variant::Bech32m);
+        recode("split1checkupstagehandshakeupstreamerranterredcaperredlc445v", Variant::Bech32m);
+        recode("?1v759aa", Variant::Bech32m);
+    }
+
+    #[test]
+    fn when_bip173_invalid_then_error() {
+        for (bech, error) in [
+            (" 1nwldj5", Error::Bech32Char),
+            ("\u{7f}1axkwrx", Error::Bech32Char),
+            ("\u{80}1eym55h", Error::Bech32Char),
+            ("an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx", Error::Bech32Length),
+            ("pzry9x0s0muk", Error::Bech32Separator),
+            ("1pzry9x0s0muk", Error::Bech32Separator),
+            ("x1b4n0q5v", Error::Bech32Data),
+            ("li1dgmt3", Error::Bech32Separator),
+            ("de1lg7wt\u{ff}", Error::Bech32Char),
+            ("A1G7SGD8", Error::Bech32Checksum),
+            ("10a06t8", Error::Bech32Separator),
+            ("1qzzfhee", Error::Bech32Separator),
+            ("a12UEL5L", Error::Bech32Char),
+            ("A12uEL5L", Error::Bech32Char),
+            ("abcdef1qpzrz9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", Error::Bech32Checksum),
+            ("test1zg69w7y6hn0aqy352euf40x77qddq3dc", Error::Bech32Checksum),
+        ] {
+            assert_eq!(bech32::decode(bech), Err(error), "{}", bech);
+        }
+    }
+
+    #[test]
+    fn when_bip350_invalid_then_error() {
+        for (bech, error) in [
+            (" 1xj0phk", Error::Bech32Char),
+            ("\u{7f}1g6xzxy", Error::Bech32Char),
+            ("\u{80}1vctc34", Error::Bech32Char),
+            ("an84characterslonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11d6pts4", Error::Bech32Length),
+            ("qyrz8wqd2c9m", Error::Bech32Separator),
+            ("1qyrz8wqd2c9m", Error::Bech32Separator),
+            ("y1b0jsk6g", Error::Bech32Data),
+            ("lt1igcx5c0", Error::Bech32Data),
+            ("in1muywd", Error::Bech32Separator),
+            ("mm1crxm3i", Error::Bech32Data),
+            ("au1s5cgom", Error::Bech32Data),
+            ("M1VUXWEZ", Error::Bech32Checksum),
+            ("16plkw9", Error::Bech32Separator),
+            ("1p2gdwpf", Error::Bech32Separator),
+            ("abcdef1l7aum6echk45nj2s0wdvt2fg8x9yrzpqzd3ryx", Error::Bech32Checksum),
+            ("test1zg69v7y60n00qy352euf40x77qcusag6", Error::Bech32Checksum),
+        ] {
+            assert_eq!(bech32::decode(bech), Err(error), "{}", bech);
+        }
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki#test-vectors-for-v0-v16-native-segregated-witness-addresses
+    #[test]
+    fn when_valid_segwit_address_then_script() {
+        for (address, script) in [
+            ("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            ("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
+            ("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y", "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6"),
+            ("BC1SW50QGDZ25J", "6002751e"),
+            ("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", "5210751e76e8199196d454941c45d1b3a323"),
+            ("tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy", "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
+            ("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c", "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
+            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+        ] {
+            let hrp = address[..2].to_ascii_lowercase();
+            let (version, program) = bech32::decode_segwit(&hrp, address).unwrap();
+
+            let script = hex::decode(script).unwrap();
+            let expected_version = if script[0] == 0 { 0 } else { script[0] - 0x50 };
+            assert_eq!(version, expected_version);
+            assert_eq!(program, &script[2..]);
+            assert_eq!(bech32::encode_segwit(&hrp, version, &program), address.to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn when_invalid_segwit_address_then_error() {
+        for (address, error) in [
+            // BIP173
+            ("tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty", Error::Bech32Hrp),
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5", Error::Bech32Checksum),
+            ("BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2", Error::Bech32WitnessVersion),
+            ("bc1rw5uspcuh", Error::Bech32WitnessProgram),
+            ("bc10w508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kw5rljs90", Error::Bech32WitnessProgram),
+            ("BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P", Error::Bech32WitnessProgram),
+            ("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7", Error::Bech32Char),
+            ("bc1zw508d6qejxtdg4y5r3zarvaryvqyzf3du", Error::Bech32Padding),
+            ("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv", Error::Bech32Padding),
+            ("bc1gmk9yu", Error::Bech32WitnessVersion),
+            // BIP350
+            ("tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut", Error::Bech32Hrp),
+            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd", Error::Bech32Checksum),
+            ("tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf", Error::Bech32Checksum),
+            ("BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL", Error::Bech32Checksum),
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh", Error::Bech32Checksum),
+            ("tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47", Error::Bech32Checksum),
+            ("bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4", Error::Bech32Data),
+            ("BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R", Error::Bech32WitnessVersion),
+            ("bc1pw5dgrnzv", Error::Bech32WitnessProgram),
+            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav", Error::Bech32WitnessProgram),
+            ("BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P", Error::Bech32WitnessProgram),
+            ("tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq", Error::Bech32Char),
+            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf", Error::Bech32Padding),
+            ("tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j", Error::Bech32Padding),
+            ("bc1gmk9yu", Error::Bech32WitnessVersion),
+        ] {
+            let hrp = address[..2].to_ascii_lowercase();
+            let hrp = if hrp == "tc" { "tb".to_string() } else { hrp };
+            assert_eq!(bech32::decode_segwit(&hrp, address), Err(error), "{}", address);
+        }
+    }
+}
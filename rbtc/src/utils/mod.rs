@@ -1,2 +1,5 @@
 pub mod sha256;
-pub mod hexdump;
\ No newline at end of file
+pub mod uint256;
+pub mod hexdump;
+pub mod base58;
+pub mod bech32;
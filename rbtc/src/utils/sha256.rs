@@ -2,24 +2,99 @@ use crate::encode::error::Error;
 use crate::encode::encode::{Encodable, Decodable};
 use crate::encode::varint::VarInt;
 
-use std::io::{Read, Write, Cursor};
+use sha2::Digest;
+
+use std::fmt;
+use std::str::FromStr;
+use std::io::{Read, Write};
 use byteorder::{LittleEndian, BigEndian, ReadBytesExt, WriteBytesExt};
 
-#[derive(Debug)]
+/// A 32 bytes hash, stored in the byte order it is serialized and computed in.
+/// 
+/// Bitcoin displays transaction ids and block hashes as the hexadecimal of 
+/// the reversed bytes, which is what `Display` and `FromStr` use:
+/// ```
+/// genesis hash bytes : 6f e2 8c 0a b6 f1 b3 72 ... 00 00 00 00
+/// genesis hash string: 000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f
+/// ```
+/// 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Sha256 {
     pub hash: [u8; 32],
 }
 
+impl Sha256 {
+
+    pub fn new(hash: [u8; 32]) -> Sha256 {
+        Sha256 {
+            hash: hash
+        }
+    }
+
+    /// Single sha256 of data
+    pub fn single(data: &[u8]) -> Sha256 {
+        let mut hasher = sha2::Sha256::default();
+        hasher.input(data);
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(hasher.result().as_slice());
+        Sha256::new(hash)
+    }
+
+    /// sha256(sha256(data)), the hash used for txids, block hashes and 
+    /// message checksums
+    pub fn double(data: &[u8]) -> Sha256 {
+        let first = Sha256::single(data);
+        Sha256::single(&first.hash)
+    }
+
+    /// sha256(sha256(tag) || sha256(tag) || data), BIP340, the tag making the
+    /// hashes of different uses never collide
+    pub fn tagged(tag: &str, data: &[u8]) -> Sha256 {
+        let tag = Sha256::single(tag.as_bytes());
+        let mut hasher = sha2::Sha256::default();
+        hasher.input(tag.hash);
+        hasher.input(tag.hash);
+        hasher.input(data);
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(hasher.result().as_slice());
+        Sha256::new(hash)
+    }
+}
+
+impl fmt::Display for Sha256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.hash.iter().rev() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Sha256 {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Sha256, Error> {
+        let bytes = hex::decode(s).map_err(|e| Error::Sha256Parse.caused_by(e))?;
+        if bytes.len() != 32 {
+            return Err(Error::Sha256Parse);
+        }
+        let mut hash = [0u8; 32];
+        for (i, b) in bytes.iter().rev().enumerate() {
+            hash[i] = *b;
+        }
+        Ok(Sha256::new(hash))
+    }
+}
+
 impl Encodable for Sha256 {
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
-        w.write_all(&self.hash).map_err(|_| Error::WriteSha256)?;
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(&self.hash).map_err(|e| Error::WriteSha256.caused_by(e))?;
         Ok(())
     }
 }
 
 impl Decodable for Sha256 {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Sha256, Error> {
-        let hash = <[u8; 32]>::decode(r).map_err(|_| Error::ReadSha256)?;
+    fn decode<R: Read>(r: &mut R) -> Result<Sha256, Error> {
+        let hash = <[u8; 32]>::decode(r).map_err(|e| Error::ReadSha256.caused_by(e))?;
         let sha = Sha256 {
             hash: hash
         };
@@ -28,10 +103,10 @@ impl Decodable for Sha256 {
 }
 
 impl Decodable for Vec<Sha256> {
-    fn decode(r: &mut Cursor<&Vec<u8>>) -> Result<Vec<Sha256>, Error> {
+    fn decode<R: Read>(r: &mut R) -> Result<Vec<Sha256>, Error> {
         
         let mut result : Vec<Sha256> = Vec::new();
-        let count = VarInt::decode(r).map_err(|_| Error::Sha256Count)?;
+        let count = VarInt::decode(r).map_err(|e| Error::Sha256Count.caused_by(e))?;
 
         for _ in 0..count.0 {
             let locator = Sha256::decode(r)?;
@@ -44,14 +119,49 @@ impl Decodable for Vec<Sha256> {
 
 impl Encodable for Vec<Sha256> {
 
-    fn encode(&self, w: &mut Vec<u8>) -> Result<(), Error> {
+    fn encode<W: Write>(&self, w: &mut W) -> Result<(), Error> {
     
         let varint = VarInt::new(self.len() as u64);
-        varint.encode(w).map_err(|_| Error::Sha256Count)?;
+        varint.encode(w).map_err(|e| Error::Sha256Count.caused_by(e))?;
         for sha in self {
             sha.encode(w)?;
         };
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::utils::sha256::Sha256;
+
+    #[test]
+    fn when_double_empty_then_known_hash() {
+        let result = Sha256::double(&[]);
+        assert_eq!(result.hash[0..4], [0x5d, 0xf6, 0xe0, 0xe2]);
+        assert_eq!(result.to_string(), "56944c5d3f98413ef45cf54545538103cc9f298e0575820ad3591376e2e0f65d");
+    }
+
+    #[test]
+    fn when_tagged_then_tag_hashed_twice() {
+        let result = Sha256::tagged("BIP0340/challenge", &[]);
+        assert_eq!(hex::encode(result.hash), "c216d352f5818b7b4beacd4ae0a26fe888080823d2a598856661bcd54f1b3713");
+        assert_ne!(Sha256::tagged("TapLeaf", &[]), Sha256::tagged("TapBranch", &[]));
+    }
+
+    #[test]
+    fn when_from_str_then_to_string_same() {
+        let s = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let result : Sha256 = s.parse().unwrap();
+        assert_eq!(result.hash[0], 0x6f);
+        assert_eq!(result.hash[31], 0x00);
+        assert_eq!(result.to_string(), s);
+    }
+
+    #[test]
+    fn when_from_str_invalid_then_fail() {
+        assert!("00".parse::<Sha256>().is_err());
+        assert!("zz".parse::<Sha256>().is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::encode::error::Error;
+use crate::utils::sha256::Sha256;
+
+use std::fmt;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::ops::{Add, Sub, Not, Shl, Shr, Mul, Div};
+
+/// An unsigned 256 bits integer, used for proof of work targets and chain work.
+///
+/// Stored as four 64 bits limbs, least significant first. Arithmetic wraps on
+/// overflow, the same way Core's `arith_uint256` does.
+///
+/// ## Compact format
+///
+/// The `bits` field of a block header is a floating point encoding of a target,
+/// one byte of exponent (the size in bytes) followed by a 23 bits mantissa and a sign bit:
+/// ```
+/// +------+------+----------------------+
+/// | size | sign | mantissa             |
+/// +------+------+----------------------+
+/// | 0x1d | 0    | 0x00ffff             |
+/// +------+------+----------------------+
+/// target = mantissa * 256^(size - 3)
+///        = 0x00000000ffff0000000000000000000000000000000000000000000000000000
+/// ```
+///
+// https://github.com/bitcoin/bitcoin/blob/master/src/arith_uint256.cpp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Uint256(pub [u64; 4]);
+
+impl Uint256 {
+
+    pub fn zero() -> Uint256 {
+        Uint256([0; 4])
+    }
+
+    pub fn from_u64(value: u64) -> Uint256 {
+        Uint256([value, 0, 0, 0])
+    }
+
+    /// Reads 32 little endian bytes, the order hashes are computed in.
+    pub fn from_le_bytes(bytes: &[u8; 32]) -> Uint256 {
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate() {
+            let mut buffer = [0u8; 8];
+            buffer.copy_from_slice(&bytes[i * 8..i * 8 + 8]);
+            *limb = u64::from_le_bytes(buffer);
+        }
+        Uint256(result)
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            result[i * 8..i * 8 + 8].copy_from_slice(&limb.to_le_bytes());
+        }
+        result
+    }
+
+    /// A hash read as a number, so it can be compared with a target.
+    pub fn from_hash(hash: &Sha256) -> Uint256 {
+        Uint256::from_le_bytes(&hash.hash)
+    }
+
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|limb| *limb == 0)
+    }
+
+    /// Position of the highest bit set plus one, 0 for zero.
+    pub fn bits(&self) -> u32 {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return 64 * i as u32 + 64 - self.0[i].leading_zeros();
+            }
+        }
+        0
+    }
+
+    /// Decodes a compact target, returning the value along with the negative
+    /// and overflow flags. Both flags make the target invalid for proof of work.
+    pub fn from_compact(compact: u32) -> (Uint256, bool, bool) {
+
+        let size = compact >> 24;
+        let mut word = compact & 0x007fffff;
+        let result = if size <= 3 {
+            word >>= 8 * (3 - size);
+            Uint256::from_u64(word as u64)
+        } else {
+            Uint256::from_u64(word as u64) << (8 * (size - 3))
+        };
+
+        let negative = word != 0 && (compact & 0x00800000) != 0;
+        let overflow = word != 0 && (size > 34 || (word > 0xff && size > 33) || (word > 0xffff && size > 32));
+
+        (result, negative, overflow)
+    }
+
+    /// Encodes the value in the compact format, rounding down to the 23 bits mantissa.
+    pub fn to_compact(&self, negative: bool) -> u32 {
+
+        let mut size = self.bits().div_ceil(8);
+        let mut compact = if size <= 3 {
+            (self.low_u64() << (8 * (3 - size))) as u32
+        } else {
+            (*self >> (8 * (size - 3))).low_u64() as u32
+        };
+
+        // the sign bit is set, move the mantissa one byte down
+        if compact & 0x00800000 != 0 {
+            compact >>= 8;
+            size += 1;
+        }
+        compact |= size << 24;
+        if negative && compact & 0x007fffff != 0 {
+            compact |= 0x00800000;
+        }
+        compact
+    }
+
+    fn bit(&self, index: u32) -> bool {
+        (self.0[(index / 64) as usize] >> (index % 64)) & 1 == 1
+    }
+}
+
+impl Ord for Uint256 {
+    fn cmp(&self, other: &Uint256) -> Ordering {
+        for i in (0..4).rev() {
+            match self.0[i].cmp(&other.0[i]) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for Uint256 {
+    fn partial_cmp(&self, other: &Uint256) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Uint256 {
+    type Output = Uint256;
+    fn add(self, other: Uint256) -> Uint256 {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        Uint256(result)
+    }
+}
+
+impl Sub for Uint256 {
+    type Output = Uint256;
+    fn sub(self, other: Uint256) -> Uint256 {
+        // two's complement, wraps like the addition
+        self + !other + Uint256::from_u64(1)
+    }
+}
+
+impl Not for Uint256 {
+    type Output = Uint256;
+    fn not(self) -> Uint256 {
+        Uint256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl Shl<u32> for Uint256 {
+    type Output = Uint256;
+    fn shl(self, shift: u32) -> Uint256 {
+        let mut result = [0u64; 4];
+        let limbs = (shift / 64) as usize;
+        let bits = shift % 64;
+        for i in 0..4 {
+            if i + limbs >= 4 {
+                break;
+            }
+            result[i + limbs] |= self.0[i] << bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                result[i + limbs + 1] |= self.0[i] >> (64 - bits);
+            }
+        }
+        Uint256(result)
+    }
+}
+
+impl Shr<u32> for Uint256 {
+    type Output = Uint256;
+    fn shr(self, shift: u32) -> Uint256 {
+        let mut result = [0u64; 4];
+        let limbs = (shift / 64) as usize;
+        let bits = shift % 64;
+        for i in limbs..4 {
+            result[i - limbs] |= self.0[i] >> bits;
+            if bits > 0 && i > limbs {
+                result[i - limbs - 1] |= self.0[i] << (64 - bits);
+            }
+        }
+        Uint256(result)
+    }
+}
+
+impl Mul<u64> for Uint256 {
+    type Output = Uint256;
+    fn mul(self, other: u64) -> Uint256 {
+        let mut result = [0u64; 4];
+        let mut carry : u128 = 0;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let product = self.0[i] as u128 * other as u128 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+        Uint256(result)
+    }
+}
+
+impl Div for Uint256 {
+    type Output = Uint256;
+    /// Long division, one bit at a time. Panics on division by zero.
+    fn div(self, other: Uint256) -> Uint256 {
+        if other.is_zero() {
+            panic!("Uint256 division by zero");
+        }
+        let mut quotient = Uint256::zero();
+        let mut remainder = Uint256::zero();
+        for i in (0..self.bits()).rev() {
+            remainder = remainder << 1;
+            if self.bit(i) {
+                remainder.0[0] |= 1;
+            }
+            if remainder >= other {
+                remainder = remainder - other;
+                quotient.0[(i / 64) as usize] |= 1 << (i % 64);
+            }
+        }
+        quotient
+    }
+}
+
+impl fmt::Display for Uint256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for limb in self.0.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Uint256 {
+    type Err = Error;
+    /// Parses the big endian hexadecimal, as displayed.
+    fn from_str(s: &str) -> Result<Uint256, Error> {
+        let bytes = hex::decode(s).map_err(|e| Error::Uint256Parse.caused_by(e))?;
+        if bytes.len() != 32 {
+            return Err(Error::Uint256Parse);
+        }
+        let mut le = [0u8; 32];
+        for (i, b) in bytes.iter().rev().enumerate() {
+            le[i] = *b;
+        }
+        Ok(Uint256::from_le_bytes(&le))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::utils::uint256::Uint256;
+    use crate::utils::sha256::Sha256;
+    use crate::encode::error::Error;
+
+    use std::str::FromStr;
+
+    // https://github.com/bitcoin/bitcoin/blob/master/src/test/arith_uint256_tests.cpp
+    #[test]
+    fn when_from_compact_then_same_as_core() {
+
+        let vectors : Vec<(u32, u64, u32, bool)> = vec![
+            (0x00000000, 0, 0, false),
+            (0x00123456, 0, 0, false),
+            (0x01003456, 0, 0, false),
+            (0x02000056, 0, 0, false),
+            (0x03000000, 0, 0, false),
+            (0x04000000, 0, 0, false),
+            (0x00923456, 0, 0, false),
+            (0x01803456, 0, 0, false),
+            (0x02800056, 0, 0, false),
+            (0x03800000, 0, 0, false),
+            (0x04800000, 0, 0, false),
+            (0x01123456, 0x12, 0x01120000, false),
+            (0x01fedcba, 0x7e, 0x01fe0000, true),
+            (0x02123456, 0x1234, 0x02123400, false),
+            (0x03123456, 0x123456, 0x03123456, false),
+            (0x04123456, 0x12345600, 0x04123456, false),
+            (0x04923456, 0x12345600, 0x04923456, true),
+            (0x05009234, 0x92340000, 0x05009234, false),
+        ];
+
+        for (compact, value, expected, negative) in vectors {
+            let (result, n, overflow) = Uint256::from_compact(compact);
+            assert_eq!(result, Uint256::from_u64(value), "{:08x} value", compact);
+            assert_eq!(n, negative, "{:08x} negative", compact);
+            assert!(!overflow, "{:08x} overflow", compact);
+            assert_eq!(result.to_compact(n), expected, "{:08x} to_compact", compact);
+        }
+    }
+
+    #[test]
+    fn when_from_compact_large_then_shifted() {
+        let (result, negative, overflow) = Uint256::from_compact(0x20123456);
+        assert_eq!(result.to_string(), "1234560000000000000000000000000000000000000000000000000000000000");
+        assert!(!negative);
+        assert!(!overflow);
+        assert_eq!(result.to_compact(false), 0x20123456);
+    }
+
+    #[test]
+    fn when_from_compact_too_large_then_overflow() {
+        let (_, negative, overflow) = Uint256::from_compact(0xff123456);
+        assert!(!negative);
+        assert!(overflow);
+    }
+
+    #[test]
+    fn when_genesis_bits_then_target() {
+        let (target, _, _) = Uint256::from_compact(0x1d00ffff);
+        assert_eq!(target.to_string(), "00000000ffff0000000000000000000000000000000000000000000000000000");
+        assert_eq!(target.to_compact(false), 0x1d00ffff);
+    }
+
+    #[test]
+    fn when_arithmetic_then_wraps() {
+        let max = !Uint256::zero();
+        assert_eq!(max + Uint256::from_u64(1), Uint256::zero());
+        assert_eq!(Uint256::zero() - Uint256::from_u64(1), max);
+        assert_eq!(Uint256::from_u64(1) << 255 >> 255, Uint256::from_u64(1));
+        assert_eq!(Uint256::from_u64(1) << 256, Uint256::zero());
+        assert_eq!((Uint256::from_u64(u64::MAX) * 2).0, [u64::MAX - 1, 1, 0, 0]);
+        assert_eq!(max / max, Uint256::from_u64(1));
+        assert_eq!((Uint256::from_u64(1) << 200) / (Uint256::from_u64(1) << 100), Uint256::from_u64(1) << 100);
+        assert_eq!(Uint256::from_u64(1000) / Uint256::from_u64(7), Uint256::from_u64(142));
+    }
+
+    #[test]
+    fn when_hash_then_compare_as_number() {
+        let genesis = Sha256::from_str("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+        let value = Uint256::from_hash(&genesis);
+        assert_eq!(value.to_string(), genesis.to_string());
+        assert_eq!(value.to_le_bytes(), genesis.hash);
+        assert!(value < Uint256::from_compact(0x1d00ffff).0);
+    }
+
+    #[test]
+    fn when_parse_invalid_then_error() {
+        assert_eq!(Uint256::from_str("00"), Err(Error::Uint256Parse));
+    }
+}
@@ -12,6 +12,8 @@ pub mod walker;
 pub mod program;
 pub mod message;
 
+use rbtc::chain::chainparams::ChainParams;
+
 use std::path::Path;
 
 fn main() {
@@ -20,16 +22,12 @@ fn main() {
 
     info!("rbtc_nodewalker 0.4.0 (q)");
 
+    let params = ChainParams::mainnet();
     let config = config::Config {
-        dns_seeds: vec![
-            String::from("seed.bitcoin.sipa.be"),
-            String::from("dnsseed.bluematt.me"),
-            String::from("dnsseed.bitcoin.dashjr.org"),
-            String::from("seed.bitcoinstats.com"),
-            String::from("seed.bitcoin.jonasschnelli.ch"),
-            String::from("seed.btc.petertodd.org"),
-            String::from("seed.bitcoin.sprovoost.nl"),
-        ],
+        dns_seeds: params.dns_seeds
+            .iter()
+            .map(|seed| String::from(*seed))
+            .collect(),
         sqlite_path: Path::new("./nodes.sqlite"),
     };
 
@@ -37,6 +35,7 @@ fn main() {
     let provider = provider::NodeProvider::new(&config.sqlite_path).unwrap();
 
     let mut program = program::Program::new(
+        params,
         resolver,
         provider
     );
@@ -4,7 +4,8 @@ use rbtc::network::verack::VerAck;
 use rbtc::network::getaddr::GetAddr;
 use rbtc::network::version::Service;
 use rbtc::network::message::Payload;
-use rbtc::network::message::{Message, Magic};
+use rbtc::network::message::Message;
+use rbtc::chain::chainparams::ChainParams;
 
 use rand::Rng;
 use std::net::{IpAddr};
@@ -13,7 +14,7 @@ pub struct MessageProvider {}
 
 impl MessageProvider {
 
-    pub fn version() -> Vec<Message> {
+    pub fn version(params: &ChainParams) -> Vec<Message> {
 
         let now = chrono::Local::now();
         let mut rng = rand::thread_rng();
@@ -42,17 +43,17 @@ impl MessageProvider {
         let version = Payload::Version(version);
         vec![
             Message {
-                magic: Magic::MainNet,
+                magic: params.magic,
                 payload: version
             }
         ]
     }
 
-    pub fn getaddr() -> Vec<Message> {
+    pub fn getaddr(params: &ChainParams) -> Vec<Message> {
 
         vec![
             Message {
-                magic: Magic::MainNet,
+                magic: params.magic,
                 payload: Payload::GetAddr(GetAddr {
 
                 })
@@ -60,10 +61,10 @@ impl MessageProvider {
         ]
     }
 
-    pub fn verack() -> Vec<Message> {
+    pub fn verack(params: &ChainParams) -> Vec<Message> {
         vec![
             Message {
-                magic: Magic::MainNet,
+                magic: params.magic,
                 payload: Payload::VerAck(VerAck {
                     
                 })
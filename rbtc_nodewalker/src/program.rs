@@ -7,6 +7,8 @@ use crate::walker::walker::WalkResult;
 use crate::walker::fsm::WalkerFsmEvents;
 use crate::node;
 
+use rbtc::chain::chainparams::ChainParams;
+
 use rayon::prelude::*;
 
 use std::{thread, time};
@@ -17,6 +19,7 @@ use std::sync::mpsc::Receiver;
 
 
 pub struct Program {
+    params: ChainParams,
     resolver: resolver::Resolver,
     provider: provider::NodeProvider,
 }
@@ -29,6 +32,7 @@ pub struct Comm {
 impl Program {
 
     pub fn new(
+        params: ChainParams,
         resolver: resolver::Resolver,
         provider: provider::NodeProvider,
     ) -> Program {
@@ -36,6 +40,7 @@ impl Program {
         trace!("new");
 
         Program {
+            params: params,
             resolver: resolver,
             provider: provider,
         }
@@ -96,6 +101,7 @@ impl Program {
         let nodes = self.provider.ten()
             .unwrap();
 
+        let params = &self.params;
         let comms : Vec<Comm> = nodes
             .into_iter()
             .map(|node| Comm {
@@ -115,7 +121,7 @@ impl Program {
                 info!("walk [id: {}]", id);
                 info!("walk [src: {}]", src);
 
-                let mut walker = walker::NodeWalker::new(id, &src);
+                let mut walker = walker::NodeWalker::new(id, &src, params.clone());
                 walker.run();
 
                 let result = walker.result();
@@ -1,18 +1,22 @@
 use crate::message::MessageProvider;
 use crate::walker::result::*;
 
+use rbtc::chain::chainparams::ChainParams;
 use rbtc::network::message::Message;
 use rbtc::network::message::Payload;
-use rbtc::encode::error::Error;
-use rbtc::encode::encode::{Encodable, Decodable};
+use rbtc::network::codec::MessageCodec;
+use rbtc::encode::encode::Encodable;
 
 use std::net::{TcpStream, SocketAddr};
 use std::io::prelude::*;
-use std::io::{Cursor};
+
+use bytes::BytesMut;
+use tokio::codec::Decoder;
 
 pub struct NodeWalker {
     id: u32,
     node_ip_port: String,
+    params: ChainParams,
     
     connect_retry: u8,
     getaddr_retry: u8,
@@ -20,7 +24,8 @@ pub struct NodeWalker {
     addr: Option<SocketAddr>,
     ips: Vec<String>,
     stream: Option<TcpStream>,
-    response: Vec<u8>,
+    response: BytesMut,
+    codec: MessageCodec,
     messages: Vec<Message>,
 
     result: Option<EndResult>,
@@ -35,10 +40,11 @@ pub struct WalkResult {
 
 impl NodeWalker {
 
-    pub fn new(id: u32, nodeip: &String) -> NodeWalker {
+    pub fn new(id: u32, nodeip: &String, params: ChainParams) -> NodeWalker {
 
         let node_ip_port = nodeip.clone();
-        let response = Vec::new();
+        let response = BytesMut::new();
+        let codec = MessageCodec::new(params.magic);
         let messages = Vec::new();
         let ips: Vec<String>= Vec::new();
 
@@ -47,10 +53,12 @@ impl NodeWalker {
             connect_retry: 0,
             getaddr_retry: 0,
             node_ip_port: node_ip_port,
+            params: params,
             addr: None,
             ips: ips,
             stream: None,
             response: response,
+            codec: codec,
             messages: messages,
             result: None,
         }
@@ -115,7 +123,7 @@ impl NodeWalker {
             return InitResult::Succeed;
         }
         
-        node_ip_port.push_str(":8333");
+        node_ip_port.push_str(&format!(":{}", self.params.default_port));
         match node_ip_port.parse() {
             Ok(addr) => {
                 self.addr = Some(addr);
@@ -166,7 +174,7 @@ impl NodeWalker {
 
         trace!("send_version");
         
-        let messages = MessageProvider::version();
+        let messages = MessageProvider::version(&self.params);
         match self.send(messages) {
             SendResult::Succeed => SendMessageResult::Succeed,
             _ => {
@@ -260,31 +268,26 @@ impl NodeWalker {
 
         trace!("decode");
 
-        let response = &self.response;
-        let result = &mut self.messages;
-        let mut cursor = Cursor::new(response);
-
-        match <Vec<Message>>::decode(&mut cursor) {
-
-            Ok(mut messages) => {
-                result.append(&mut messages);
-                &mut self.response.clear();
-                DecodeResult::Succeed
-            },
-
-            Err(err) => {
-
-                debug!("decode [err: {:?}]", err);
-                match err {
-                    Error::PayloadCommandString => DecodeResult::NeedMoreData,
-                    Error::PayloadLen => DecodeResult::NeedMoreData,
-                    Error::PayloadTooSmall => DecodeResult::NeedMoreData,
-                    Error::PayloadChecksum => DecodeResult::NeedMoreData,
-                    Error::PayloadData => DecodeResult::NeedMoreData,
-                    _ => DecodeResult::DecodeFailed,
+        let mut decoded = 0;
+        loop {
+            match self.codec.decode(&mut self.response) {
+                Ok(Some(message)) => {
+                    self.messages.push(message);
+                    decoded = decoded + 1;
+                },
+                Ok(None) => break,
+                Err(err) => {
+                    debug!("decode [err: {:?}]", err);
+                    return DecodeResult::DecodeFailed;
                 }
             }
         }
+
+        debug!("decode [decoded: {}]", decoded);
+        if decoded == 0 {
+            return DecodeResult::NeedMoreData;
+        }
+        DecodeResult::Succeed
     }
 
     fn receive_decode_loop(&mut self) {
@@ -357,9 +360,7 @@ impl NodeWalker {
                 return ReceiveResult::ReadEmpty;
             }
 
-            let mut temp = buffer.to_vec();
-            temp.truncate(_read);
-            response.append(&mut temp);
+            response.extend_from_slice(&buffer[.._read]);
 
             if _read == buffer.len() {
                 continue;
@@ -374,7 +375,7 @@ impl NodeWalker {
 
         trace!("send_verack");
 
-        let messages = MessageProvider::verack();
+        let messages = MessageProvider::verack(&self.params);
         match self.send(messages) {
             SendResult::Succeed => SendMessageResult::Succeed,
             _ => SendMessageResult::Failed
@@ -385,7 +386,7 @@ impl NodeWalker {
 
         trace!("send_getaddr");
 
-        let messages = MessageProvider::getaddr();
+        let messages = MessageProvider::getaddr(&self.params);
         match self.send(messages) {
             SendResult::Succeed => SendMessageResult::Succeed,
             _ => SendMessageResult::Failed